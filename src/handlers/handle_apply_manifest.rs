@@ -0,0 +1,499 @@
+use std::{collections::BTreeMap, io::IsTerminal, path::Path};
+
+use crossterm::style::Stylize;
+use serde::Serialize;
+
+use crate::{
+    handlers::{
+        handle_create_service::{
+            get_interpreter, get_service_user, new_service_unit, render_service_unit,
+        },
+        handle_delete_service::handle_delete_service,
+        handle_disable_service::handle_disable_service,
+        handle_enable_service::handle_enable_service,
+        handle_restart_service::handle_restart_service,
+        handle_show_status::handle_show_status,
+        handle_start_service::handle_start_service,
+        handle_stop_service::handle_stop_service,
+    },
+    utils::{
+        find_binary_path::find_interpreter,
+        history::record_change,
+        import::{print_unsupported, ImportedServices},
+        manifest::{load_manifest, ServiceManifest},
+        output::{
+            is_table, print_document, print_message, print_result, print_unified_diff,
+            unified_diff, ActionResult,
+        },
+        scope::{connect, get_unit_directory, is_user},
+        service_actions::{daemon_reload, DEFAULT_JOB_TIMEOUT},
+        service_names::{
+            get_full_service_name, get_service_file_path, get_servicer_services,
+            get_short_service_name, validate_service_name,
+        },
+        systemd::{get_active_state, get_unit_file_state, is_running, ManagerProxy},
+        unit_file::quote_word,
+    },
+};
+
+/// A change needed to converge the host, in the order changes are made
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+enum PlanAction {
+    Delete,
+    Create,
+    Update,
+    Disable,
+    Enable,
+    Stop,
+    Restart,
+    Start,
+}
+
+impl PlanAction {
+    /// The action as shown in the plan, eg. `create`
+    fn as_str(self) -> &'static str {
+        match self {
+            PlanAction::Delete => "delete",
+            PlanAction::Create => "create",
+            PlanAction::Update => "update",
+            PlanAction::Disable => "disable",
+            PlanAction::Enable => "enable",
+            PlanAction::Stop => "stop",
+            PlanAction::Restart => "restart",
+            PlanAction::Start => "start",
+        }
+    }
+}
+
+/// A step of the plan printed by `ser apply`
+#[derive(Serialize)]
+struct PlanStep {
+    /// What will be done
+    action: PlanAction,
+
+    /// The short service name
+    service: String,
+
+    /// Unified diff of the .service file for `create` and `update`
+    diff: Option<String>,
+
+    /// Contents of the .service file to write for `create` and `update`
+    #[serde(skip)]
+    contents: Option<String>,
+}
+
+/// Creates, updates and deletes services so that the host matches a manifest, then enables,
+/// starts, stops or restarts them as the manifest says. The plan is printed first.
+///
+/// # Arguments
+///
+/// * `file` - Path of the manifest, eg. `servicer.toml`
+/// * `prune` - Delete servicer services not listed in the manifest. They are kept in the trash
+/// * `dry_run` - Only print the plan
+///
+pub async fn handle_apply_manifest(
+    file: &Path,
+    prune: bool,
+    dry_run: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let manifest = load_manifest(file)?;
+
+    // Relative paths in the manifest start at its directory
+    let base_directory = std::fs::canonicalize(file)?
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+
+    apply_services(
+        &manifest.services,
+        &base_directory,
+        &file.to_string_lossy(),
+        prune,
+        dry_run,
+    )
+    .await
+}
+
+/// Prints the options of an imported file which were not translated, then applies its services
+/// without deleting others
+///
+/// # Arguments
+///
+/// * `imported` - Services translated from the file
+/// * `path` - The imported file, eg. a Procfile
+/// * `dry_run` - Only print the plan
+///
+pub async fn apply_imported(
+    imported: &ImportedServices,
+    path: &Path,
+    dry_run: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    print_unsupported(&imported.unsupported);
+
+    let base_directory = std::fs::canonicalize(path)?
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+
+    apply_services(
+        &imported.services,
+        &base_directory,
+        &path.to_string_lossy(),
+        false,
+        dry_run,
+    )
+    .await
+}
+
+/// Prints the plan to converge the host to a set of services, then carries it out unless
+/// `dry_run` is set
+///
+/// # Arguments
+///
+/// * `services` - The wanted services by name
+/// * `base_directory` - Directory relative paths of the services start at
+/// * `source` - Where the services come from, for messages, eg. a file name
+/// * `prune` - Delete servicer services not in `services`
+/// * `dry_run` - Only print the plan
+///
+pub async fn apply_services(
+    services: &BTreeMap<String, ServiceManifest>,
+    base_directory: &Path,
+    source: &str,
+    prune: bool,
+    dry_run: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let connection = connect().await?;
+    let mut plan = vec![];
+
+    for (name, service) in services {
+        let contents = render_service(name, service, base_directory)
+            .map_err(|error| format!("Service {name} in {source}: {error}"))?;
+
+        let full_service_name = get_full_service_name(name);
+        let service_file_path = get_service_file_path(&full_service_name)?;
+        let path = service_file_path.to_string_lossy();
+        let current = std::fs::read_to_string(&service_file_path).ok();
+
+        let (changed, active, enabled) = match &current {
+            None => {
+                plan.push(PlanStep {
+                    action: PlanAction::Create,
+                    service: name.clone(),
+                    diff: Some(unified_diff("", &contents, "/dev/null", &path)),
+                    contents: Some(contents),
+                });
+                (true, false, false)
+            }
+            Some(current) => {
+                let changed = *current != contents;
+                if changed {
+                    plan.push(PlanStep {
+                        action: PlanAction::Update,
+                        service: name.clone(),
+                        diff: Some(unified_diff(current, &contents, &path, &path)),
+                        contents: Some(contents),
+                    });
+                }
+
+                let active_state = get_active_state(&connection, &full_service_name).await?;
+                let unit_state = get_unit_file_state(&connection, &full_service_name).await?;
+
+                (
+                    changed,
+                    is_running(&active_state),
+                    matches!(unit_state.as_str(), "enabled" | "enabled-runtime"),
+                )
+            }
+        };
+
+        let mut push = |action| {
+            plan.push(PlanStep {
+                action,
+                service: name.clone(),
+                diff: None,
+                contents: None,
+            })
+        };
+
+        match service.enable {
+            Some(true) if !enabled => push(PlanAction::Enable),
+            Some(false) if enabled => push(PlanAction::Disable),
+            _ => {}
+        }
+
+        // A running service is restarted so that changes to its file take effect
+        match service.start {
+            Some(false) if active => push(PlanAction::Stop),
+            Some(false) => {}
+            _ if active && changed => push(PlanAction::Restart),
+            Some(true) if !active => push(PlanAction::Start),
+            _ => {}
+        }
+    }
+
+    if prune {
+        let mut full_service_names = get_servicer_services()?;
+        full_service_names.sort();
+
+        for full_service_name in full_service_names {
+            let name = get_short_service_name(&full_service_name);
+
+            if !services.contains_key(&name) {
+                plan.push(PlanStep {
+                    action: PlanAction::Delete,
+                    service: name,
+                    diff: None,
+                    contents: None,
+                });
+            }
+        }
+    }
+
+    if is_table() {
+        print_plan(&plan, source);
+    } else if dry_run {
+        print_document("plan", &plan)?;
+    }
+
+    if dry_run || plan.is_empty() {
+        return Ok(());
+    }
+
+    plan.sort_by_key(|step| step.action);
+
+    for step in plan.iter().filter(|step| step.action == PlanAction::Delete) {
+        handle_delete_service(&step.service, true, true, false).await?;
+    }
+
+    let mut written = false;
+    for step in &plan {
+        if let Some(contents) = &step.contents {
+            write_service_file(&step.service, step.action, contents)?;
+            written = true;
+        }
+    }
+    if written {
+        let manager_proxy = ManagerProxy::new(&connection).await?;
+        daemon_reload(&manager_proxy).await?;
+    }
+
+    for step in &plan {
+        let name = step.service.as_str();
+
+        match step.action {
+            PlanAction::Delete | PlanAction::Create | PlanAction::Update => {}
+            PlanAction::Disable => handle_disable_service(name, false).await?,
+            PlanAction::Enable => handle_enable_service(name, false).await?,
+            PlanAction::Stop => handle_stop_service(name, DEFAULT_JOB_TIMEOUT, false).await?,
+            PlanAction::Restart => {
+                handle_restart_service(
+                    &[name.to_string()],
+                    false,
+                    false,
+                    DEFAULT_JOB_TIMEOUT,
+                    false,
+                )
+                .await?
+            }
+            PlanAction::Start => handle_start_service(name, DEFAULT_JOB_TIMEOUT, false).await?,
+        }
+    }
+
+    if is_table() {
+        handle_show_status().await?;
+    }
+
+    Ok(())
+}
+
+/// Returns the .service file servicer writes for a service of a manifest, the same way as
+/// `ser create`
+///
+/// # Arguments
+///
+/// * `name` - The service name
+/// * `service` - The service in the manifest
+/// * `base_directory` - Directory relative paths start at
+///
+fn render_service(
+    name: &str,
+    service: &ServiceManifest,
+    base_directory: &Path,
+) -> Result<String, Box<dyn std::error::Error>> {
+    validate_service_name(name)?;
+
+    let path = base_directory.join(&service.path);
+    if !path.is_file() {
+        return Err(format!("{} is not a file", path.display()).into());
+    }
+    // Absolute paths are kept as written, eg. /usr/bin/python3 instead of the version it links to
+    let path = if service.path.is_absolute() {
+        path
+    } else {
+        std::fs::canonicalize(path)?
+    };
+
+    let user = match &service.user {
+        Some(_) if is_user() => {
+            return Err("user can't be set for user services, they run as your user".into())
+        }
+        Some(user) => Some(user.clone()),
+        None => get_service_user()?,
+    };
+
+    let interpreter = match service.interpreter.as_deref() {
+        Some("none") => None,
+        Some(interpreter) => Some(interpreter.to_string()),
+        None => get_interpreter(path.extension())?,
+    };
+
+    let file_path = path.to_string_lossy();
+    let mut words = vec![];
+    if let Some(interpreter) = interpreter {
+        words.push(find_interpreter(&interpreter, user.as_deref())?);
+        words.extend(service.interpreter_args.iter().cloned());
+    }
+    words.push(file_path.to_string());
+    words.extend(service.args.iter().cloned());
+
+    let exec_start = words
+        .iter()
+        .map(|word| quote_word(word))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let working_directory = match &service.working_directory {
+        Some(directory) => base_directory.join(directory),
+        None => path.parent().map(Path::to_path_buf).unwrap_or_default(),
+    };
+
+    let environment = service
+        .env
+        .iter()
+        .map(|(key, value)| quote_word(&format!("{key}={value}")))
+        .collect();
+
+    // A service which exits while the start job sleeps fails to start. sleep isn't in /bin
+    // everywhere, eg. on NixOS, so it is looked up like an interpreter
+    let exec_start_post = match service.start_secs.filter(|seconds| *seconds > 0) {
+        Some(seconds) => {
+            let sleep = find_interpreter("sleep", user.as_deref())
+                .map_err(|error| format!("start_secs of {name} needs sleep: {error}"))?;
+            vec![format!("{} {seconds}", quote_word(&sleep))]
+        }
+        None => vec![],
+    };
+
+    let mut service_unit = new_service_unit(
+        user,
+        &working_directory.to_string_lossy(),
+        exec_start,
+        service.auto_restart,
+        environment,
+    );
+
+    let section = &mut service_unit.service;
+    if service.restart.is_some() {
+        section.restart = service.restart.clone();
+    }
+    if service.restart_sec.is_some() {
+        section.restart_sec = service.restart_sec.clone();
+    }
+    section.memory_max = service.memory_max.clone();
+    section.cpu_quota = service.cpu_quota.clone();
+    section.tasks_max = service.tasks_max.clone();
+
+    section.exec_start_post = exec_start_post;
+    section.kill_signal = service
+        .stop_signal
+        .as_ref()
+        .map(|signal| match signal.to_uppercase() {
+            signal if signal.starts_with("SIG") => signal,
+            signal => format!("SIG{signal}"),
+        });
+
+    Ok(render_service_unit(&service_unit))
+}
+
+/// Writes the .service file of a created or updated service and records it in the history
+///
+/// # Arguments
+///
+/// * `name` - The service name
+/// * `action` - `Create` or `Update`
+/// * `contents` - The new contents
+///
+fn write_service_file(
+    name: &str,
+    action: PlanAction,
+    contents: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let service_file_path = get_service_file_path(&get_full_service_name(name))?;
+    let path = service_file_path.to_string_lossy().to_string();
+    let previous = std::fs::read_to_string(&service_file_path).ok();
+
+    // ~/.config/systemd/user may not exist yet
+    std::fs::create_dir_all(get_unit_directory())?;
+    std::fs::write(&service_file_path, contents)?;
+    record_change(name, previous.as_deref(), contents)?;
+
+    let message = if action == PlanAction::Create {
+        format!("Created {path}")
+    } else {
+        format!("Updated {path}")
+    };
+    print_result(ActionResult::new(action.as_str(), name, message).with_path(&path));
+
+    Ok(())
+}
+
+/// Prints the plan like a diff, with the changes to each .service file
+///
+/// # Arguments
+///
+/// * `plan` - The steps in the order they were planned
+/// * `source` - Where the services come from, eg. a file name
+///
+fn print_plan(plan: &[PlanStep], source: &str) {
+    if plan.is_empty() {
+        print_message(&format!("No changes. The services match {source}"));
+        return;
+    }
+
+    let color = std::io::stdout().is_terminal();
+    println!("Plan for {source}:");
+
+    for step in plan {
+        let sign = match step.action {
+            PlanAction::Create | PlanAction::Enable | PlanAction::Start => '+',
+            PlanAction::Update | PlanAction::Restart => '~',
+            PlanAction::Delete | PlanAction::Disable | PlanAction::Stop => '-',
+        };
+        let line = format!("{sign} {} {}", step.action.as_str(), step.service);
+
+        match sign {
+            _ if !color => println!("{line}"),
+            '+' => println!("{}", line.green()),
+            '-' => println!("{}", line.red()),
+            _ => println!("{}", line.yellow()),
+        }
+
+        if let Some(diff) = &step.diff {
+            print_unified_diff(diff);
+        }
+    }
+
+    let mut counts = BTreeMap::new();
+    for step in plan {
+        *counts.entry(step.action).or_insert(0) += 1;
+    }
+    let summary = counts
+        .iter()
+        .map(|(action, count)| format!("{count} to {}", action.as_str()))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    println!("Plan: {summary}");
+}
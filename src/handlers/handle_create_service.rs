@@ -1,5 +1,7 @@
-use indoc::formatdoc;
-use std::{env, path::PathBuf};
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
 
 use crate::{
     handlers::{
@@ -7,8 +9,15 @@ use crate::{
         handle_start_service::handle_start_service,
     },
     utils::{
-        find_binary_path::find_binary_path,
-        service_names::{get_full_service_name, get_service_file_path},
+        error::ServicerError,
+        find_binary_path::find_interpreter,
+        history::record_change,
+        output::{is_table, print_message, print_result, ActionResult},
+        scope::{connect, get_default_target, get_unit_directory, is_user},
+        service_actions::{daemon_reload, DEFAULT_JOB_TIMEOUT},
+        service_names::{get_full_service_name, get_service_file_path, validate_service_name},
+        systemd::ManagerProxy,
+        unit_file::{InstallSection, ServiceSection, ServiceUnit, UnitFile, UnitSection},
     },
 };
 
@@ -37,54 +46,50 @@ pub async fn handle_create_service(
     create_params: ServiceCreateParams,
 ) -> Result<(), Box<dyn std::error::Error>> {
     if !create_params.path.is_file() {
-        return Err(format!("{} is not a file", create_params.path.to_str().unwrap()).into());
+        return Err(ServicerError::NotFound(create_params.path.display().to_string()).into());
     }
 
     // The file name including extension, eg. index.js
     let file_name = create_params
         .path
         .file_name()
-        .expect("Failed to get file name")
-        .to_str()
-        .expect("Failed to stringify file name")
-        .to_string();
+        .map(|file_name| file_name.to_string_lossy().to_string())
+        .unwrap_or_default();
     let file_path = create_params.path.to_string_lossy();
 
     let service_name = create_params
         .custom_name
         .unwrap_or_else(|| file_name.to_string());
+    validate_service_name(&service_name)?;
     let full_service_name = get_full_service_name(&service_name);
 
     // Create file if it doesn't exist
-    let service_file_path = get_service_file_path(&full_service_name);
-    let service_file_path_str = service_file_path.to_str().unwrap().to_string();
+    let service_file_path = get_service_file_path(&full_service_name)?;
+    let service_file_path_str = service_file_path.to_string_lossy().to_string();
 
     if service_file_path.exists() {
-        panic!(
-            "Service {} already exists at {}. Provide a custom name with --name or delete the existing service with `ser delete {}",
-            service_name,
-            service_file_path_str,
-            service_name
-        );
+        return Err(ServicerError::UnitExists {
+            name: service_name,
+            path: service_file_path_str,
+        }
+        .into());
     } else {
         let interpreter = match create_params.custom_interpreter {
             Some(_) => create_params.custom_interpreter,
-            None => get_interpreter(create_params.path.extension()),
+            None => get_interpreter(create_params.path.extension())?,
         };
 
         // Handle case `ser create index.js` where relative path lacks ./
-        let mut parent_path = create_params.path.parent().unwrap();
-        let current_dir = env::current_dir().unwrap();
-        if parent_path.to_str() == Some("") {
+        let mut parent_path = create_params.path.parent().unwrap_or(Path::new(""));
+        let current_dir = env::current_dir()?;
+        if parent_path.as_os_str().is_empty() {
             parent_path = &current_dir;
         }
-        let working_directory = std::fs::canonicalize(parent_path)
-            .unwrap()
-            .to_str()
-            .unwrap()
+        let working_directory = std::fs::canonicalize(parent_path)?
+            .to_string_lossy()
             .to_string();
 
-        create_service_file(
+        let contents = create_service_file(
             &service_file_path_str,
             &working_directory,
             create_params.auto_restart,
@@ -93,19 +98,32 @@ pub async fn handle_create_service(
             create_params.internal_args,
             &file_path,
         )
-        .await
-        .unwrap();
+        .await?;
+        record_change(&service_name, None, &contents)?;
+
+        let connection = connect().await?;
+        let manager_proxy = ManagerProxy::new(&connection).await?;
+        daemon_reload(&manager_proxy).await?;
 
-        println!("Service {service_name} created at {service_file_path_str}. To start run `ser start {service_name}`");
+        print_result(
+            ActionResult::new(
+                "create",
+                &service_name,
+                format!("Service {service_name} created at {service_file_path_str}. To start run `ser start {service_name}`"),
+            )
+            .with_path(&service_file_path_str),
+        );
 
         if create_params.start {
-            handle_start_service(&service_name, false).await.unwrap();
+            handle_start_service(&service_name, DEFAULT_JOB_TIMEOUT, false).await?;
         }
         if create_params.enable {
-            handle_enable_service(&service_name, false).await.unwrap();
+            handle_enable_service(&service_name, false).await?;
         }
 
-        handle_show_status().await?;
+        if is_table() {
+            handle_show_status().await?;
+        }
     }
 
     Ok(())
@@ -117,26 +135,27 @@ pub async fn handle_create_service(
 ///
 /// * `extension`: The file extension
 ///
-fn get_interpreter(extension: Option<&std::ffi::OsStr>) -> Option<String> {
+pub fn get_interpreter(
+    extension: Option<&std::ffi::OsStr>,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
     match extension {
         Some(extension_os_str) => {
-            let extension_str = extension_os_str
-                .to_str()
-                .expect("failed to stringify extension");
+            let extension_str = extension_os_str.to_string_lossy();
 
-            let interpreter = match extension_str {
-              "js" => "node",
-              "py" => "python3",
-              _ => panic!("No interpeter found for extension {}. Please provide a custom interpeter and try again.", extension_str)
-          };
+            let interpreter = match extension_str.as_ref() {
+                "js" => "node",
+                "py" => "python3",
+                _ => return Err(format!("No interpeter found for extension {}. Please provide a custom interpeter with --interpreter and try again.", extension_str).into())
+            };
 
-            Some(interpreter.to_string())
+            Ok(Some(interpreter.to_string()))
         }
-        None => None,
+        None => Ok(None),
     }
 }
 
-/// Creates a systemd service file at `/etc/systemd/system/{}.ser.service` and returns the unit name
+/// Creates a systemd service file at `/etc/systemd/system/{}.ser.service`, or in
+/// `~/.config/systemd/user` for user services, and returns its contents
 ///
 /// # Arguments
 ///
@@ -145,7 +164,8 @@ fn get_interpreter(extension: Option<&std::ffi::OsStr>) -> Option<String> {
 /// * `working_directory` - Working directory of the file to execute
 /// * `auto_restart` - Auto restart the service on error
 /// * `interpreter` - The executable used to run the app, eg. `node` or `python3`. The executable
-///   must be visible from path for a sudo user. Note that the app itself does not run in sudo.
+///   must be visible from path for a sudo user, or the calling user for user services. Note
+///   that the app itself does not run in sudo.
 /// * `env_vars` - Environment variables
 /// * `internal_args` - Args passed to the file
 /// * `file_name` - Name of the file to run
@@ -158,18 +178,13 @@ async fn create_service_file(
     env_vars: Option<String>,
     internal_args: Vec<String>,
     file_name: &str,
-) -> std::io::Result<()> {
-    // This gets `root` instead of `hp` if sudo is used
-    let user =
-        env::var("SUDO_USER").expect("Must be in sudo mode. ENV variable $SUDO_USER not found");
+) -> Result<String, Box<dyn std::error::Error>> {
+    let user = get_service_user()?;
     let mut exec_start = match interpreter {
         Some(interpreter) => {
-            let interpreter_path = find_binary_path(&interpreter, &user)
-                .unwrap()
-                .trim_end_matches("\n")
-                .to_string();
+            let interpreter_path = find_interpreter(&interpreter, user.as_deref())?;
 
-            println!("got path {}", interpreter_path);
+            print_message(&format!("got path {}", interpreter_path));
 
             format!("{} {}", interpreter_path, file_name)
         }
@@ -180,47 +195,96 @@ async fn create_service_file(
         exec_start = format!("{} {}", exec_start, arg);
     }
 
-    let env_vars_formatted = match env_vars {
-        Some(vars) => {
-            // Split the input string by whitespace
-            let pairs: Vec<&str> = vars.split_whitespace().collect();
-
-            // Format each pair as "Environment=key=value"
-            let formatted_pairs: Vec<String> = pairs
-                .iter()
-                .map(|pair| format!("Environment={}", pair))
-                .collect();
+    let environment = match env_vars {
+        // Each pair is written as its own `Environment=key=value` line
+        Some(vars) => vars.split_whitespace().map(str::to_string).collect(),
+        None => vec![],
+    };
 
-            // Join the formatted pairs with newlines
+    let service_unit = new_service_unit(
+        user,
+        working_directory,
+        exec_start,
+        auto_restart,
+        environment,
+    );
+    let service_body = render_service_unit(&service_unit);
 
-            formatted_pairs.join("\n")
-        }
-        None => "".to_string(),
-    };
+    // Create the service file and write the content. ~/.config/systemd/user may not exist yet
+    std::fs::create_dir_all(get_unit_directory())?;
+    std::fs::write(service_file_path, service_body.as_bytes())?;
 
-    let restart_policy = if auto_restart { "Restart=always" } else { "" };
+    Ok(service_body)
+}
 
-    // Replacement for format!(). This proc macro removes spaces produced by indentation.
-    let service_body = formatdoc! {
-        r#"
-      # Generated with Servicer
-      [Unit]
-      After=network.target
+/// Returns the user a new service runs as: the user who called sudo. `None` for user services,
+/// which run as the calling user and can't set `User=`.
+pub fn get_service_user() -> Result<Option<String>, ServicerError> {
+    if is_user() {
+        return Ok(None);
+    }
 
-      [Service]
-      Type=simple
-      User={user}
+    // This gets `root` instead of `hp` if sudo is used
+    env::var("SUDO_USER").map(Some).map_err(|_| {
+        ServicerError::PermissionDenied(
+            "create a service outside of sudo mode. ENV variable $SUDO_USER not found. Pass \
+            --user to create a service of your own user instead"
+                .to_string(),
+        )
+    })
+}
 
-      WorkingDirectory={working_directory}
-      ExecStart={exec_start}
-      {restart_policy}
-      {env_vars_formatted}
+/// Builds the unit of a new service: a simple service started after the network and wanted by
+/// the default target
+///
+/// # Arguments
+///
+/// * `user` - `User=` of the service. `None` for user services
+/// * `working_directory` - Working directory of the file to execute
+/// * `exec_start` - The command line, starting with an absolute path or the file to run
+/// * `auto_restart` - Restart the service whenever it exits
+/// * `environment` - `KEY=value` pairs, each written as its own `Environment=` line
+///
+pub fn new_service_unit(
+    user: Option<String>,
+    working_directory: &str,
+    exec_start: String,
+    auto_restart: bool,
+    environment: Vec<String>,
+) -> ServiceUnit {
+    ServiceUnit {
+        unit: UnitSection {
+            after: vec!["network.target".to_string()],
+            ..Default::default()
+        },
+        service: ServiceSection {
+            service_type: Some("simple".to_string()),
+            user,
+            working_directory: Some(working_directory.to_string()),
+            exec_start: vec![exec_start],
+            restart: auto_restart.then(|| "always".to_string()),
+            // Without it systemd restarts after 100ms and quickly hits the start limit
+            restart_sec: auto_restart.then(|| "1s".to_string()),
+            environment,
+            ..Default::default()
+        },
+        install: InstallSection {
+            wanted_by: vec![get_default_target().to_string()],
+        },
+    }
+}
 
-      [Install]
-      WantedBy=multi-user.target
-      "#
-    };
+/// Returns the contents of a .service file servicer writes for a unit
+///
+/// # Arguments
+///
+/// * `service_unit` - The unit
+///
+pub fn render_service_unit(service_unit: &ServiceUnit) -> String {
+    let mut unit_file: UnitFile = "# Generated with Servicer\n"
+        .parse()
+        .expect("Failed to parse service file header");
+    service_unit.write_to(&mut unit_file);
 
-    // Create the service file and write the content
-    std::fs::write(service_file_path, service_body.as_bytes())
+    unit_file.to_string()
 }
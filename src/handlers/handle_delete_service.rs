@@ -1,33 +1,88 @@
-use crate::utils::service_names::{get_full_service_name, get_service_file_path};
+use std::io::IsTerminal;
+
+use crate::utils::{
+    error::ServicerError,
+    output::{is_table, print_result, ActionResult},
+    prompt::confirm,
+    scope::connect,
+    service_actions::{daemon_reload, DEFAULT_JOB_TIMEOUT},
+    service_names::{get_full_service_name, get_service_file_path},
+    systemd::{get_active_state, get_unit_file_state, ManagerProxy},
+    trash::move_to_trash,
+};
 
 use super::{
     handle_disable_service::handle_disable_service, handle_show_status::handle_show_status,
     handle_stop_service::handle_stop_service,
 };
 
-/// Deletes a service, stopping and disabling it if necessary and removing the .service file
+/// Deletes a service, stopping and disabling it if necessary. The .service file and its
+/// drop-ins are moved to the trash along with whether the service was enabled and running, so
+/// `ser undelete` can bring it back.
 ///
 /// # Arguments
 ///
 /// * `name`- Name of the service to stop
+/// * `yes` - Delete without asking for confirmation
+/// * `trash` - Keep the .service file in the trash. Otherwise it is removed
+/// * `show_status` - Print the status table after deleting
 ///
 pub async fn handle_delete_service(
     name: &str,
+    yes: bool,
+    trash: bool,
     show_status: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    handle_stop_service(name, false).await?;
+    let full_service_name = get_full_service_name(name);
+    let service_file_path = get_service_file_path(&full_service_name)?;
+
+    if !service_file_path.exists() {
+        return Err(ServicerError::NotFound(format!("service {full_service_name}")).into());
+    }
+
+    if !yes {
+        if !std::io::stdin().is_terminal() {
+            return Err(format!("Not deleting {name} without confirmation. Pass --yes").into());
+        }
+
+        if !confirm(&format!("Delete {name}?"), false)? {
+            print_result(ActionResult {
+                success: false,
+                ..ActionResult::new("delete", name, "Delete canceled.".to_string())
+            });
+            return Ok(());
+        }
+    }
+
+    let connection = connect().await?;
+    let active_state = get_active_state(&connection, &full_service_name).await?;
+    let unit_state = get_unit_file_state(&connection, &full_service_name).await?;
+
+    handle_stop_service(name, DEFAULT_JOB_TIMEOUT, false).await?;
     handle_disable_service(name, false).await?;
 
-    let full_service_name = get_full_service_name(name);
-    let service_file_path = get_service_file_path(&full_service_name);
-    let service_file_path_str = service_file_path.to_str().unwrap().to_string();
+    let service_file_path_str = service_file_path.to_string_lossy().to_string();
+
+    let message = if trash {
+        let entry_path = move_to_trash(name, unit_state == "enabled", active_state == "active")?;
+
+        format!(
+            "Moved {service_file_path_str} to {}. Restore it with `ser undelete {name}`",
+            entry_path.display()
+        )
+    } else {
+        // Delete .service file
+        std::fs::remove_file(&service_file_path)?;
+
+        format!("Deleted {service_file_path_str}")
+    };
 
-    // Delete .service file
-    std::fs::remove_file(&service_file_path)?;
+    let manager_proxy = ManagerProxy::new(&connection).await?;
+    daemon_reload(&manager_proxy).await?;
 
-    println!("Deleted {service_file_path_str}");
+    print_result(ActionResult::new("delete", name, message).with_path(&service_file_path_str));
 
-    if show_status {
+    if show_status && is_table() {
         handle_show_status().await?;
     }
 
@@ -0,0 +1,68 @@
+use serde::Serialize;
+
+use crate::utils::{
+    error::ServicerError,
+    history::{get_previous_revision, read_version},
+    output::{is_table, print_diff, print_document, unified_diff},
+    service_names::{get_full_service_name, get_service_file_path},
+};
+
+#[derive(Serialize)]
+struct ServiceFileDiff {
+    /// The short service name
+    service: String,
+
+    /// Revision compared with the current .service file
+    revision: u32,
+
+    /// Unified diff from the revision to the current .service file. Empty if they are the same
+    diff: String,
+}
+
+/// Print the changes between a recorded version of a service file and the current file
+///
+/// # Arguments
+///
+/// * `name` - The service name
+/// * `revision` - Revision to compare with. Defaults to the version before the latest change
+///
+pub fn handle_diff_service_file(
+    name: &str,
+    revision: Option<u32>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let full_service_name = get_full_service_name(name);
+    let service_file_path = get_service_file_path(&full_service_name)?;
+
+    if !service_file_path.exists() {
+        return Err(ServicerError::NotFound(format!("service {full_service_name}")).into());
+    }
+
+    let current = std::fs::read_to_string(&service_file_path)?;
+    let revision = match revision {
+        Some(revision) => revision,
+        None => get_previous_revision(name, Some(&current))?,
+    };
+    let old = read_version(name, revision)?;
+
+    let old_label = format!("{full_service_name}@{revision}");
+    let new_label = service_file_path.to_string_lossy();
+
+    if is_table() {
+        if old == current {
+            println!("Revision {revision} is the same as {new_label}");
+        } else {
+            print_diff(&old, &current, &old_label, &new_label);
+        }
+    } else {
+        print_document(
+            "diff",
+            &[ServiceFileDiff {
+                service: name.to_string(),
+                revision,
+                diff: unified_diff(&old, &current, &old_label, &new_label),
+            }],
+        )?;
+    }
+
+    Ok(())
+}
@@ -1,6 +1,11 @@
 use crate::handlers::handle_show_status::handle_show_status;
-use crate::utils::service_actions::disable_service;
-use crate::utils::{service_names::get_full_service_name, systemd::ManagerProxy};
+use crate::utils::service_actions::{daemon_reload, disable_service};
+use crate::utils::{
+    output::{is_table, print_result, ActionResult},
+    scope::connect,
+    service_names::get_full_service_name,
+    systemd::ManagerProxy,
+};
 
 /// Disables a service from starting on boot
 ///
@@ -14,17 +19,21 @@ pub async fn handle_disable_service(
 ) -> Result<(), Box<dyn std::error::Error>> {
     let full_service_name = get_full_service_name(name);
 
-    let connection = zbus::Connection::system().await?;
+    let connection = connect().await?;
     let manager_proxy = ManagerProxy::new(&connection).await?;
 
-    disable_service(&manager_proxy, &full_service_name).await;
+    disable_service(&manager_proxy, &full_service_name).await?;
 
     // Reload necessary for UnitFileState to update
-    manager_proxy.reload().await?;
+    daemon_reload(&manager_proxy).await?;
 
-    println!("Disabled {name}");
+    print_result(ActionResult::new(
+        "disable",
+        name,
+        format!("Disabled {name}"),
+    ));
 
-    if show_status {
+    if show_status && is_table() {
         handle_show_status().await?;
     }
 
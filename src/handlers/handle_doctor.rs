@@ -0,0 +1,504 @@
+use std::{ffi::CString, fmt, os::unix::ffi::OsStrExt, path::Path};
+
+use serde::Serialize;
+use zbus::Connection;
+
+use crate::utils::{
+    find_binary_path::is_executable,
+    journal::JOURNAL_DIRECTORIES,
+    logind::is_lingering,
+    output::{is_table, print_document},
+    polkit::check_authorization,
+    scope::{connect, get_bus_name, get_unit_directory, get_user_name, is_user},
+    service_names::{get_service_file_path, get_servicer_services},
+    systemd::{get_unit_path, ManagerProxy, UnitProxy},
+    unit_file::{split_exec_start, UnitFile},
+};
+
+/// Oldest systemd with `ListUnitFilesByPatterns` and `ListUnitsByPatterns`
+const MIN_SYSTEMD_VERSION: u32 = 230;
+
+/// polkit actions for starting units and for enabling unit files
+const POLKIT_ACTIONS: [&str; 2] = [
+    "org.freedesktop.systemd1.manage-units",
+    "org.freedesktop.systemd1.manage-unit-files",
+];
+
+/// Outcome of a check
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            CheckStatus::Pass => "pass",
+            CheckStatus::Warn => "warn",
+            CheckStatus::Fail => "fail",
+        };
+
+        write!(f, "{status}")
+    }
+}
+
+/// Result of one check of the environment
+#[derive(Serialize)]
+pub struct Check {
+    /// What was checked, eg. `system-bus`
+    pub check: String,
+
+    pub status: CheckStatus,
+
+    /// What was found
+    pub message: String,
+
+    /// How to fix a warning or failure
+    pub hint: Option<String>,
+}
+
+impl Check {
+    fn pass(check: &str, message: String) -> Self {
+        Check {
+            check: check.to_string(),
+            status: CheckStatus::Pass,
+            message,
+            hint: None,
+        }
+    }
+
+    fn warn(check: &str, message: String, hint: &str) -> Self {
+        Check {
+            check: check.to_string(),
+            status: CheckStatus::Warn,
+            message,
+            hint: Some(hint.to_string()),
+        }
+    }
+
+    fn fail(check: &str, message: String, hint: &str) -> Self {
+        Check {
+            status: CheckStatus::Fail,
+            ..Check::warn(check, message, hint)
+        }
+    }
+}
+
+/// Check that servicer can manage services on this machine: the system bus and systemd
+/// version, permissions, interpreters of existing services, persistent logs, the unit
+/// directory and pending daemon-reloads. Fails if any check fails.
+///
+/// User services are checked on the session bus. Permissions and sudo are not needed for them,
+/// lingering is checked instead.
+pub async fn handle_doctor() -> Result<(), Box<dyn std::error::Error>> {
+    let mut checks = vec![];
+
+    let bus = get_bus_name();
+    let bus_check = bus.replace(' ', "-");
+    let bus_hint = if is_user() {
+        "Ensure your systemd user manager is running, eg. `systemctl --user status`, and \
+        $XDG_RUNTIME_DIR is set. Sessions opened with su or sudo have neither"
+    } else {
+        "Ensure systemd is PID 1 and dbus is running, eg. `systemctl status dbus`. \
+        Containers and WSL often run without systemd"
+    };
+
+    let connection = match connect().await {
+        Ok(connection) => {
+            checks.push(Check::pass(&bus_check, format!("Connected to the {bus}")));
+            Some(connection)
+        }
+        Err(error) => {
+            checks.push(Check::fail(
+                &bus_check,
+                format!("Can't connect to the {bus}: {error}"),
+                bus_hint,
+            ));
+            None
+        }
+    };
+
+    if let Some(connection) = &connection {
+        checks.push(check_systemd_version(connection).await);
+        if !is_user() {
+            checks.push(check_permission(connection).await);
+        }
+    }
+
+    if is_user() {
+        checks.push(check_linger());
+    } else {
+        checks.push(check_sudo_user());
+    }
+    checks.push(check_interpreters());
+    checks.push(check_journal_storage());
+    checks.push(check_unit_directory());
+
+    if let Some(connection) = &connection {
+        checks.push(check_daemon_reload(connection).await);
+    }
+
+    let failed = checks
+        .iter()
+        .filter(|check| check.status == CheckStatus::Fail)
+        .count();
+
+    if is_table() {
+        let width = checks
+            .iter()
+            .map(|check| check.check.len())
+            .max()
+            .unwrap_or(0);
+
+        for check in &checks {
+            println!(
+                "[{}] {:<width$}  {}",
+                check.status, check.check, check.message
+            );
+            if let Some(hint) = &check.hint {
+                println!("       {:<width$}  Hint: {hint}", "");
+            }
+        }
+    } else {
+        print_document("doctor", &checks)?;
+    }
+
+    if failed > 0 {
+        return Err(format!("{failed} of {} checks failed", checks.len()).into());
+    }
+
+    Ok(())
+}
+
+/// Reads the `Version` property of the systemd manager
+///
+/// # Arguments
+///
+/// * `connection` - zbus connection
+///
+async fn check_systemd_version(connection: &Connection) -> Check {
+    const CHECK: &str = "systemd-version";
+
+    let version = match ManagerProxy::new(connection).await {
+        Ok(manager_proxy) => manager_proxy.version().await,
+        Err(error) => Err(error),
+    };
+
+    let version = match version {
+        Ok(version) => version,
+        Err(error) => {
+            return Check::fail(
+                CHECK,
+                format!("Can't read the systemd version: {error}"),
+                "Ensure systemd is running as the init system",
+            )
+        }
+    };
+
+    // Eg. `252.22-1~deb12u1` or `v255`
+    let major = version
+        .trim_start_matches('v')
+        .split(|c: char| !c.is_ascii_digit())
+        .next()
+        .and_then(|major| major.parse::<u32>().ok());
+
+    match major {
+        Some(major) if major >= MIN_SYSTEMD_VERSION => {
+            Check::pass(CHECK, format!("systemd {version}"))
+        }
+        _ => Check::warn(
+            CHECK,
+            format!("systemd {version} may be too old"),
+            &format!("servicer needs systemd {MIN_SYSTEMD_VERSION} or later"),
+        ),
+    }
+}
+
+/// Checks that servicer may call Manager methods, as root or through polkit
+///
+/// # Arguments
+///
+/// * `connection` - zbus connection
+///
+async fn check_permission(connection: &Connection) -> Check {
+    const CHECK: &str = "permission";
+    const HINT: &str = "Run servicer with sudo, eg. `sudo ser start hello-world`";
+
+    if unsafe { libc::geteuid() } == 0 {
+        return Check::pass(CHECK, "Running as root".to_string());
+    }
+
+    let mut challenge = false;
+
+    for action in POLKIT_ACTIONS {
+        match check_authorization(connection, action).await {
+            Ok((true, _)) => {}
+            Ok((false, true)) => challenge = true,
+            Ok((false, false)) => {
+                return Check::fail(CHECK, format!("Not root and polkit denies {action}"), HINT)
+            }
+            Err(error) => {
+                return Check::fail(
+                    CHECK,
+                    format!("Not root and polkit can't be asked: {error}"),
+                    HINT,
+                )
+            }
+        }
+    }
+
+    if challenge {
+        Check::warn(
+            CHECK,
+            "Not root. polkit asks for a password to manage units".to_string(),
+            HINT,
+        )
+    } else {
+        Check::pass(CHECK, "polkit allows managing units".to_string())
+    }
+}
+
+/// `ser create` looks up interpreters in the PATH of the user who ran sudo
+fn check_sudo_user() -> Check {
+    const CHECK: &str = "sudo-user";
+
+    match std::env::var("SUDO_USER") {
+        Ok(user) => Check::pass(CHECK, format!("Invoked with sudo by {user}")),
+        Err(_) => Check::warn(
+            CHECK,
+            "$SUDO_USER is not set, so `ser create` can't find interpreters in your PATH"
+                .to_string(),
+            "Run servicer with sudo from your own account instead of a root shell",
+        ),
+    }
+}
+
+/// Checks that the first program of every `ExecStart=` of servicer services is an executable
+/// file, eg. `/usr/bin/node`
+fn check_interpreters() -> Check {
+    const CHECK: &str = "interpreters";
+
+    let services = get_servicer_services().unwrap_or_default();
+    let mut problems = vec![];
+
+    for full_service_name in &services {
+        let Ok(unit_file) = get_service_file_path(full_service_name)
+            .map_err(|error| error.to_string())
+            .and_then(|path| std::fs::read_to_string(path).map_err(|error| error.to_string()))
+            .and_then(|contents| {
+                contents
+                    .parse::<UnitFile>()
+                    .map_err(|error| error.to_string())
+            })
+        else {
+            problems.push(format!("{full_service_name} can't be read"));
+            continue;
+        };
+
+        for command in unit_file.get_all("Service", "ExecStart") {
+            let Some((_, program, _)) = split_exec_start(command) else {
+                continue;
+            };
+
+            if !program.starts_with('/') {
+                problems.push(format!(
+                    "{full_service_name}: {program} is not an absolute path"
+                ));
+            } else if !is_executable(Path::new(&program)) {
+                problems.push(format!("{full_service_name}: {program} not found"));
+            }
+        }
+    }
+
+    if problems.is_empty() {
+        Check::pass(
+            CHECK,
+            format!(
+                "Every ExecStart program of {} services resolves",
+                services.len()
+            ),
+        )
+    } else {
+        Check::fail(
+            CHECK,
+            problems.join("; "),
+            "Install the interpreter or fix ExecStart with `ser edit`. For interpreters in your \
+            home directory, eg. installed with nvm, symlink them into /usr/local/bin",
+        )
+    }
+}
+
+/// Checks whether user services keep running after logout
+fn check_linger() -> Check {
+    const CHECK: &str = "linger";
+
+    match get_user_name() {
+        Some(user) if is_lingering(&user) => Check::pass(
+            CHECK,
+            format!("Lingering is enabled for {user}, services start on boot"),
+        ),
+        Some(user) => Check::warn(
+            CHECK,
+            format!("Lingering is disabled for {user}, services stop when you log out"),
+            "Run `loginctl enable-linger`, or accept the offer of `ser --user enable`",
+        ),
+        None => Check::warn(
+            CHECK,
+            "Can't look up the current user".to_string(),
+            "Ensure your user is in /etc/passwd",
+        ),
+    }
+}
+
+/// Checks whether journald keeps logs across reboots. With the default `Storage=auto` logs are
+/// persistent once journald writes to `/var/log/journal/<machine-id>`, which only happens if
+/// `/var/log/journal` existed when it started or was flushed.
+fn check_journal_storage() -> Check {
+    const CHECK: &str = "journal-storage";
+    const HINT: &str = "Set Storage=persistent in /etc/systemd/journald.conf and run \
+        `systemctl restart systemd-journald`";
+
+    let mut config_files = vec![Path::new("/etc/systemd/journald.conf").to_path_buf()];
+    if let Ok(entries) = std::fs::read_dir("/etc/systemd/journald.conf.d") {
+        let mut drop_ins: Vec<_> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "conf")
+            })
+            .collect();
+        drop_ins.sort();
+        config_files.extend(drop_ins);
+    }
+
+    // Later files override earlier ones, so search from the end
+    let storage = config_files
+        .iter()
+        .rev()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .filter_map(|contents| contents.parse::<UnitFile>().ok())
+        .find_map(|config| config.get("Journal", "Storage").map(str::to_string))
+        .unwrap_or("auto".to_string());
+
+    let persistent_directory = std::fs::read_to_string("/etc/machine-id")
+        .map(|machine_id| Path::new(JOURNAL_DIRECTORIES[0]).join(machine_id.trim()))
+        .unwrap_or_else(|_| Path::new(JOURNAL_DIRECTORIES[0]).to_path_buf());
+    let has_journal_files = std::fs::read_dir(&persistent_directory).is_ok_and(|entries| {
+        entries.flatten().any(|entry| {
+            entry
+                .path()
+                .extension()
+                .is_some_and(|extension| extension == "journal")
+        })
+    });
+
+    match storage.as_str() {
+        "persistent" => Check::pass(CHECK, "Logs are kept across reboots".to_string()),
+        "auto" if has_journal_files => Check::pass(
+            CHECK,
+            format!(
+                "Logs are kept across reboots in {}",
+                persistent_directory.display()
+            ),
+        ),
+        "auto" if persistent_directory.is_dir() => Check::warn(
+            CHECK,
+            format!(
+                "Logs are lost on reboot since journald hasn't written to {} yet",
+                persistent_directory.display()
+            ),
+            HINT,
+        ),
+        "auto" => Check::warn(
+            CHECK,
+            format!(
+                "Logs are lost on reboot since {} doesn't exist",
+                persistent_directory.display()
+            ),
+            HINT,
+        ),
+        storage => Check::warn(
+            CHECK,
+            format!("Storage={storage}, logs are lost on reboot"),
+            HINT,
+        ),
+    }
+}
+
+/// Checks that .service files can be created
+fn check_unit_directory() -> Check {
+    const CHECK: &str = "unit-directory";
+
+    let unit_directory = get_unit_directory();
+    let display = unit_directory.display();
+
+    // servicer creates ~/.config/systemd/user along with the first user service
+    if is_user() && !unit_directory.exists() {
+        return Check::pass(
+            CHECK,
+            format!("{display} will be created with the first service"),
+        );
+    }
+
+    let Ok(path) = CString::new(unit_directory.as_os_str().as_bytes()) else {
+        return Check::fail(CHECK, format!("Invalid path {display}"), "");
+    };
+
+    if unsafe { libc::access(path.as_ptr(), libc::W_OK) } == 0 {
+        Check::pass(CHECK, format!("{display} is writable"))
+    } else {
+        Check::fail(
+            CHECK,
+            format!(
+                "{display} is not writable: {}",
+                std::io::Error::last_os_error()
+            ),
+            "Run servicer with sudo. On read-only systems such as Fedora Silverblue, ensure \
+            /etc is writable",
+        )
+    }
+}
+
+/// Checks whether systemd still uses an outdated copy of any .service file
+///
+/// # Arguments
+///
+/// * `connection` - zbus connection
+///
+async fn check_daemon_reload(connection: &Connection) -> Check {
+    const CHECK: &str = "daemon-reload";
+
+    let mut outdated = vec![];
+
+    for full_service_name in get_servicer_services().unwrap_or_default() {
+        let Ok(unit_proxy) = UnitProxy::new(connection, get_unit_path(&full_service_name)).await
+        else {
+            continue;
+        };
+
+        if unit_proxy.need_daemon_reload().await.unwrap_or(false) {
+            outdated.push(full_service_name);
+        }
+    }
+
+    if outdated.is_empty() {
+        Check::pass(CHECK, "systemd uses the current .service files".to_string())
+    } else {
+        Check::warn(
+            CHECK,
+            format!(
+                "Changed on disk since systemd loaded them: {}",
+                outdated.join(", ")
+            ),
+            if is_user() {
+                "Run `systemctl --user daemon-reload`"
+            } else {
+                "Run `sudo systemctl daemon-reload`"
+            },
+        )
+    }
+}
@@ -1,6 +1,17 @@
-use std::path::PathBuf;
-
-use crate::utils::service_names::{get_full_service_name, get_service_file_path};
+use std::path::Path;
+
+use crate::{
+    handlers::handle_restart_service::offer_restart,
+    utils::{
+        history::record_change,
+        output::{is_table, print_diff, print_message, print_result, ActionResult},
+        scope::{connect, get_default_target, get_unit_directory},
+        service_actions::daemon_reload,
+        service_names::{get_full_service_name, get_service_file_path},
+        systemd::ManagerProxy,
+        unit_lint::{lint_unit_file, Diagnostic, Severity},
+    },
+};
 use tempfile::Builder;
 
 const SERVICE_TEMPLATE: &str = r#"
@@ -21,58 +32,184 @@ Restart=always
 WantedBy=multi-user.target
 "#;
 
-/// Opens an text editor to create or update a service file
+/// Prefix of the comments servicer adds to a file with errors. Removed before validating.
+const ERROR_COMMENT_PREFIX: &str = "# ser: ";
+
+/// Editor used if neither `$VISUAL` nor `$EDITOR` is set
+const DEFAULT_EDITOR: &str = "nano";
+
+/// Opens a text editor to create or update a service file
+///
+/// The file is edited in a temporary copy which is validated on save, like `visudo`. If it has
+/// errors the editor is reopened with the errors as comments. Valid changes are saved, systemd
+/// reloads its units and a diff is printed. A running service may be restarted to apply them.
+/// Every saved version is kept in the history of the service.
 ///
 /// # Arguments
 ///
 /// * `name`- Name of the service to edit
-/// * `editor` - Name of editor. The editor must be visible in path
+/// * `editor` - Editor command, eg. `vim` or `code --wait`. Defaults to `$VISUAL`, `$EDITOR`,
+///   then nano
 ///
-pub fn handle_edit_service_file(
+pub async fn handle_edit_service_file(
     name: &str,
-    editor: &str,
+    editor: Option<&str>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let full_service_name = get_full_service_name(name);
-    let service_file_path = get_service_file_path(&full_service_name);
-
-    if service_file_path.exists() {
-        let edit_success = edit_file(editor, &service_file_path)?;
-
-        if edit_success {
-            println!(
-                "Service file {} edited successfully.",
-                service_file_path.to_str().unwrap()
-            );
-        } else {
-            eprintln!("Edit operation canceled. No changes were saved.");
+    let service_file_path = get_service_file_path(&full_service_name)?;
+    let path = service_file_path.to_string_lossy().to_string();
+
+    let previous = if service_file_path.exists() {
+        Some(std::fs::read_to_string(&service_file_path)?)
+    } else {
+        None
+    };
+    let action = if previous.is_some() { "edit" } else { "create" };
+
+    let editor = get_editor(editor);
+
+    let temp_file = Builder::new()
+        .prefix(&format!("{name}.ser."))
+        .suffix(".service")
+        .tempfile()?;
+    let temp_file_path = temp_file.path().to_owned();
+
+    let template = SERVICE_TEMPLATE.replace("multi-user.target", get_default_target());
+    std::fs::write(&temp_file_path, previous.as_deref().unwrap_or(&template))?;
+
+    let (contents, diagnostics) = loop {
+        if !edit_file(&editor, &temp_file_path)? {
+            print_result(ActionResult {
+                success: false,
+                ..ActionResult::new(
+                    action,
+                    name,
+                    "Edit operation canceled. No changes were saved.".to_string(),
+                )
+            });
+            return Ok(());
         }
+
+        let contents = strip_error_comments(&std::fs::read_to_string(&temp_file_path)?);
+        let diagnostics = lint_unit_file(&path, &contents);
+
+        if diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+        {
+            std::fs::write(&temp_file_path, add_error_comments(&contents, &diagnostics))?;
+            continue;
+        }
+
+        break (contents, diagnostics);
+    };
+
+    if previous.as_deref() == Some(contents.as_str()) {
+        print_result(ActionResult::new(action, name, "No changes".to_string()).with_path(&path));
+        return Ok(());
+    }
+
+    std::fs::create_dir_all(get_unit_directory())?;
+    std::fs::write(&service_file_path, &contents)?;
+    record_change(name, previous.as_deref(), &contents)?;
+
+    for diagnostic in &diagnostics {
+        print_message(&diagnostic.to_string());
+    }
+
+    let connection = connect().await?;
+    let manager_proxy = ManagerProxy::new(&connection).await?;
+    daemon_reload(&manager_proxy).await?;
+
+    if let (Some(previous), true) = (&previous, is_table()) {
+        print_diff(previous, &contents, &path, &path);
+    }
+
+    let message = if previous.is_some() {
+        format!("Service file {path} edited successfully.")
     } else {
-        // Write the template content to a temporary file
-        let temp_file = Builder::new().prefix(&full_service_name).tempfile()?;
-        let temp_file_path = temp_file.path().to_owned();
+        format!("Service file {path} created.")
+    };
+    print_result(ActionResult::new(action, name, message).with_path(&path));
 
-        std::fs::write(&temp_file_path, SERVICE_TEMPLATE.as_bytes())?;
+    offer_restart(&connection, name, false).await?;
 
-        // Prompt user to edit
-        let edit_success = edit_file(editor, &temp_file_path)?;
+    Ok(())
+}
 
-        if edit_success {
-            // Copy the content of the temporary file to the target location
-            std::fs::copy(&temp_file_path, &service_file_path)?;
+/// Returns the editor command split into the program and its arguments
+///
+/// # Arguments
+///
+/// * `editor` - Editor passed on the command line. Falls back to `$VISUAL`, `$EDITOR` and nano
+///
+fn get_editor(editor: Option<&str>) -> Vec<String> {
+    let editor = editor
+        .map(str::to_string)
+        .or_else(|| std::env::var("VISUAL").ok())
+        .or_else(|| std::env::var("EDITOR").ok())
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or(DEFAULT_EDITOR.to_string());
+
+    editor.split_whitespace().map(str::to_string).collect()
+}
+
+/// Removes the comments added by [`add_error_comments`]
+///
+/// # Arguments
+///
+/// * `contents` - Contents of the edited file
+///
+fn strip_error_comments(contents: &str) -> String {
+    let mut stripped: String = contents
+        .lines()
+        .filter(|line| !line.starts_with(ERROR_COMMENT_PREFIX))
+        .flat_map(|line| [line, "\n"])
+        .collect();
+
+    if !contents.ends_with('\n') {
+        stripped.pop();
+    }
+
+    stripped
+}
 
-            println!(
-                "Service file {} created.",
-                service_file_path.to_str().unwrap()
-            );
-        } else {
-            eprintln!("Create operation canceled. No changes were saved.");
+/// Adds a header explaining that the file was not saved and a comment above every line with
+/// an error
+///
+/// # Arguments
+///
+/// * `contents` - Contents of the edited file
+/// * `diagnostics` - Problems found in the file
+///
+fn add_error_comments(contents: &str, diagnostics: &[Diagnostic]) -> String {
+    let errors: Vec<&Diagnostic> = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .collect();
+    let line_count = contents.lines().count();
+
+    let mut annotated = format!(
+        "{ERROR_COMMENT_PREFIX}This file has errors and was not saved. Fix them and save, or \
+        quit without saving to discard your changes.\n"
+    );
+
+    // Errors not tied to a line of the file go in the header
+    for error in &errors {
+        if error.line == 0 || error.line > line_count {
+            annotated.push_str(&format!("{ERROR_COMMENT_PREFIX}error: {}\n", error.message));
         }
+    }
 
-        // Remove the temporary file
-        std::fs::remove_file(&temp_file_path)?;
+    for (index, line) in contents.lines().enumerate() {
+        for error in errors.iter().filter(|error| error.line == index + 1) {
+            annotated.push_str(&format!("{ERROR_COMMENT_PREFIX}error: {}\n", error.message));
+        }
+        annotated.push_str(line);
+        annotated.push('\n');
     }
 
-    Ok(())
+    annotated
 }
 
 /// Prompt the user to edit the file. Returns true if the file editor command exits successfully
@@ -80,12 +217,15 @@ pub fn handle_edit_service_file(
 ///
 /// # Args
 ///
-/// * `editor`
+/// * `editor` - Editor program followed by its arguments
 /// * `path`
 ///
-fn edit_file(editor: &str, path: &PathBuf) -> Result<bool, std::io::Error> {
+fn edit_file(editor: &[String], path: &Path) -> Result<bool, std::io::Error> {
     let orig_mod_time = std::fs::metadata(path)?.modified()?;
-    let edit_status = std::process::Command::new(editor).arg(path).status()?;
+    let edit_status = std::process::Command::new(&editor[0])
+        .args(&editor[1..])
+        .arg(path)
+        .status()?;
 
     let edited_mod_time = std::fs::metadata(path)?.modified()?;
 
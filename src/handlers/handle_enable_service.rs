@@ -1,8 +1,16 @@
 use crate::handlers::handle_show_status::handle_show_status;
-use crate::utils::service_actions::enable_service;
-use crate::utils::{service_names::get_full_service_name, systemd::ManagerProxy};
+use crate::utils::service_actions::{daemon_reload, enable_service};
+use crate::utils::{
+    logind::{enable_linger, is_lingering},
+    output::{is_table, print_message, print_result, ActionResult},
+    prompt::confirm,
+    scope::{connect, get_user_name, is_user},
+    service_names::get_full_service_name,
+    systemd::ManagerProxy,
+};
 
-/// Enables a service to start on boot
+/// Enables a service to start on boot. User services start on login instead, or on boot if
+/// lingering is enabled, which is offered.
 ///
 /// # Arguments
 ///
@@ -14,19 +22,51 @@ pub async fn handle_enable_service(
 ) -> Result<(), Box<dyn std::error::Error>> {
     let full_service_name = get_full_service_name(name);
 
-    let connection = zbus::Connection::system().await?;
+    let connection = connect().await?;
     let manager_proxy = ManagerProxy::new(&connection).await?;
 
-    enable_service(&manager_proxy, &full_service_name).await;
+    enable_service(&manager_proxy, &full_service_name).await?;
 
     // Reload necessary for UnitFileState to update
-    manager_proxy.reload().await?;
+    daemon_reload(&manager_proxy).await?;
 
-    println!("Enabled {name}");
+    print_result(ActionResult::new("enable", name, format!("Enabled {name}")));
 
-    if show_status {
+    if is_user() {
+        offer_linger().await?;
+    }
+
+    if show_status && is_table() {
         handle_show_status().await?;
     }
 
     Ok(())
 }
+
+/// Asks to enable lingering for the calling user unless it is on, so that user services start on
+/// boot and keep running after logout
+async fn offer_linger() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(user) = get_user_name() else {
+        return Ok(());
+    };
+
+    if is_lingering(&user) {
+        return Ok(());
+    }
+
+    if confirm(
+        "User services stop when you log out. Enable lingering so they start on boot and keep \
+        running?",
+        false,
+    )? {
+        enable_linger().await?;
+        print_message(&format!("Enabled lingering for {user}"));
+    } else {
+        print_message(&format!(
+            "{user} isn't lingering, so services stop on logout. Run `loginctl enable-linger` to \
+            keep them running"
+        ));
+    }
+
+    Ok(())
+}
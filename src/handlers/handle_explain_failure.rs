@@ -0,0 +1,413 @@
+use std::{path::Path, time::Duration};
+
+use serde::Serialize;
+use zbus::Connection;
+
+use crate::{
+    handlers::handle_show_logs::{
+        format_entry, get_current_boot_id, get_log_lines, LogFormat, DEFAULT_LOG_LINES,
+    },
+    utils::{
+        error::ServicerError,
+        find_binary_path::is_executable,
+        journal::JournalReader,
+        log_filter::{LogFilter, PriorityRange},
+        output::{output_format, print_document, OutputFormat},
+        process_status::{describe_exit, get_signal_name},
+        scope::connect,
+        service_names::{get_full_service_name, get_service_file_path, get_short_service_name},
+        systemd::{get_control_group, get_unit_path, ManagerProxy, ServiceProxy, UnitProxy},
+    },
+};
+
+/// Number of OOM killer messages in the diagnosis
+const OOM_LINES: usize = 5;
+
+/// Why a service failed, from its properties, files and logs
+#[derive(Serialize)]
+pub struct Diagnosis {
+    /// The short service name, excluding '.ser.service'
+    pub name: String,
+
+    /// Active state, eg. `failed`
+    pub active: String,
+
+    /// Sub-state, eg. `auto-restart`
+    pub sub_state: String,
+
+    /// Result of the last run, eg. `exit-code` or `oom-kill`
+    pub result: String,
+
+    /// How the main process last exited, eg. `exited with status 203 (EXEC)`
+    pub last_exit: Option<String>,
+
+    /// Automatic restarts since the unit was loaded
+    pub restarts: u32,
+
+    /// Whether systemd gave up restarting the service
+    pub start_limit_hit: bool,
+
+    /// Causes found, most important first
+    pub findings: Vec<Finding>,
+}
+
+/// One cause or piece of evidence
+#[derive(Serialize)]
+pub struct Finding {
+    /// What was checked: `result`, `restarts`, `exec`, `working-directory`, `oom` or `log`
+    pub kind: String,
+
+    pub message: String,
+}
+
+impl Finding {
+    fn new(kind: &str, message: String) -> Self {
+        Finding {
+            kind: kind.to_string(),
+            message,
+        }
+    }
+}
+
+/// Explain why a service failed or keeps restarting. Combines the result and exit status of the
+/// last run, restarts and the start limit, OOM killer messages, missing binaries or working
+/// directory, and the last error-priority log lines.
+///
+/// # Arguments
+///
+/// * `name` - The service name
+///
+pub async fn handle_explain_failure(name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let full_service_name = get_full_service_name(name);
+
+    if !get_service_file_path(&full_service_name)?.exists() {
+        return Err(ServicerError::NotFound(format!("service {full_service_name}")).into());
+    }
+
+    let connection = connect().await?;
+    let diagnosis = get_diagnosis(&connection, &full_service_name).await?;
+
+    match output_format() {
+        OutputFormat::Table => print_diagnosis(&full_service_name, &diagnosis),
+        OutputFormat::Csv => print_document("diagnosis", &diagnosis.findings)?,
+        _ => print_document("diagnosis", &[diagnosis])?,
+    }
+
+    Ok(())
+}
+
+/// Collects the findings of every check
+///
+/// # Arguments
+///
+/// * `connection` - zbus connection
+/// * `full_service_name` - Full name of the service, having '.ser.service' at the end
+///
+async fn get_diagnosis(
+    connection: &Connection,
+    full_service_name: &str,
+) -> Result<Diagnosis, ServicerError> {
+    let name = get_short_service_name(full_service_name);
+    let unit_path = get_unit_path(full_service_name);
+    let unit_proxy = UnitProxy::new(connection, unit_path.clone()).await?;
+    let service_proxy = ServiceProxy::new(connection, unit_path).await?;
+
+    let active = unit_proxy.active_state().await?;
+    let sub_state = unit_proxy.sub_state().await?;
+    let result = service_proxy.result().await.unwrap_or_default();
+    let exit_code = service_proxy.exec_main_code().await.unwrap_or(0);
+    let exit_status = service_proxy.exec_main_status().await.unwrap_or(0);
+    let last_exit = describe_exit(exit_code, exit_status);
+    let restarts = service_proxy.n_restarts().await.unwrap_or(0);
+
+    let mut findings = vec![];
+
+    if let Some(message) = explain_result(&result, last_exit.as_deref()) {
+        findings.push(Finding::new("result", message));
+    }
+    if exit_code == libc::CLD_EXITED {
+        if let Some(message) = explain_exit_status(exit_status) {
+            findings.push(Finding::new("result", message));
+        }
+    }
+
+    let start_limit_hit = result == "start-limit-hit";
+    if start_limit_hit {
+        let burst = unit_proxy.start_limit_burst().await.unwrap_or(0);
+        let interval = unit_proxy.start_limit_interval_usec().await.unwrap_or(0);
+        findings.push(Finding::new(
+            "restarts",
+            format!(
+                "It was started more than {burst} times within {}, so systemd stopped restarting \
+                it. Fix the cause, then run `ser start {name}`",
+                humantime::format_duration(Duration::from_micros(interval))
+            ),
+        ));
+    } else if sub_state == "auto-restart" {
+        let delay = service_proxy.restart_usec().await.unwrap_or(0);
+        findings.push(Finding::new(
+            "restarts",
+            format!(
+                "It is crash-looping: restarted {restarts} times, next start in {}",
+                humantime::format_duration(Duration::from_micros(delay))
+            ),
+        ));
+    } else if restarts > 0 {
+        findings.push(Finding::new(
+            "restarts",
+            format!("It was restarted automatically {restarts} times"),
+        ));
+    }
+
+    for (path, ..) in service_proxy.exec_start().await.unwrap_or_default() {
+        if let Some(message) = check_executable(&path) {
+            findings.push(Finding::new("exec", message));
+        }
+    }
+
+    let working_directory = service_proxy.working_directory().await.unwrap_or_default();
+    if let Some(message) = check_working_directory(&working_directory) {
+        findings.push(Finding::new("working-directory", message));
+    }
+
+    // The control group is cleared once the service stops. It is derived from the slice, which
+    // is under the control group of the user manager for user services.
+    let control_group = match service_proxy.control_group().await.unwrap_or_default() {
+        control_group if control_group.is_empty() => {
+            let manager_proxy = ManagerProxy::new(connection).await?;
+            get_control_group(
+                &manager_proxy.control_group().await.unwrap_or_default(),
+                &service_proxy.slice().await.unwrap_or_default(),
+                full_service_name,
+            )
+        }
+        control_group => control_group,
+    };
+    for line in get_oom_messages(&control_group) {
+        findings.push(Finding::new("oom", line));
+    }
+
+    let error_filter = LogFilter {
+        priority: Some(PriorityRange(0..=3)),
+        ..Default::default()
+    };
+    let mut logs = get_log_lines(&name, DEFAULT_LOG_LINES, &error_filter).unwrap_or_default();
+
+    // Output of most programs is logged as `info`, so show the latest lines instead
+    if logs.is_empty() {
+        logs = get_log_lines(&name, DEFAULT_LOG_LINES, &LogFilter::default()).unwrap_or_default();
+    }
+    for line in logs {
+        findings.push(Finding::new("log", line));
+    }
+
+    Ok(Diagnosis {
+        name,
+        active,
+        sub_state,
+        result,
+        last_exit,
+        restarts,
+        start_limit_hit,
+        findings,
+    })
+}
+
+/// Explains the `Result` property of a service. `None` if the last run succeeded.
+///
+/// # Arguments
+///
+/// * `result` - Eg. `exit-code`
+/// * `last_exit` - How the main process last exited
+///
+fn explain_result(result: &str, last_exit: Option<&str>) -> Option<String> {
+    let last_exit = last_exit.unwrap_or("exited");
+
+    let message = match result {
+        "" | "success" => return None,
+        "exit-code" => format!("The main process {last_exit}"),
+        "signal" => format!("The main process was {last_exit}"),
+        "core-dump" => format!("The main process crashed and {last_exit}"),
+        "timeout" => "It didn't start or stop within its timeout, see TimeoutStartSec= and \
+            TimeoutStopSec="
+            .to_string(),
+        "watchdog" => "It stopped sending watchdog keep-alives, see WatchdogSec=".to_string(),
+        "oom-kill" => "The OOM killer killed a process of the service. Reduce its memory use or \
+            raise MemoryMax="
+            .to_string(),
+        "start-limit-hit" => "It failed to start too often in a short time".to_string(),
+        "resources" => "systemd couldn't set up the service, eg. because of a missing binary, \
+            working directory or user"
+            .to_string(),
+        "protocol" => "It didn't follow the protocol of its Type=, eg. a forking service \
+            didn't write its PID file"
+            .to_string(),
+        "exec-condition" => "ExecCondition= failed, so the service was skipped".to_string(),
+        result => format!("The last run failed with result {result}"),
+    };
+
+    Some(message)
+}
+
+/// Explains exit statuses with a known cause, eg. the 200+ statuses systemd uses when it
+/// can't set up the process
+///
+/// # Arguments
+///
+/// * `status` - `ExecMainStatus`
+///
+fn explain_exit_status(status: i32) -> Option<String> {
+    let message = match status {
+        126 => "The command was found but isn't executable",
+        127 => "The command or its interpreter wasn't found",
+        200 => "systemd couldn't change to the WorkingDirectory=",
+        203 => "systemd couldn't execute the ExecStart= binary or its interpreter",
+        204 => "systemd ran out of memory while starting the service",
+        209 => "systemd couldn't set up standard output, see StandardOutput=",
+        216 => "The Group= doesn't exist",
+        217 => "The User= doesn't exist",
+        226 => {
+            "systemd couldn't set up the file system namespace, eg. a path in ReadWritePaths= \
+            doesn't exist"
+        }
+        status if status > 128 && status - 128 <= 31 => {
+            return Some(format!(
+                "Status {status} usually means a shell or wrapper saw the program die of \
+                signal {}",
+                get_signal_name(status - 128).unwrap_or("unknown")
+            ))
+        }
+        _ => return None,
+    };
+
+    Some(message.to_string())
+}
+
+/// Checks that an `ExecStart=` binary exists and is executable
+///
+/// # Arguments
+///
+/// * `path` - Absolute path of the binary
+///
+fn check_executable(path: &str) -> Option<String> {
+    let path = Path::new(path);
+
+    if !path.exists() {
+        Some(format!("ExecStart binary {} doesn't exist", path.display()))
+    } else if !is_executable(path) {
+        Some(format!(
+            "ExecStart {} isn't an executable file",
+            path.display()
+        ))
+    } else {
+        None
+    }
+}
+
+/// Checks that the `WorkingDirectory=` of a service exists
+///
+/// # Arguments
+///
+/// * `working_directory` - The property as reported by systemd. Optional directories start with
+///   `!` and `~` is the home directory of the user
+///
+fn check_working_directory(working_directory: &str) -> Option<String> {
+    if working_directory.is_empty()
+        || working_directory.starts_with('!')
+        || working_directory.starts_with('~')
+    {
+        return None;
+    }
+
+    if Path::new(working_directory).is_dir() {
+        None
+    } else {
+        Some(format!(
+            "WorkingDirectory {working_directory} doesn't exist"
+        ))
+    }
+}
+
+/// Kernel messages of the current boot about the OOM killer acting on a control group. The
+/// `oom-kill` line names the group and PID, the `Killed process` line that follows the PID.
+///
+/// # Arguments
+///
+/// * `control_group` - Eg. `/system.slice/hello.ser.service`
+///
+fn get_oom_messages(control_group: &str) -> Vec<String> {
+    let filter = LogFilter {
+        boot_id: get_current_boot_id().ok(),
+        ..Default::default()
+    };
+    let Ok(entries) = JournalReader::for_kernel().read_tail(usize::MAX, &filter) else {
+        return vec![];
+    };
+
+    let memcg = format!("memcg={control_group}");
+    let mut pids = vec![];
+    let mut lines = vec![];
+
+    for entry in &entries {
+        let message = entry.field("MESSAGE").unwrap_or_default();
+
+        if message.starts_with("oom-kill:") && message.contains(&memcg) {
+            if let Some(pid) = message
+                .split(',')
+                .find_map(|field| field.strip_prefix("pid="))
+            {
+                pids.push(format!("Killed process {pid} "));
+            }
+            lines.push(format_entry(entry, LogFormat::Short));
+        } else if pids.iter().any(|pid| message.contains(pid.as_str())) {
+            lines.push(format_entry(entry, LogFormat::Short));
+        }
+    }
+
+    let skip = lines.len().saturating_sub(OOM_LINES);
+    lines.split_off(skip)
+}
+
+/// Prints the diagnosis as sentences, followed by the OOM killer messages and logs
+///
+/// # Arguments
+///
+/// * `full_service_name` - Full name of the service, having '.ser.service' at the end
+/// * `diagnosis`
+///
+fn print_diagnosis(full_service_name: &str, diagnosis: &Diagnosis) {
+    println!(
+        "{full_service_name} is {} ({})",
+        diagnosis.active, diagnosis.sub_state
+    );
+
+    let causes: Vec<&Finding> = diagnosis
+        .findings
+        .iter()
+        .filter(|finding| finding.kind != "oom" && finding.kind != "log")
+        .collect();
+
+    if causes.is_empty() {
+        println!("\nNo failure found in its last run");
+    } else {
+        println!();
+        for finding in causes {
+            println!("  - {}", finding.message);
+        }
+    }
+
+    for (kind, title) in [("oom", "OOM killer"), ("log", "Logs")] {
+        let mut lines = diagnosis
+            .findings
+            .iter()
+            .filter(|finding| finding.kind == kind)
+            .peekable();
+
+        if lines.peek().is_some() {
+            println!("\n{title}:");
+        }
+        for finding in lines {
+            println!("  {}", finding.message);
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::{collections::BTreeMap, fs::File, io::Write, path::PathBuf, time::SystemTime};
+
+use zbus::zvariant::OwnedObjectPath;
+
+use crate::{
+    handlers::handle_show_logs::{format_entry, LogFormat, LogRecord},
+    utils::{
+        error::ServicerError,
+        journal::JournalReader,
+        log_filter::LogFilter,
+        output::{print_result, ActionResult},
+        scope::connect,
+        service_names::{get_full_service_name, get_service_file_path},
+        systemd::{get_all_properties, get_unit_path},
+    },
+};
+
+/// zstd compression level of the archive
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// Write a self-contained `.tar.zst` archive of a service for debugging elsewhere. It holds
+/// `entries.jsonl` and `entries.log` with the journal entries of the service, its .service file
+/// and `properties.json` with a snapshot of its unit properties.
+///
+/// # Arguments
+///
+/// * `name` - The service name
+/// * `since` - Earliest entry time in microseconds since the Unix epoch
+/// * `until` - Latest entry time in microseconds since the Unix epoch
+/// * `file` - Path of the archive. Defaults to `<name>-logs.tar.zst`
+///
+pub async fn handle_export_logs(
+    name: &str,
+    since: Option<u64>,
+    until: Option<u64>,
+    file: Option<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let full_service_name = get_full_service_name(name);
+    let service_file_path = get_service_file_path(&full_service_name)?;
+
+    let mut reader =
+        JournalReader::for_units(std::slice::from_ref(&full_service_name)).map_err(|error| {
+            ServicerError::InvalidName {
+                name: name.to_string(),
+                reason: error.msg.to_string(),
+            }
+        })?;
+
+    let filter = LogFilter {
+        since,
+        until,
+        ..Default::default()
+    };
+    let entries = reader.read_tail(usize::MAX, &filter)?;
+
+    if entries.is_empty() && !service_file_path.exists() {
+        return Err(ServicerError::NotFound(format!("service {full_service_name}")).into());
+    }
+
+    let mut jsonl = String::new();
+    let mut log = String::new();
+    for entry in &entries {
+        let record = LogRecord::new(entry, Some(name.to_string()));
+        jsonl.push_str(&serde_json::to_string(&record)?);
+        jsonl.push('\n');
+        log.push_str(&format_entry(entry, LogFormat::ShortIso));
+        log.push('\n');
+    }
+
+    let properties = get_unit_properties(&full_service_name).await;
+
+    let path = file.unwrap_or_else(|| PathBuf::from(format!("{name}-logs.tar.zst")));
+    let encoder = zstd::Encoder::new(File::create(&path)?, COMPRESSION_LEVEL)?.auto_finish();
+    let mut archive = tar::Builder::new(encoder);
+
+    let directory = format!("{name}-logs");
+    let mtime = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)?
+        .as_secs();
+
+    let mut append = |file_name: &str, data: &[u8]| -> std::io::Result<()> {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(mtime);
+        header.set_cksum();
+        archive.append_data(&mut header, format!("{directory}/{file_name}"), data)
+    };
+
+    append("entries.jsonl", jsonl.as_bytes())?;
+    append("entries.log", log.as_bytes())?;
+    append(
+        "properties.json",
+        serde_json::to_string_pretty(&properties)?.as_bytes(),
+    )?;
+
+    // The .service file may be gone while its logs remain
+    if let Ok(contents) = std::fs::read(&service_file_path) {
+        append(&full_service_name, &contents)?;
+    }
+
+    archive.into_inner()?.flush()?;
+
+    print_result(
+        ActionResult::new(
+            "export",
+            name,
+            format!(
+                "Exported {} entries of {full_service_name} to {}",
+                entries.len(),
+                path.display()
+            ),
+        )
+        .with_path(&path.to_string_lossy()),
+    );
+
+    Ok(())
+}
+
+/// Reads the properties of the unit and service interfaces, sorted by name. Empty if the unit is
+/// not loaded or systemd can't be reached.
+///
+/// # Arguments
+///
+/// * `full_service_name` - Full name of the service, having '.ser.service' at the end
+///
+async fn get_unit_properties(full_service_name: &str) -> BTreeMap<String, serde_json::Value> {
+    let mut properties = BTreeMap::new();
+
+    let Ok(connection) = connect().await else {
+        return properties;
+    };
+    let Ok(unit_path) = OwnedObjectPath::try_from(get_unit_path(full_service_name)) else {
+        return properties;
+    };
+
+    for interface in [
+        "org.freedesktop.systemd1.Unit",
+        "org.freedesktop.systemd1.Service",
+    ] {
+        if let Ok(values) = get_all_properties(&connection, unit_path.clone(), interface).await {
+            properties.extend(values.into_iter().filter_map(|(key, value)| {
+                serde_json::to_value(&value).ok().map(|value| (key, value))
+            }));
+        }
+    }
+
+    properties
+}
@@ -0,0 +1,44 @@
+use std::path::Path;
+
+use crate::{
+    handlers::handle_apply_manifest::apply_imported,
+    utils::pm2::{get_default_dump_path, import_pm2},
+};
+
+/// Creates services for the apps of a pm2 process list or ecosystem file, the same way as `ser
+/// apply`. Options which are not translated are reported.
+///
+/// # Arguments
+///
+/// * `file` - `dump.pm2` written by `pm2 save`, or an ecosystem file in JSON or YAML. Defaults to
+///   the process list of the user who called sudo
+/// * `env_name` - Use `env_<name>` of each app, like `pm2 start --env <name>`
+/// * `start` - Start the services. From a process list, only apps which were online are started
+/// * `enable` - Enable the services
+/// * `dry_run` - Only print the plan
+///
+pub async fn handle_import_pm2(
+    file: Option<&Path>,
+    env_name: Option<&str>,
+    start: bool,
+    enable: bool,
+    dry_run: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let default_path = get_default_dump_path();
+    let path = file.unwrap_or(&default_path);
+
+    if file.is_none() && !path.exists() {
+        return Err(format!(
+            "No pm2 process list at {}. Run `pm2 save` first, or pass an ecosystem file",
+            path.display()
+        )
+        .into());
+    }
+
+    let imported = import_pm2(path, env_name, start, enable)?;
+    if imported.services.is_empty() {
+        return Err(format!("No pm2 apps found in {}", path.display()).into());
+    }
+
+    apply_imported(&imported, path, dry_run).await
+}
@@ -0,0 +1,32 @@
+use std::path::Path;
+
+use crate::{handlers::handle_apply_manifest::apply_imported, utils::procfile::import_procfile};
+
+/// Creates a service for each process type of a Procfile, the same way as `ser apply`. Services
+/// are named `<prefix>-<process type>` and get a `PORT` like foreman gives them.
+///
+/// # Arguments
+///
+/// * `file` - The Procfile
+/// * `env_file` - File with variables for every process. Defaults to `.env` next to the Procfile
+/// * `prefix` - Prefix of the service names. Defaults to the name of the Procfile's directory.
+///   Empty for no prefix.
+/// * `start` - Start the services
+/// * `enable` - Enable the services
+/// * `dry_run` - Only print the plan
+///
+pub async fn handle_import_procfile(
+    file: &Path,
+    env_file: Option<&Path>,
+    prefix: Option<&str>,
+    start: bool,
+    enable: bool,
+    dry_run: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let imported = import_procfile(file, env_file, prefix, start, enable)?;
+    if imported.services.is_empty() {
+        return Err(format!("No processes found in {}", file.display()).into());
+    }
+
+    apply_imported(&imported, file, dry_run).await
+}
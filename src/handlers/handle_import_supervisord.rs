@@ -0,0 +1,40 @@
+use std::path::Path;
+
+use crate::{
+    handlers::handle_apply_manifest::apply_imported,
+    utils::supervisord::{get_default_config_path, import_supervisord},
+};
+
+/// Creates a service for each process of the programs in a supervisord configuration, the same
+/// way as `ser apply`. Options which are not translated are reported.
+///
+/// # Arguments
+///
+/// * `file` - The configuration. Defaults to the one supervisord would read
+/// * `prefix` - Prefix of the service names. Defaults to the group of each program, if it's in
+///   one. Empty for no prefix.
+/// * `start` - Start the programs having `autostart`, which is the default
+/// * `enable` - Enable the programs having `autostart`
+/// * `dry_run` - Only print the plan
+///
+pub async fn handle_import_supervisord(
+    file: Option<&Path>,
+    prefix: Option<&str>,
+    start: bool,
+    enable: bool,
+    dry_run: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let default_path = get_default_config_path();
+    let Some(path) = file.or(default_path.as_deref()) else {
+        return Err(
+            "No supervisord.conf found in the current directory, /etc or /etc/supervisor".into(),
+        );
+    };
+
+    let imported = import_supervisord(path, prefix, start, enable)?;
+    if imported.services.is_empty() {
+        return Err(format!("No programs found in {}", path.display()).into());
+    }
+
+    apply_imported(&imported, path, dry_run).await
+}
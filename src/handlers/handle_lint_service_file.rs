@@ -0,0 +1,88 @@
+use crate::utils::{
+    error::ServicerError,
+    output::{is_table, print_document},
+    service_names::{get_full_service_name, get_service_file_path, get_servicer_services},
+    unit_lint::{lint_unit_file, Diagnostic, Severity},
+};
+
+/// Check .service files for mistakes such as unknown keys, relative `ExecStart=` paths, missing
+/// working directories or users, conflicting `Restart=` and `Type=` and missing `WantedBy=`
+/// targets. Prints `file:line: severity: message` diagnostics and fails if any is an error.
+///
+/// # Arguments
+///
+/// * `name` - The service name. Every servicer service if `None`
+///
+pub fn handle_lint_service_file(name: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let full_service_names = match name {
+        Some(name) => {
+            let full_service_name = get_full_service_name(name);
+
+            if !get_service_file_path(&full_service_name)?.exists() {
+                return Err(ServicerError::NotFound(format!("service {full_service_name}")).into());
+            }
+
+            vec![full_service_name]
+        }
+        None => {
+            let mut services = get_servicer_services()?;
+            services.sort();
+            services
+        }
+    };
+
+    let mut diagnostics: Vec<Diagnostic> = vec![];
+
+    for full_service_name in &full_service_names {
+        let path = get_service_file_path(full_service_name)?;
+        let contents = std::fs::read_to_string(&path)?;
+
+        diagnostics.extend(lint_unit_file(&path.to_string_lossy(), &contents));
+    }
+
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .count();
+    let warnings = diagnostics.len() - errors;
+
+    if is_table() {
+        for diagnostic in &diagnostics {
+            println!("{diagnostic}");
+        }
+
+        let files = plural(full_service_names.len(), "file");
+        if diagnostics.is_empty() {
+            println!("No problems found in {files}");
+        } else {
+            println!(
+                "{} and {} in {files}",
+                plural(errors, "error"),
+                plural(warnings, "warning")
+            );
+        }
+    } else {
+        print_document("lint", &diagnostics)?;
+    }
+
+    if errors > 0 {
+        return Err(format!("{} found", plural(errors, "error")).into());
+    }
+
+    Ok(())
+}
+
+/// Formats a count with a noun, eg. `1 error` or `2 errors`
+///
+/// # Arguments
+///
+/// * `count`
+/// * `noun` - Singular noun
+///
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("{count} {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
+}
@@ -1,11 +1,14 @@
 use cli_table::{Table, WithTitle};
+use serde::Serialize;
 
 use crate::utils::{
+    error::ServicerError,
+    output::{is_table, print_document, print_message},
     service_names::{get_full_service_name, get_service_file_path},
     systemd::get_unit_path,
 };
 
-#[derive(Table, Clone)]
+#[derive(Table, Serialize, Clone)]
 pub struct PathStatus {
     // The file name
     name: String,
@@ -25,15 +28,15 @@ pub fn handle_print_paths(name: &str) -> Result<(), Box<dyn std::error::Error>>
     let mut path_details = Vec::<PathStatus>::new();
 
     let full_service_name = get_full_service_name(name);
-    let service_file_path = get_service_file_path(&full_service_name);
+    let service_file_path = get_service_file_path(&full_service_name)?;
 
     if service_file_path.exists() {
-        println!("Paths for {}:", full_service_name);
+        print_message(&format!("Paths for {}:", full_service_name));
 
         // 1. Service file path
         path_details.push(PathStatus {
             name: "Service file".to_string(),
-            path: service_file_path.to_str().unwrap().to_string(),
+            path: service_file_path.to_string_lossy().to_string(),
         });
 
         // 2. Unit file
@@ -42,9 +45,13 @@ pub fn handle_print_paths(name: &str) -> Result<(), Box<dyn std::error::Error>>
             path: get_unit_path(&full_service_name),
         });
 
-        cli_table::print_stdout(path_details.with_title())?;
+        if is_table() {
+            cli_table::print_stdout(path_details.with_title())?;
+        } else {
+            print_document("paths", &path_details)?;
+        }
     } else {
-        eprintln!("No such service {}", full_service_name);
+        return Err(ServicerError::NotFound(format!("service {full_service_name}")).into());
     }
 
     Ok(())
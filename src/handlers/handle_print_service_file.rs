@@ -1,4 +1,22 @@
-use crate::utils::service_names::{get_full_service_name, get_service_file_path};
+use serde::Serialize;
+
+use crate::utils::{
+    error::ServicerError,
+    output::{is_table, print_document},
+    service_names::{get_full_service_name, get_service_file_path},
+};
+
+#[derive(Serialize)]
+struct ServiceFile {
+    /// The short service name
+    service: String,
+
+    /// Path of the .service file
+    path: String,
+
+    /// Contents of the .service file
+    contents: String,
+}
 
 /// Print contents of a .service file
 ///
@@ -8,7 +26,7 @@ use crate::utils::service_names::{get_full_service_name, get_service_file_path};
 ///
 pub fn handle_print_service_file(name: &str) -> Result<(), Box<dyn std::error::Error>> {
     let full_service_name = get_full_service_name(name);
-    let service_file_path = get_service_file_path(&full_service_name);
+    let service_file_path = get_service_file_path(&full_service_name)?;
 
     if service_file_path.exists() {
         // Open the file using Tokio's File API
@@ -16,13 +34,22 @@ pub fn handle_print_service_file(name: &str) -> Result<(), Box<dyn std::error::E
 
         // Convert the buffer to a UTF-8 string and print it
         let contents = String::from_utf8(buffer)?;
-        println!(
-            "Reading {}:\n{}",
-            service_file_path.to_str().unwrap(),
-            contents
-        );
+        if is_table() {
+            println!("Reading {}:\n{}", service_file_path.display(), contents);
+        } else {
+            print_document(
+                "service_file",
+                &[ServiceFile {
+                    service: name.to_string(),
+                    path: service_file_path.to_string_lossy().to_string(),
+                    contents,
+                }],
+            )?;
+        }
     } else {
-        eprintln!("{}: No such file", service_file_path.to_str().unwrap());
+        return Err(
+            ServicerError::NotFound(service_file_path.to_string_lossy().to_string()).into(),
+        );
     }
 
     Ok(())
@@ -0,0 +1,54 @@
+use std::io::IsTerminal;
+
+use crate::utils::{
+    output::{print_message, print_result, ActionResult},
+    prompt::confirm,
+    trash::list_trash,
+};
+
+/// Permanently removes deleted services from the trash
+///
+/// # Arguments
+///
+/// * `name` - Only remove entries of this service. Every entry if `None`
+/// * `yes` - Remove without asking for confirmation
+///
+pub fn handle_purge_trash(name: Option<&str>, yes: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let entries: Vec<_> = list_trash()?
+        .into_iter()
+        .filter(|entry| name.is_none_or(|name| entry.metadata.name == name))
+        .collect();
+
+    if entries.is_empty() {
+        print_message("Nothing to purge");
+        return Ok(());
+    }
+
+    if !yes {
+        if !std::io::stdin().is_terminal() {
+            return Err("Not purging the trash without confirmation. Pass --yes".into());
+        }
+
+        let question = format!(
+            "Permanently remove {} deleted service{} from the trash?",
+            entries.len(),
+            if entries.len() == 1 { "" } else { "s" }
+        );
+        if !confirm(&question, false)? {
+            print_message("Purge canceled.");
+            return Ok(());
+        }
+    }
+
+    for entry in entries {
+        std::fs::remove_dir_all(&entry.path)?;
+
+        let path = entry.path.to_string_lossy();
+        print_result(
+            ActionResult::new("purge", &entry.metadata.name, format!("Removed {path}"))
+                .with_path(&path),
+        );
+    }
+
+    Ok(())
+}
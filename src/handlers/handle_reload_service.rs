@@ -1,6 +1,9 @@
 use crate::{
     utils::service_names::get_full_service_name,
     utils::{
+        error::ServicerError,
+        output::{is_table, print_result, ActionResult},
+        scope::connect,
         service_actions::reload_service,
         systemd::{get_active_state, ManagerProxy},
     },
@@ -14,26 +17,46 @@ use super::handle_show_status::handle_show_status;
 /// # Arguments
 ///
 /// * `name` - The service name
+/// * `timeout` - Seconds to wait for the reload job to finish
+/// * `show_status` - Print the status table after reloading
 ///
 pub async fn handle_reload_service(
     name: &str,
+    timeout: u64,
     show_status: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let connection = zbus::Connection::system().await?;
+    let connection = connect().await?;
     let manager_proxy = ManagerProxy::new(&connection).await?;
 
     let full_service_name = get_full_service_name(name);
 
-    let active_state = get_active_state(&connection, &full_service_name).await;
+    let active_state = get_active_state(&connection, &full_service_name).await?;
 
     if active_state == "reloading" {
-        eprintln!("No-op. Service {full_service_name} is already {active_state}");
+        print_result(ActionResult::new(
+            "reload",
+            name,
+            format!("No-op. Service {full_service_name} is already {active_state}"),
+        ));
     } else {
-        reload_service(&manager_proxy, &full_service_name).await;
-        println!("service reloaded: {name}");
+        let outcome = reload_service(&manager_proxy, &full_service_name, timeout).await?;
+
+        if !outcome.reached(&["active"]) {
+            return Err(ServicerError::JobFailed {
+                name: name.to_string(),
+                action: "reload".to_string(),
+                outcome: outcome.to_string(),
+            }
+            .into());
+        }
+
+        print_result(
+            ActionResult::new("reload", name, format!("service reloaded: {name}"))
+                .with_job(&outcome, true),
+        );
     };
 
-    if show_status {
+    if show_status && is_table() {
         handle_show_status().await?;
     }
 
@@ -1,13 +1,16 @@
 use crate::{
     handlers::handle_show_status::handle_show_status,
     utils::{
-        service_names::{get_full_service_name, get_service_file_path},
+        error::ServicerError,
+        history::record_change,
+        output::{is_table, print_result, ActionResult},
+        scope::connect,
+        service_actions::DEFAULT_JOB_TIMEOUT,
+        service_names::{get_full_service_name, get_service_file_path, validate_service_name},
         systemd::{get_active_state, get_unit_file_state},
     },
 };
 
-use zbus::Connection;
-
 use super::{
     handle_delete_service::handle_delete_service, handle_enable_service::handle_enable_service,
     handle_start_service::handle_start_service,
@@ -27,35 +30,61 @@ pub async fn handle_rename_service(
     name: &str,
     new_name: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    validate_service_name(new_name)?;
+
     // Create new service file
     let full_service_name = get_full_service_name(name);
-    let service_file_path = get_service_file_path(&full_service_name);
-    let service_file_path_str = service_file_path.to_str().unwrap().to_string();
+    let service_file_path = get_service_file_path(&full_service_name)?;
+    let service_file_path_str = service_file_path.to_string_lossy().to_string();
 
     let new_full_service_name = get_full_service_name(new_name);
-    let new_service_file_path = get_service_file_path(&new_full_service_name);
-    let new_service_file_path_str = new_service_file_path.to_str().unwrap().to_string();
+    let new_service_file_path = get_service_file_path(&new_full_service_name)?;
+    let new_service_file_path_str = new_service_file_path.to_string_lossy().to_string();
+
+    if !service_file_path.exists() {
+        return Err(ServicerError::NotFound(format!("service {full_service_name}")).into());
+    }
+
+    if new_service_file_path.exists() {
+        return Err(ServicerError::UnitExists {
+            name: new_name.to_string(),
+            path: new_service_file_path_str,
+        }
+        .into());
+    }
 
     // Copy .service file
     std::fs::copy(service_file_path_str, new_service_file_path_str)?;
+    record_change(
+        new_name,
+        None,
+        &std::fs::read_to_string(&new_service_file_path)?,
+    )?;
 
     // Read active and unit state of current service
-    let connection = Connection::system().await?;
-    let active_state: String = get_active_state(&connection, &full_service_name).await;
-    let unit_state = get_unit_file_state(&connection, &full_service_name).await;
+    let connection = connect().await?;
+    let active_state: String = get_active_state(&connection, &full_service_name).await?;
+    let unit_state = get_unit_file_state(&connection, &full_service_name).await?;
 
-    // Delete existing service
-    handle_delete_service(name, false).await?;
+    // Delete existing service. Its daemon-reload also loads the new .service file
+    handle_delete_service(name, true, false, false).await?;
 
     if active_state == "active" {
-        handle_start_service(new_name, false).await?;
+        handle_start_service(new_name, DEFAULT_JOB_TIMEOUT, false).await?;
     }
 
     if unit_state == "enabled" {
         handle_enable_service(new_name, false).await?;
     }
 
-    handle_show_status().await?;
+    print_result(
+        ActionResult::new("rename", new_name, format!("Renamed {name} to {new_name}"))
+            .with_path(&new_service_file_path.to_string_lossy()),
+    );
+
+    if is_table() {
+        handle_show_status().await?;
+    }
 
     Ok(())
 }
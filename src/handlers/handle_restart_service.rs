@@ -0,0 +1,157 @@
+use crate::{
+    handlers::handle_show_status::handle_show_status,
+    utils::{
+        error::ServicerError,
+        output::{is_table, print_result, ActionResult},
+        prompt::confirm,
+        scope::connect,
+        service_actions::{restart_service, DEFAULT_JOB_TIMEOUT},
+        service_names::{get_full_service_name, get_servicer_services, get_short_service_name},
+        systemd::{get_active_state, is_running, ManagerProxy},
+    },
+};
+
+/// Restarts one or more services. Services are restarted one after another and the status table
+/// is printed once at the end. Returns an error if any service did not come back up.
+///
+/// # Arguments
+///
+/// * `names` - The service names
+/// * `all` - Restart every servicer service. `names` is ignored
+/// * `if_running` - Leave services which aren't running alone instead of starting them
+/// * `timeout` - Seconds to wait for each restart job to finish
+/// * `show_status` - Print the status table after restarting
+///
+pub async fn handle_restart_service(
+    names: &[String],
+    all: bool,
+    if_running: bool,
+    timeout: u64,
+    show_status: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let connection = connect().await?;
+    let manager_proxy = ManagerProxy::new(&connection).await?;
+
+    let full_service_names: Vec<String> = if all {
+        get_servicer_services()?
+    } else {
+        names
+            .iter()
+            .map(|name| get_full_service_name(name))
+            .collect()
+    };
+
+    let mut failed = vec![];
+
+    for full_service_name in full_service_names {
+        let name = get_short_service_name(&full_service_name);
+
+        if if_running {
+            let active_state = match get_active_state(&connection, &full_service_name).await {
+                Ok(active_state) => active_state,
+                Err(error) => {
+                    print_failure(&name, &error);
+                    failed.push(name);
+                    continue;
+                }
+            };
+
+            if !is_running(&active_state) {
+                print_result(ActionResult::new(
+                    "restart",
+                    &name,
+                    format!("Skipped {name}. Service is {active_state}"),
+                ));
+                continue;
+            }
+        }
+
+        let outcome =
+            match restart_service(&manager_proxy, &full_service_name, if_running, timeout).await {
+                Ok(outcome) => outcome,
+                Err(error) => {
+                    print_failure(&name, &error);
+                    failed.push(name);
+                    continue;
+                }
+            };
+
+        if outcome.reached(&["active"]) {
+            print_result(
+                ActionResult::new("restart", &name, format!("service restarted: {name}"))
+                    .with_job(&outcome, true),
+            );
+        } else {
+            print_result(
+                ActionResult::new(
+                    "restart",
+                    &name,
+                    format!("Failed to restart {name}: {outcome}"),
+                )
+                .with_job(&outcome, false),
+            );
+            failed.push(name);
+        }
+    }
+
+    if show_status && is_table() {
+        handle_show_status().await?;
+    }
+
+    if !failed.is_empty() {
+        return Err(ServicerError::JobFailed {
+            name: failed.join(", "),
+            action: "restart".to_string(),
+            outcome: "service did not come back up".to_string(),
+        }
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Reports a service whose restart failed with an error, so the others are still restarted
+///
+/// # Arguments
+///
+/// * `name` - The service name
+/// * `error` - The error
+///
+fn print_failure(name: &str, error: &ServicerError) {
+    print_result(ActionResult {
+        success: false,
+        ..ActionResult::new(
+            "restart",
+            name,
+            format!("Failed to restart {name}: {error}"),
+        )
+    });
+}
+
+/// Restarts a service after its .service file changed, so the changes take effect. Only running
+/// services are restarted, after asking unless `restart` is set.
+///
+/// # Arguments
+///
+/// * `connection` - zbus connection
+/// * `name` - The service name
+/// * `restart` - Restart without asking
+///
+pub async fn offer_restart(
+    connection: &zbus::Connection,
+    name: &str,
+    restart: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let active_state = get_active_state(connection, &get_full_service_name(name)).await?;
+
+    if !is_running(&active_state) {
+        return Ok(());
+    }
+
+    if restart || confirm(&format!("Restart {name} to apply the changes?"), false)? {
+        handle_restart_service(&[name.to_string()], false, true, DEFAULT_JOB_TIMEOUT, false)
+            .await?;
+    }
+
+    Ok(())
+}
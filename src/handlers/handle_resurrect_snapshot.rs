@@ -0,0 +1,276 @@
+use std::{collections::HashMap, io::IsTerminal, path::Path};
+
+use crate::{
+    handlers::{
+        handle_enable_service::handle_enable_service, handle_show_status::handle_show_status,
+        handle_start_service::handle_start_service,
+    },
+    utils::{
+        find_binary_path::{find_interpreter, is_executable},
+        history::record_change,
+        output::{is_table, print_message, print_result, ActionResult},
+        prompt::{ask, confirm},
+        scope::{connect, get_unit_directory, is_user, user_exists},
+        service_actions::{daemon_reload, DEFAULT_JOB_TIMEOUT},
+        service_names::{get_full_service_name, get_service_file_path, validate_service_name},
+        snapshot::{get_default_snapshot_path, read_snapshot, write_drop_ins},
+        systemd::ManagerProxy,
+        unit_file::{quote_word, split_exec_start, UnitFile},
+    },
+};
+
+/// Recreates the services of a snapshot written by `ser save`, then enables and starts them as
+/// they were. `User=` and interpreter paths which don't exist on this host are rewritten after
+/// asking. Without a terminal to ask on they are kept with a warning unless `yes` is set.
+///
+/// # Arguments
+///
+/// * `file` - The snapshot. Defaults to `dump.json` in the state directory
+/// * `yes` - Accept suggested rewrites and overwrite services with other contents without asking
+///
+pub async fn handle_resurrect_snapshot(
+    file: Option<&Path>,
+    yes: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let default_path = get_default_snapshot_path();
+    let snapshot = read_snapshot(file.unwrap_or(&default_path))?;
+
+    print_message(&format!(
+        "Resurrecting {} services saved on {} at {}",
+        snapshot.services.len(),
+        snapshot.hostname,
+        snapshot.saved_at
+    ));
+
+    let mut restored = vec![];
+
+    for service in &snapshot.services {
+        let name = service.name.as_str();
+        validate_service_name(name)?;
+
+        let full_service_name = get_full_service_name(name);
+        let service_file_path = get_service_file_path(&full_service_name)?;
+        let path = service_file_path.to_string_lossy().to_string();
+
+        let mut unit_file: UnitFile = service
+            .service_file
+            .parse()
+            .map_err(|error| format!("Invalid .service file of {name} in the snapshot: {error}"))?;
+        let user = rewrite_user(&mut unit_file, name, yes)?;
+        rewrite_interpreter(&mut unit_file, name, user.as_deref(), yes)?;
+
+        if let Some(directory) = unit_file.get("Service", "WorkingDirectory") {
+            if !Path::new(directory.trim_start_matches('-')).is_dir() {
+                print_message(&format!(
+                    "Warning: WorkingDirectory {directory} of {name} doesn't exist on this host"
+                ));
+            }
+        }
+
+        let contents = unit_file.to_string();
+        let previous = std::fs::read_to_string(&service_file_path).ok();
+
+        if let Some(previous) = &previous {
+            if *previous != contents
+                && !yes
+                && !confirm(
+                    &format!("{path} exists with other contents. Overwrite it?"),
+                    false,
+                )?
+            {
+                print_result(ActionResult {
+                    success: false,
+                    ..ActionResult::new("resurrect", name, format!("Skipped {name}, {path} exists"))
+                });
+                continue;
+            }
+        }
+
+        std::fs::create_dir_all(get_unit_directory())?;
+        std::fs::write(&service_file_path, &contents)?;
+        write_drop_ins(&full_service_name, &service.drop_ins)?;
+        record_change(name, previous.as_deref(), &contents)?;
+
+        print_result(
+            ActionResult::new("resurrect", name, format!("Restored {path}")).with_path(&path),
+        );
+        restored.push(service);
+    }
+
+    if restored.is_empty() {
+        return Ok(());
+    }
+
+    let connection = connect().await?;
+    let manager_proxy = ManagerProxy::new(&connection).await?;
+    daemon_reload(&manager_proxy).await?;
+
+    for service in restored {
+        if service.enabled {
+            handle_enable_service(&service.name, false).await?;
+        }
+        if service.active {
+            handle_start_service(&service.name, DEFAULT_JOB_TIMEOUT, false).await?;
+        }
+    }
+
+    if is_table() {
+        handle_show_status().await?;
+    }
+
+    Ok(())
+}
+
+/// Replaces a `User=` which doesn't exist on this host, after asking. User services can't set
+/// `User=`, so it is removed. Returns the user the service runs as.
+///
+/// # Arguments
+///
+/// * `unit_file` - The .service file
+/// * `name` - The service name
+/// * `yes` - Use the user who called sudo without asking
+///
+fn rewrite_user(
+    unit_file: &mut UnitFile,
+    name: &str,
+    yes: bool,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let Some(user) = unit_file.get("Service", "User").map(str::to_string) else {
+        return Ok(None);
+    };
+
+    if is_user() {
+        unit_file.remove("Service", "User");
+        print_message(&format!(
+            "Removed User={user} from {name}. User services run as your user"
+        ));
+        return Ok(None);
+    }
+
+    if user_exists(&user) {
+        return Ok(Some(user));
+    }
+
+    let suggestion = std::env::var("SUDO_USER").ok();
+    let replacement = if yes {
+        suggestion.unwrap_or_default()
+    } else if !std::io::stdin().is_terminal() {
+        // Nobody to ask, keep the user
+        String::new()
+    } else {
+        ask(
+            &format!("User {user} of {name} doesn't exist on this host. Run {name} as"),
+            suggestion.as_deref(),
+        )?
+    };
+
+    if replacement.is_empty() || replacement == user {
+        print_message(&format!(
+            "Warning: {name} runs as {user}, who doesn't exist"
+        ));
+        return Ok(Some(user));
+    }
+
+    unit_file.set("Service", "User", &replacement);
+    print_message(&format!(
+        "Changed User= of {name} from {user} to {replacement}"
+    ));
+
+    Ok(Some(replacement))
+}
+
+/// Replaces the program of every `ExecStart=` if it doesn't exist on this host, eg. a node
+/// installed with nvm in another home directory. A program with the same name in the `PATH` of
+/// the user is suggested. Each missing program is asked about once.
+///
+/// # Arguments
+///
+/// * `unit_file` - The .service file
+/// * `name` - The service name
+/// * `user` - User the service runs as
+/// * `yes` - Use the suggested program without asking
+///
+fn rewrite_interpreter(
+    unit_file: &mut UnitFile,
+    name: &str,
+    user: Option<&str>,
+    yes: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut replacements: HashMap<String, Option<String>> = HashMap::new();
+    let mut changes = vec![];
+
+    for exec_start in unit_file.get_all("Service", "ExecStart") {
+        let Some((prefix, program, args)) = split_exec_start(exec_start) else {
+            continue;
+        };
+
+        if !program.starts_with('/') || is_executable(Path::new(&program)) {
+            continue;
+        }
+
+        let replacement = match replacements.get(&program) {
+            Some(replacement) => replacement.clone(),
+            None => {
+                let replacement = ask_program(&program, name, user, yes)?;
+                replacements.insert(program.clone(), replacement.clone());
+                replacement
+            }
+        };
+
+        if let Some(replacement) = replacement {
+            changes.push((
+                exec_start.to_string(),
+                format!("{prefix}{}{args}", quote_word(&replacement)),
+            ));
+        }
+    }
+
+    // Replaced in place so other `ExecStart=` lines and resets stay as they were
+    for (exec_start, replacement) in changes {
+        unit_file.replace_value("Service", "ExecStart", &exec_start, &replacement);
+    }
+
+    Ok(())
+}
+
+/// Asks what replaces a program which doesn't exist on this host. Returns `None` to keep it.
+///
+/// # Arguments
+///
+/// * `program` - Absolute path of the program
+/// * `name` - The service name
+/// * `user` - User the service runs as
+/// * `yes` - Use the suggested program without asking
+///
+fn ask_program(
+    program: &str,
+    name: &str,
+    user: Option<&str>,
+    yes: bool,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let suggestion = Path::new(program)
+        .file_name()
+        .and_then(|file_name| find_interpreter(&file_name.to_string_lossy(), user).ok());
+    let replacement = if yes {
+        suggestion.unwrap_or_default()
+    } else if !std::io::stdin().is_terminal() {
+        // Nobody to ask, keep the program
+        String::new()
+    } else {
+        ask(
+            &format!("{program} of {name} doesn't exist on this host. Replace it with"),
+            suggestion.as_deref(),
+        )?
+    };
+
+    if replacement.is_empty() || replacement == program {
+        print_message(&format!("Warning: {program} of {name} doesn't exist"));
+        return Ok(None);
+    }
+
+    print_message(&format!(
+        "Changed the program of {name} from {program} to {replacement}"
+    ));
+
+    Ok(Some(replacement))
+}
@@ -0,0 +1,72 @@
+use crate::{
+    handlers::handle_restart_service::offer_restart,
+    utils::{
+        history::{get_previous_revision, read_version, record_change},
+        output::{print_result, ActionResult},
+        scope::connect,
+        service_actions::daemon_reload,
+        service_names::{get_full_service_name, get_service_file_path},
+        systemd::ManagerProxy,
+    },
+};
+
+/// Restore a recorded version of a service file. systemd reloads its units and a running
+/// service may be restarted to apply the change. The restored version is recorded as a new
+/// revision, so a rollback can itself be undone.
+///
+/// # Arguments
+///
+/// * `name` - The service name
+/// * `revision` - Revision to restore. Defaults to the version before the latest change
+/// * `restart` - Restart a running service without asking
+///
+pub async fn handle_rollback_service_file(
+    name: &str,
+    revision: Option<u32>,
+    restart: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let full_service_name = get_full_service_name(name);
+    let service_file_path = get_service_file_path(&full_service_name)?;
+    let path = service_file_path.to_string_lossy().to_string();
+
+    // A deleted service can be restored as well
+    let previous = std::fs::read_to_string(&service_file_path).ok();
+
+    let revision = match revision {
+        Some(revision) => revision,
+        None => get_previous_revision(name, previous.as_deref())?,
+    };
+    let contents = read_version(name, revision)?;
+
+    if previous.as_deref() == Some(contents.as_str()) {
+        print_result(
+            ActionResult::new(
+                "rollback",
+                name,
+                format!("{path} is already at revision {revision}"),
+            )
+            .with_path(&path),
+        );
+        return Ok(());
+    }
+
+    std::fs::write(&service_file_path, &contents)?;
+    record_change(name, previous.as_deref(), &contents)?;
+
+    let connection = connect().await?;
+    let manager_proxy = ManagerProxy::new(&connection).await?;
+    daemon_reload(&manager_proxy).await?;
+
+    print_result(
+        ActionResult::new(
+            "rollback",
+            name,
+            format!("Rolled back {path} to revision {revision}"),
+        )
+        .with_path(&path),
+    );
+
+    offer_restart(&connection, name, restart).await?;
+
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use std::path::Path;
+
+use crate::utils::{
+    output::{print_message, print_result, ActionResult},
+    scope::connect,
+    service_names::{get_service_file_path, get_servicer_services, get_short_service_name},
+    snapshot::{
+        get_default_snapshot_path, get_hostname, read_drop_ins, ServiceSnapshot, Snapshot,
+        SNAPSHOT_VERSION,
+    },
+    systemd::{get_active_state, get_unit_file_state, is_running},
+};
+
+/// Saves every servicer service with its drop-ins, whether it is enabled and whether it is
+/// running to one file, so `ser resurrect` can recreate them, eg. on another host
+///
+/// # Arguments
+///
+/// * `file` - Where to write the snapshot. Defaults to `dump.json` in the state directory
+///
+pub async fn handle_save_snapshot(file: Option<&Path>) -> Result<(), Box<dyn std::error::Error>> {
+    let default_path = get_default_snapshot_path();
+    let path = file.unwrap_or(&default_path);
+    let path_str = path.to_string_lossy().to_string();
+
+    let connection = connect().await?;
+
+    let mut full_service_names = get_servicer_services()?;
+    full_service_names.sort();
+
+    let mut services = vec![];
+    for full_service_name in full_service_names {
+        let active_state = get_active_state(&connection, &full_service_name).await?;
+        let unit_state = get_unit_file_state(&connection, &full_service_name).await?;
+
+        services.push(ServiceSnapshot {
+            name: get_short_service_name(&full_service_name),
+            service_file: std::fs::read_to_string(get_service_file_path(&full_service_name)?)?,
+            drop_ins: read_drop_ins(&full_service_name)?,
+            enabled: matches!(unit_state.as_str(), "enabled" | "enabled-runtime"),
+            active: is_running(&active_state),
+        });
+    }
+
+    let snapshot = Snapshot {
+        version: SNAPSHOT_VERSION,
+        saved_at: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        hostname: get_hostname(),
+        services,
+    };
+
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(&snapshot)?)?;
+
+    for service in &snapshot.services {
+        let state = match (service.enabled, service.active) {
+            (true, true) => "enabled and running",
+            (true, false) => "enabled",
+            (false, true) => "running",
+            (false, false) => "stopped",
+        };
+
+        print_result(
+            ActionResult::new(
+                "save",
+                &service.name,
+                format!("Saved {}, {state}", service.name),
+            )
+            .with_path(&path_str),
+        );
+    }
+
+    print_message(&format!(
+        "Saved {} services to {path_str}. Restore them with `ser resurrect {path_str}`",
+        snapshot.services.len()
+    ));
+
+    Ok(())
+}
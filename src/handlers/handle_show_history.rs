@@ -0,0 +1,64 @@
+use cli_table::{Table, WithTitle};
+use serde::Serialize;
+
+use crate::utils::{
+    error::ServicerError,
+    history::{list_versions, read_version},
+    output::{display_timestamp, is_table, print_document},
+    service_names::{get_full_service_name, get_service_file_path},
+};
+
+#[derive(Table, Serialize)]
+struct HistoryEntry {
+    /// Revision number, starting at 1
+    revision: u32,
+
+    /// When the version was written, in RFC 3339 format. Tables show local time
+    #[table(title = "time", display_fn = "display_timestamp")]
+    timestamp: String,
+
+    /// Who ran the command
+    user: String,
+
+    /// The command which wrote the version
+    command: String,
+
+    /// Whether the .service file currently has this version
+    current: bool,
+}
+
+/// List the recorded versions of a service file, oldest first
+///
+/// # Arguments
+///
+/// * `name` - The service name
+///
+pub fn handle_show_history(name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let versions = list_versions(name)?;
+
+    if versions.is_empty() {
+        return Err(ServicerError::NotFound(format!("history of service {name}")).into());
+    }
+
+    let current =
+        std::fs::read_to_string(get_service_file_path(&get_full_service_name(name))?).ok();
+
+    let entries: Vec<HistoryEntry> = versions
+        .into_iter()
+        .map(|version| HistoryEntry {
+            current: current.is_some() && read_version(name, version.revision).ok() == current,
+            revision: version.revision,
+            timestamp: version.timestamp,
+            user: version.user,
+            command: version.command,
+        })
+        .collect();
+
+    if is_table() {
+        cli_table::print_stdout(entries.with_title())?;
+    } else {
+        print_document("history", &entries)?;
+    }
+
+    Ok(())
+}
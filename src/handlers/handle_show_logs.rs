@@ -1,55 +1,506 @@
-use crate::utils::service_names::get_full_service_name;
-use std::{io::BufRead, process::Stdio};
+use std::{collections::VecDeque, io::IsTerminal};
 
-/// Show logs for a service
+use chrono::{DateTime, Local};
+use clap::ValueEnum;
+use crossterm::style::{Color, Stylize};
+use glob::Pattern;
+use regex::Regex;
+use serde::Serialize;
+
+use crate::utils::{
+    error::ServicerError,
+    journal::{JournalEntry, JournalReader},
+    log_filter::{LogFilter, PriorityRange},
+    scope::connect,
+    service_names::{get_full_service_name, get_servicer_services, get_short_service_name},
+    systemd::{get_unit_path, UnitProxy},
+};
+
+/// Colors of the service name prefixes, picked by a hash of the name
+const PREFIX_COLORS: [Color; 6] = [
+    Color::Cyan,
+    Color::Green,
+    Color::Yellow,
+    Color::Magenta,
+    Color::Blue,
+    Color::Red,
+];
+
+/// How often `--follow` checks the journal files for new entries
+const FOLLOW_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// Number of lines shown when no time range is given
+pub const DEFAULT_LOG_LINES: u32 = 15;
+
+/// How `ser logs` prints entries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Like syslog, eg. `Oct 16 09:12:44 host node[24294]: listening`
+    #[default]
+    Short,
+
+    /// Like `short` with an ISO 8601 timestamp
+    ShortIso,
+
+    /// One JSON object per line with the timestamp, priority, PID, unit, invocation and message
+    Json,
+
+    /// Only the message
+    Cat,
+}
+
+/// An entry as printed by `--format json` and stored by `ser export-logs`
+#[derive(Serialize)]
+pub struct LogRecord {
+    /// RFC 3339 timestamp with microseconds
+    pub timestamp: String,
+
+    /// Microseconds since the Unix epoch
+    pub realtime: u64,
+
+    /// Syslog priority, 0 (emerg) to 7 (debug)
+    pub priority: Option<u8>,
+
+    pub pid: Option<u32>,
+
+    /// The short service name, excluding '.ser.service'
+    pub unit: Option<String>,
+
+    /// ID of the run of the service which wrote the entry
+    pub invocation: Option<String>,
+
+    /// Name of the program which wrote the entry, eg. `node`
+    pub identifier: Option<String>,
+
+    pub boot_id: String,
+
+    pub message: String,
+}
+
+impl LogRecord {
+    /// Collects the fields of an entry
+    ///
+    /// # Arguments
+    ///
+    /// * `entry`
+    /// * `unit` - The short name of the service the entry belongs to
+    ///
+    pub fn new(entry: &JournalEntry, unit: Option<String>) -> Self {
+        LogRecord {
+            timestamp: DateTime::from_timestamp_micros(entry.realtime as i64)
+                .map(|time| {
+                    time.with_timezone(&Local)
+                        .to_rfc3339_opts(chrono::SecondsFormat::Micros, false)
+                })
+                .unwrap_or_default(),
+            realtime: entry.realtime,
+            priority: entry.field("PRIORITY").and_then(|level| level.parse().ok()),
+            pid: entry
+                .field("_PID")
+                .or(entry.field("SYSLOG_PID"))
+                .and_then(|pid| pid.parse().ok()),
+            unit,
+            invocation: entry
+                .field("_SYSTEMD_INVOCATION_ID")
+                .or(entry.field("INVOCATION_ID"))
+                .map(str::to_string),
+            identifier: entry
+                .field("SYSLOG_IDENTIFIER")
+                .or(entry.field("_COMM"))
+                .map(str::to_string),
+            boot_id: entry.boot_id.clone(),
+            message: entry.field("MESSAGE").unwrap_or_default().to_string(),
+        }
+    }
+}
+
+/// Options of `ser logs`
+#[derive(Default)]
+pub struct ShowLogsParams {
+    /// Show the last N entries. Defaults to `DEFAULT_LOG_LINES`, or every entry if a time range,
+    /// boot or run is selected.
+    pub lines: Option<u32>,
+    pub follow: bool,
+
+    /// Earliest time in microseconds since the Unix epoch
+    pub since: Option<u64>,
+
+    /// Latest time in microseconds since the Unix epoch
+    pub until: Option<u64>,
+    pub priority: Option<PriorityRange>,
+
+    /// Only show entries whose message matches
+    pub grep: Option<Regex>,
+
+    /// Entries shown before and after each entry matching `grep`
+    pub context: usize,
+
+    /// Only show entries of the current boot
+    pub boot: bool,
+
+    /// Only show entries since the service was last started
+    pub this_run: bool,
+
+    pub format: LogFormat,
+}
+
+/// Show logs for one or more services, merged by time
+///
+/// Reads the journal files directly, so `journalctl` isn't needed. Lines are prefixed with the
+/// service name unless exactly one service is named.
+///
+/// # Arguments
+///
+/// * `names` - Short service names or glob patterns, eg. `api-*`. Every servicer service if empty
+/// * `params` - Number of lines, follow mode and filters
 ///
-/// Proxies to `journalctl`. Consider decoding the journal directly in future.
+pub async fn handle_show_logs(
+    names: &[String],
+    params: ShowLogsParams,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let patterns: Vec<String> = if names.is_empty() {
+        vec![get_full_service_name("*")]
+    } else {
+        names
+            .iter()
+            .map(|name| get_full_service_name(name))
+            .collect()
+    };
+
+    let mut reader =
+        JournalReader::for_units(&patterns).map_err(|error| ServicerError::InvalidName {
+            name: names.join(" "),
+            reason: error.msg.to_string(),
+        })?;
+
+    // Services with a unit file which match the names
+    let services: Vec<String> = get_servicer_services()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|service| reader.matches_unit(service))
+        .collect();
+
+    let filter = LogFilter {
+        since: params.since,
+        until: params.until,
+        priority: params.priority,
+        boot_id: if params.boot {
+            Some(get_current_boot_id()?)
+        } else {
+            None
+        },
+        invocation_ids: if params.this_run {
+            Some(get_invocation_ids(&services, &patterns).await?)
+        } else {
+            None
+        },
+    };
+
+    let lines = match params.lines {
+        Some(lines) => lines as usize,
+        None if params.since.is_some()
+            || params.until.is_some()
+            || params.boot
+            || params.this_run =>
+        {
+            usize::MAX
+        }
+        None => DEFAULT_LOG_LINES as usize,
+    };
+
+    let is_single_service = names.len() == 1 && Pattern::escape(&names[0]) == names[0];
+    let mut printer = EntryPrinter {
+        grep: params.grep,
+        context: params.context,
+        prefix_width: (!is_single_service).then(|| {
+            services
+                .iter()
+                .map(|service| get_short_service_name(service).len())
+                .max()
+                .unwrap_or(0)
+        }),
+        format: params.format,
+        color: std::io::stdout().is_terminal(),
+        before: VecDeque::new(),
+        after: 0,
+        skipped: false,
+        printed: false,
+    };
+
+    let entries = match &printer.grep {
+        // Read everything in range, then keep the last matches and their context
+        Some(grep) => {
+            let entries = reader.read_tail(usize::MAX, &filter)?;
+            let matches: Vec<usize> = entries
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| grep.is_match(entry.field("MESSAGE").unwrap_or_default()))
+                .map(|(index, _)| index)
+                .collect();
+
+            let start = match matches.len().checked_sub(lines) {
+                _ if lines == 0 => entries.len(),
+                Some(first_match) => matches[first_match].saturating_sub(params.context),
+                None => 0,
+            };
+
+            entries.into_iter().skip(start).collect()
+        }
+        None => reader.read_tail(lines, &filter)?,
+    };
+
+    if entries.is_empty() && !params.follow && params.format != LogFormat::Json {
+        println!("-- No entries --");
+    }
+
+    let entries: Vec<(JournalEntry, Option<String>)> = entries
+        .into_iter()
+        .map(|entry| {
+            let service = reader.get_unit(&entry).map(get_short_service_name);
+            (entry, service)
+        })
+        .collect();
+
+    // Align with services which only have logs left, eg. deleted ones
+    if let Some(width) = printer.prefix_width.as_mut() {
+        for (_, service) in &entries {
+            *width = (*width).max(service.as_deref().unwrap_or_default().len());
+        }
+    }
+
+    for (entry, service) in entries {
+        printer.print(entry, service);
+    }
+
+    if !params.follow {
+        return Ok(());
+    }
+
+    loop {
+        tokio::time::sleep(FOLLOW_INTERVAL).await;
+
+        for entry in reader.read_new(&filter)? {
+            let service = reader.get_unit(&entry).map(get_short_service_name);
+            printer.print(entry, service);
+        }
+    }
+}
+
+/// Returns the last log lines of a service without printing them
 ///
 /// # Arguments
 ///
 /// * `name`- Name of the service in short form (hello-world)
-/// * `follow` - Print logs
+/// * `lines` - Number of lines
+/// * `filter` - Conditions on time, priority, boot and invocation
 ///
-pub fn handle_show_logs(
+pub fn get_log_lines(
     name: &str,
     lines: u32,
-    follow: bool,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let full_name = get_full_service_name(name);
+    filter: &LogFilter,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut reader = JournalReader::for_units(&[get_full_service_name(name)])?;
+
+    Ok(reader
+        .read_tail(lines as usize, filter)?
+        .iter()
+        .map(|entry| format_entry(entry, LogFormat::Short))
+        .collect())
+}
 
-    let mut command = std::process::Command::new("journalctl");
+/// Prints entries, optionally only those matching a regex with context lines like `grep -C`
+struct EntryPrinter {
+    grep: Option<Regex>,
 
-    // Set the journal unit name with -u option
-    command.arg("-u").arg(full_name);
+    /// Entries shown before and after each match
+    context: usize,
 
-    // Set the number of lines to show with -n option
-    command.arg("-n").arg(lines.to_string());
+    /// Width of the service name prefix. `None` if lines have no prefix
+    prefix_width: Option<usize>,
 
-    if follow {
-        // Enable continuous following with --follow option
-        command.arg("--follow");
+    format: LogFormat,
+
+    /// Whether prefixes are colored
+    color: bool,
+
+    /// Recent entries which didn't match and their service, shown if the next match is near
+    before: VecDeque<(JournalEntry, Option<String>)>,
+
+    /// Entries after the last match which are still shown
+    after: usize,
+
+    /// Whether entries were left out since the last printed entry
+    skipped: bool,
+
+    /// Whether any entry was printed
+    printed: bool,
+}
+
+impl EntryPrinter {
+    /// Prints an entry if it matches or is in the context of a match
+    ///
+    /// # Arguments
+    ///
+    /// * `entry`
+    /// * `service` - The short name of the service the entry belongs to
+    ///
+    fn print(&mut self, entry: JournalEntry, service: Option<String>) {
+        let Some(grep) = &self.grep else {
+            self.print_line(&entry, service.as_deref());
+            return;
+        };
+
+        if grep.is_match(entry.field("MESSAGE").unwrap_or_default()) {
+            // Separate groups of context like grep
+            if self.skipped && self.printed && self.context > 0 {
+                println!("--");
+            }
+
+            for (entry, service) in std::mem::take(&mut self.before) {
+                self.print_line(&entry, service.as_deref());
+            }
+            self.print_line(&entry, service.as_deref());
+
+            self.after = self.context;
+            self.skipped = false;
+            self.printed = true;
+        } else if self.after > 0 {
+            self.print_line(&entry, service.as_deref());
+            self.after -= 1;
+        } else {
+            self.before.push_back((entry, service));
+
+            if self.before.len() > self.context {
+                self.before.pop_front();
+                self.skipped = true;
+            }
+        }
     }
 
-    // Set stdout to be captured (piped) so we can read the output
-    command.stdout(Stdio::piped());
+    /// Prints an entry with the service name prefix, padded to align the messages
+    fn print_line(&mut self, entry: &JournalEntry, service: Option<&str>) {
+        // JSON records hold the service name instead of a prefix
+        if self.format == LogFormat::Json {
+            let record = LogRecord::new(entry, service.map(str::to_string));
+            println!("{}", serde_json::to_string(&record).unwrap_or_default());
+            return;
+        }
 
-    // Run the command asynchronously
-    let mut child = command.spawn()?;
+        let Some(width) = self.prefix_width.as_mut() else {
+            println!("{}", format_entry(entry, self.format));
+            return;
+        };
 
-    // Get a handle to the child process's stdout
-    let stdout = child.stdout.take().unwrap();
+        let service = service.unwrap_or_default();
+        *width = (*width).max(service.len());
+        let prefix = format!("{service:<width$} |", width = *width);
 
-    // Create a stream to read lines from the stdout
-    let reader = std::io::BufReader::new(stdout).lines();
+        if self.color {
+            let hash = service.bytes().fold(0usize, |hash, byte| {
+                hash.wrapping_mul(31).wrapping_add(byte as usize)
+            });
+            let color = PREFIX_COLORS[hash % PREFIX_COLORS.len()];
 
-    // Process the lines and proxy the output to the user
-    for line in reader {
-        let line = line?;
-        println!("{}", line); // You can send the line to the user in your actual code
+            println!(
+                "{} {}",
+                prefix.with(color),
+                format_entry(entry, self.format)
+            );
+        } else {
+            println!("{prefix} {}", format_entry(entry, self.format));
+        }
     }
+}
 
-    // Wait for the child process to complete and get its exit status
-    child.wait()?;
+/// Formats an entry like `journalctl`, eg. `Oct 16 09:12:44 host node[24294]: listening`
+///
+/// # Arguments
+///
+/// * `entry`
+/// * `format` - `short`, `short-iso` or `cat`
+///
+pub fn format_entry(entry: &JournalEntry, format: LogFormat) -> String {
+    let message = entry.field("MESSAGE").unwrap_or_default();
+
+    let timestamp_format = match format {
+        LogFormat::Cat => return message.to_string(),
+        LogFormat::ShortIso => "%Y-%m-%dT%H:%M:%S%z",
+        _ => "%b %d %H:%M:%S",
+    };
+
+    let timestamp = DateTime::from_timestamp_micros(entry.realtime as i64)
+        .map(|time| {
+            time.with_timezone(&Local)
+                .format(timestamp_format)
+                .to_string()
+        })
+        .unwrap_or_default();
+
+    let identifier = entry
+        .field("SYSLOG_IDENTIFIER")
+        .or(entry.field("_COMM"))
+        .unwrap_or("unknown");
+
+    let pid = match entry.field("SYSLOG_PID").or(entry.field("_PID")) {
+        Some(pid) => format!("[{pid}]"),
+        None => String::new(),
+    };
+
+    format!(
+        "{timestamp} {} {identifier}{pid}: {message}",
+        entry.field("_HOSTNAME").unwrap_or("localhost"),
+    )
+}
+
+/// ID of the running boot in the hexadecimal format of journal entries
+pub fn get_current_boot_id() -> Result<String, ServicerError> {
+    Ok(std::fs::read_to_string("/proc/sys/kernel/random/boot_id")?
+        .trim()
+        .replace('-', ""))
+}
+
+/// IDs of the current runs of services in hexadecimal. systemd assigns a new ID on every start.
+///
+/// # Arguments
+///
+/// * `services` - Full names of the services with a unit file which match the patterns
+/// * `patterns` - The requested names, which may also name services without a unit file
+///
+async fn get_invocation_ids(
+    services: &[String],
+    patterns: &[String],
+) -> Result<Vec<String>, ServicerError> {
+    let connection = connect().await?;
+    let mut invocation_ids = vec![];
+
+    let exact_names = patterns
+        .iter()
+        .filter(|pattern| Pattern::escape(pattern) == **pattern);
+
+    for full_service_name in services.iter().chain(exact_names) {
+        let unit_proxy = UnitProxy::new(&connection, get_unit_path(full_service_name)).await?;
+
+        let invocation_id = unit_proxy
+            .invocation_id()
+            .await
+            .map_err(|error| ServicerError::from_dbus(error, "read", full_service_name))?;
+
+        if !invocation_id.is_empty() {
+            invocation_ids.push(
+                invocation_id
+                    .iter()
+                    .map(|byte| format!("{byte:02x}"))
+                    .collect(),
+            );
+        }
+    }
+
+    if invocation_ids.is_empty() {
+        return Err(ServicerError::NotFound(format!(
+            "a run of {}",
+            patterns.join(", ")
+        )));
+    }
 
-    Ok(())
+    Ok(invocation_ids)
 }
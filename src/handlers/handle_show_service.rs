@@ -0,0 +1,433 @@
+use bytesize::ByteSize;
+use chrono::{DateTime, Local, Utc};
+use serde::Serialize;
+use zbus::Connection;
+
+use crate::{
+    handlers::handle_show_logs::get_log_lines,
+    utils::{
+        error::ServicerError,
+        log_filter::LogFilter,
+        output::{output_format, print_document, OutputFormat},
+        process_status::{describe_exit, get_cgroup_processes, get_cgroup_usage, ProcessInfo},
+        scope::connect,
+        service_names::{get_full_service_name, get_service_file_path, get_short_service_name},
+        systemd::{get_unit_path, ServiceProxy, UnitProxy},
+    },
+};
+
+/// Number of log lines at the end of the report
+const LOG_LINES: u32 = 10;
+
+/// Everything servicer knows about one service
+#[derive(Serialize)]
+pub struct ServiceDetails {
+    /// The short service name, excluding '.ser.service'
+    pub name: String,
+
+    pub description: String,
+
+    /// Active state, eg. `active`
+    pub active: String,
+
+    /// Sub-state, eg. `running`
+    pub sub_state: String,
+
+    /// Unit file state. `enabled` units start on boot
+    pub unit_file_state: String,
+
+    /// PID of the main process. 0 if the service is not running
+    pub pid: u32,
+
+    /// Every process in the control group of the service
+    pub processes: Vec<ProcessInfo>,
+
+    /// When the service last entered the active state, in RFC 3339 format
+    pub active_since: Option<String>,
+
+    /// Seconds since the service last entered the active state
+    pub uptime: Option<u64>,
+
+    /// Automatic restarts since the unit was loaded
+    pub restarts: u32,
+
+    /// Result of the last run, eg. `success` or `exit-code`
+    pub result: String,
+
+    /// How the main process last exited, eg. `exited with status 1`
+    pub last_exit: Option<String>,
+
+    /// Command lines run on start
+    pub exec_start: Vec<String>,
+
+    /// User the service runs as. Empty for root
+    pub user: String,
+
+    pub working_directory: String,
+
+    /// Environment variables in `KEY=value` format
+    pub environment: Vec<String>,
+
+    /// RAM usage of every process in the service, in bytes
+    pub memory: Option<u64>,
+
+    /// Highest RAM usage, in bytes
+    pub memory_peak: Option<u64>,
+
+    /// `MemoryMax`, in bytes. `None` if unlimited
+    pub memory_max: Option<u64>,
+
+    /// Total CPU time, in nanoseconds
+    pub cpu_time: Option<u64>,
+
+    /// `CPUQuota` in percent of one CPU. `None` if unlimited
+    pub cpu_quota: Option<u64>,
+
+    /// Number of tasks
+    pub tasks: Option<u64>,
+
+    /// `TasksMax`. `None` if unlimited
+    pub tasks_max: Option<u64>,
+
+    /// Bytes read from disk
+    pub io_read: Option<u64>,
+
+    /// Bytes written to disk
+    pub io_write: Option<u64>,
+
+    /// The last lines of the journal
+    pub logs: Vec<String>,
+}
+
+/// A line of the report, also used as a CSV record
+#[derive(Serialize)]
+struct ReportField {
+    field: String,
+    value: String,
+}
+
+/// Display everything about one service: state, processes, uptime, restarts, last exit, command
+/// line, environment, resource usage and limits, and recent logs
+///
+/// # Arguments
+///
+/// * `name` - The service name
+///
+pub async fn handle_show_service(name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let full_service_name = get_full_service_name(name);
+
+    if !get_service_file_path(&full_service_name)?.exists() {
+        return Err(ServicerError::NotFound(format!("service {full_service_name}")).into());
+    }
+
+    let connection = connect().await?;
+    let details = get_service_details(&connection, &full_service_name).await?;
+
+    match output_format() {
+        OutputFormat::Table => print_report(&full_service_name, &details),
+        OutputFormat::Csv => print_document("service", &get_report_fields(&details))?,
+        _ => print_document("service", &[details])?,
+    }
+
+    Ok(())
+}
+
+/// Reads the properties of a service with `UnitProxy` and `ServiceProxy`, then its processes
+/// and logs
+///
+/// # Arguments
+///
+/// * `connection` - zbus connection
+/// * `full_service_name` - Full name of the service, having '.ser.service' at the end
+///
+async fn get_service_details(
+    connection: &Connection,
+    full_service_name: &str,
+) -> Result<ServiceDetails, ServicerError> {
+    let unit_path = get_unit_path(full_service_name);
+    let unit_proxy = UnitProxy::new(connection, unit_path.clone()).await?;
+    let service_proxy = ServiceProxy::new(connection, unit_path).await?;
+
+    let active = unit_proxy.active_state().await?;
+    let control_group = service_proxy.control_group().await.unwrap_or_default();
+
+    // systemd reports u64::MAX for turned off accounting and unlimited resources
+    let limit = |value: zbus::Result<u64>| value.ok().filter(|value| *value != u64::MAX);
+
+    // The timestamp is 0 if the service never started, and stale if it is no longer active
+    let active_enter_timestamp = unit_proxy.active_enter_timestamp().await.unwrap_or(0);
+    let active_since = if active_enter_timestamp != 0 && active != "inactive" {
+        DateTime::from_timestamp_micros(active_enter_timestamp as i64)
+    } else {
+        None
+    };
+
+    let exec_start = service_proxy
+        .exec_start()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(path, argv, ..)| {
+            if argv.is_empty() {
+                path
+            } else {
+                argv.join(" ")
+            }
+        })
+        .collect();
+
+    let cgroup_usage = get_cgroup_usage(&control_group);
+
+    Ok(ServiceDetails {
+        name: get_short_service_name(full_service_name),
+        description: unit_proxy.description().await.unwrap_or_default(),
+        sub_state: unit_proxy.sub_state().await?,
+        unit_file_state: unit_proxy.unit_file_state().await.unwrap_or_default(),
+        pid: service_proxy.main_pid().await.unwrap_or(0),
+        processes: get_cgroup_processes(&control_group),
+        uptime: active_since.map(|since| (Utc::now() - since).num_seconds().max(0) as u64),
+        active_since: active_since.map(|since| since.to_rfc3339()),
+        restarts: service_proxy.n_restarts().await.unwrap_or(0),
+        result: service_proxy.result().await.unwrap_or_default(),
+        last_exit: describe_exit(
+            service_proxy.exec_main_code().await.unwrap_or(0),
+            service_proxy.exec_main_status().await.unwrap_or(0),
+        ),
+        exec_start,
+        user: service_proxy.user().await.unwrap_or_default(),
+        working_directory: service_proxy.working_directory().await.unwrap_or_default(),
+        environment: service_proxy.environment().await.unwrap_or_default(),
+        memory: limit(service_proxy.memory_current().await).or(cgroup_usage.memory_current),
+        memory_peak: cgroup_usage.memory_peak,
+        memory_max: limit(service_proxy.memory_max().await),
+        cpu_time: limit(service_proxy.cpu_usage_nsec().await),
+        // The quota is in CPU time per second of wall clock time
+        cpu_quota: limit(service_proxy.cpu_quota_per_sec_usec().await).map(|usec| usec / 10_000),
+        tasks: limit(service_proxy.tasks_current().await).or(cgroup_usage.tasks),
+        tasks_max: limit(service_proxy.tasks_max().await),
+        io_read: limit(service_proxy.io_read_bytes().await).or(cgroup_usage.io_read_bytes),
+        io_write: limit(service_proxy.io_write_bytes().await).or(cgroup_usage.io_write_bytes),
+        logs: get_log_lines(
+            &get_short_service_name(full_service_name),
+            LOG_LINES,
+            &LogFilter::default(),
+        )
+        .unwrap_or_default(),
+        active,
+    })
+}
+
+/// Flattens the details into labelled fields, in the order of the report
+///
+/// # Arguments
+///
+/// * `details`
+///
+fn get_report_fields(details: &ServiceDetails) -> Vec<ReportField> {
+    let bytes = |value: Option<u64>| value.map_or("-".to_string(), |b| ByteSize::b(b).to_string());
+    let or_unlimited = |value: Option<String>| value.unwrap_or("unlimited".to_string());
+
+    let mut state = format!("{} ({})", details.active, details.sub_state);
+    if let (Some(since), Some(uptime)) = (&details.active_since, details.uptime) {
+        let since = DateTime::parse_from_rfc3339(since)
+            .map(|since| {
+                since
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M:%S %Z")
+                    .to_string()
+            })
+            .unwrap_or(since.clone());
+        state += &format!(" since {since}; {} ago", format_duration(uptime));
+    }
+
+    let mut fields = vec![
+        ("Description", details.description.clone()),
+        ("State", state),
+        ("Enable on boot", details.unit_file_state.clone()),
+        ("Main PID", details.pid.to_string()),
+        ("Restarts", details.restarts.to_string()),
+        ("Result", details.result.clone()),
+        (
+            "Last exit",
+            details.last_exit.clone().unwrap_or("-".to_string()),
+        ),
+    ];
+
+    for command in &details.exec_start {
+        fields.push(("ExecStart", command.clone()));
+    }
+
+    fields.push((
+        "User",
+        if details.user.is_empty() {
+            "root".to_string()
+        } else {
+            details.user.clone()
+        },
+    ));
+    fields.push(("Working directory", details.working_directory.clone()));
+
+    for variable in &details.environment {
+        fields.push(("Environment", variable.clone()));
+    }
+
+    fields.push((
+        "Memory",
+        format!(
+            "{} (peak {}, max {})",
+            bytes(details.memory),
+            bytes(details.memory_peak),
+            or_unlimited(details.memory_max.map(|max| ByteSize::b(max).to_string()))
+        ),
+    ));
+    fields.push((
+        "CPU",
+        format!(
+            "{} (quota {})",
+            details
+                .cpu_time
+                .map_or("-".to_string(), |nsec| format!("{:.3}s", nsec as f64 / 1e9)),
+            or_unlimited(details.cpu_quota.map(|quota| format!("{quota}%")))
+        ),
+    ));
+    fields.push((
+        "Tasks",
+        format!(
+            "{} (max {})",
+            details
+                .tasks
+                .map_or("-".to_string(), |tasks| tasks.to_string()),
+            or_unlimited(details.tasks_max.map(|max| max.to_string()))
+        ),
+    ));
+    fields.push((
+        "IO",
+        format!(
+            "{} read, {} written",
+            bytes(details.io_read),
+            bytes(details.io_write)
+        ),
+    ));
+
+    for process in get_process_tree(&details.processes) {
+        fields.push(("Process", process));
+    }
+
+    for line in &details.logs {
+        fields.push(("Log", line.clone()));
+    }
+
+    fields
+        .into_iter()
+        .map(|(field, value)| ReportField {
+            field: field.to_string(),
+            value,
+        })
+        .collect()
+}
+
+/// Prints the report in the style of `systemctl status`
+///
+/// # Arguments
+///
+/// * `full_service_name` - Full name of the service, having '.ser.service' at the end
+/// * `details`
+///
+fn print_report(full_service_name: &str, details: &ServiceDetails) {
+    println!("{full_service_name}");
+
+    let mut previous_field = String::new();
+
+    for ReportField { field, value } in get_report_fields(details) {
+        match field.as_str() {
+            "Process" | "Log" => {
+                if field != previous_field {
+                    println!(
+                        "\n{}:",
+                        if field == "Process" {
+                            "Processes"
+                        } else {
+                            "Logs"
+                        }
+                    );
+                }
+                println!("  {value}");
+            }
+            _ => {
+                // Repeated fields such as Environment only show the label once
+                let label = if field == previous_field {
+                    String::new()
+                } else {
+                    format!("{field}:")
+                };
+                println!("{label:>19} {value}");
+            }
+        }
+
+        previous_field = field;
+    }
+}
+
+/// Renders processes as an indented tree, children below their parent
+///
+/// # Arguments
+///
+/// * `processes` - Processes of a control group
+///
+fn get_process_tree(processes: &[ProcessInfo]) -> Vec<String> {
+    fn add_children(
+        processes: &[ProcessInfo],
+        parent: &ProcessInfo,
+        depth: usize,
+        lines: &mut Vec<String>,
+    ) {
+        let indent = if depth == 0 {
+            String::new()
+        } else {
+            format!("{}└─ ", "   ".repeat(depth - 1))
+        };
+        lines.push(format!("{indent}{} {}", parent.pid, parent.command));
+
+        for child in processes.iter().filter(|child| child.ppid == parent.pid) {
+            add_children(processes, child, depth + 1, lines);
+        }
+    }
+
+    let mut lines = vec![];
+
+    for root in processes
+        .iter()
+        .filter(|process| !processes.iter().any(|parent| parent.pid == process.ppid))
+    {
+        add_children(processes, root, 0, &mut lines);
+    }
+
+    lines
+}
+
+/// Formats seconds as days, hours, minutes and seconds, eg. `2h 5min 3s`
+///
+/// # Arguments
+///
+/// * `seconds`
+///
+fn format_duration(seconds: u64) -> String {
+    let units = [(86400, "d"), (3600, "h"), (60, "min"), (1, "s")];
+
+    let parts: Vec<String> = units
+        .iter()
+        .scan(seconds, |remaining, (size, unit)| {
+            let count = *remaining / size;
+            *remaining %= size;
+            Some((count, unit))
+        })
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, unit)| format!("{count}{unit}"))
+        .collect();
+
+    if parts.is_empty() {
+        "0s".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
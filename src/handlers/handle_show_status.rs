@@ -1,16 +1,21 @@
 use crate::{
-    utils::service_names::{get_short_service_name, is_full_name},
+    utils::service_names::get_short_service_name,
     utils::{
-        process_status::{get_cpu_time, get_memory_usage, get_page_size},
-        systemd::{get_active_state, get_main_pid, get_unit_file_state},
+        output::{is_table, print_document},
+        process_status::{
+            get_cgroup_cpu_time, get_cgroup_usage, get_cpu_time, get_memory_usage, get_page_size,
+        },
+        scope::connect,
+        scope::is_user,
+        systemd::get_servicer_unit_statuses,
     },
 };
 use bytesize::ByteSize;
 use cli_table::{Table, WithTitle};
-use std::path::Path;
+use serde::Serialize;
 use zbus::Connection;
 
-#[derive(Table, Clone)]
+#[derive(Table, Serialize, Clone)]
 pub struct ServiceStatus {
     /// Process ID
     pub pid: u32,
@@ -25,51 +30,51 @@ pub struct ServiceStatus {
     #[table(title = "enable on boot")]
     pub enabled_on_boot: bool,
 
-    /// CPU usage. Formatted string in %
+    /// Automatic restarts since the unit was loaded
+    pub restarts: u32,
+
+    /// CPU usage of every process in the service, in %
     #[table(title = "cpu %")]
     pub cpu: f32,
 
-    /// RAM usage. Formatted string with MB, KB and other units
-    pub memory: String,
-}
-
-/// Display the status of your services
-pub async fn handle_show_status() -> Result<(), Box<dyn std::error::Error>> {
-    let page_kb_size = get_page_size()?;
-    let services = get_servicer_services()?;
-
-    let connection = Connection::system().await?;
+    /// RAM usage of every process in the service, in bytes. Tables show it with MB, KB and
+    /// other units
+    #[table(display_fn = "display_bytes")]
+    pub memory: u64,
 
-    let mut active_process_exists = true;
-    let mut service_statuses: Vec<ServiceStatus> = vec![];
+    /// Highest RAM usage since the service started, in bytes. Only in machine readable output
+    #[table(skip)]
+    pub memory_peak: Option<u64>,
 
-    for full_service_name in services {
-        let active_state: String = get_active_state(&connection, &full_service_name).await;
-        let unit_state = get_unit_file_state(&connection, &full_service_name).await;
+    /// Number of processes and threads in the service
+    pub tasks: u64,
 
-        let enabled_on_boot = unit_state == "enabled" || unit_state == "enabled-runtime";
+    /// Bytes read from block devices
+    #[table(title = "io read", display_fn = "display_bytes")]
+    pub io_read: u64,
 
-        // PID, CPU and memory is 0 for inactive and errored processes
-        let (pid, cpu, memory) = if active_state == "active" {
-            active_process_exists = true;
+    /// Bytes written to block devices
+    #[table(title = "io write", display_fn = "display_bytes")]
+    pub io_write: u64,
 
-            let pid = get_main_pid(&connection, &full_service_name).await?;
-            let memory = get_memory_usage(pid, page_kb_size as u64)?;
+    /// The .service file changed on disk since systemd loaded it. Tables mark the name with `*`
+    #[table(skip)]
+    pub need_daemon_reload: bool,
 
-            (pid, 0f32, ByteSize::kib(memory).to_string())
-        } else {
-            (0, 0f32, "0".to_string())
-        };
+    /// Control group of the service, used to sample CPU usage
+    #[table(skip)]
+    #[serde(skip)]
+    pub control_group: String,
+}
 
-        service_statuses.push(ServiceStatus {
-            pid,
-            name: get_short_service_name(&full_service_name),
-            active: active_state,
-            enabled_on_boot,
-            cpu,
-            memory,
-        });
-    }
+/// Display the status of your services
+///
+/// Memory, CPU, tasks and IO are totals for the service's control group, so worker processes
+/// forked by the main process are included. Values come from the cgroup v2 files, with the
+/// systemd accounting properties and `/proc/<MainPID>` as fallbacks.
+pub async fn handle_show_status() -> Result<(), Box<dyn std::error::Error>> {
+    let connection = connect().await?;
+    let mut service_statuses = get_service_statuses(&connection).await?;
 
     // CPU time algorithm- Find the change in CPU time over an interval, then divide by the interval
     // Source- https://github.com/dalance/procs/blob/ba703e98cd44be46ba32e084f1474d81b9a7f660/src/columns/usage_cpu.rs#L36C57-L36C83
@@ -77,51 +82,131 @@ pub async fn handle_show_status() -> Result<(), Box<dyn std::error::Error>> {
     // Sleep duration in ms
     const SLEEP_DURATION: u32 = 150;
 
-    if active_process_exists {
+    if service_statuses.iter().any(|status| status.pid != 0) {
         // We only need to sleep once with this method
-        let initial_cpu_times = get_cpu_times(service_statuses.clone());
+        let initial_cpu_times = get_cpu_times(&service_statuses);
         tokio::time::sleep(tokio::time::Duration::from_millis(SLEEP_DURATION as u64)).await;
-        let final_cpu_times = get_cpu_times(service_statuses.clone());
-
-        let tps = clock_ticks_per_second();
+        let final_cpu_times = get_cpu_times(&service_statuses);
 
         for i in 0..service_statuses.len() {
             let initial_time = *initial_cpu_times.get(i).unwrap();
             let final_time = *final_cpu_times.get(i).unwrap();
-            let usage_ms = (final_time - initial_time) * 1000 / tps;
-            let cpu_usage = usage_ms as f32 * 100.0 / SLEEP_DURATION as f32;
+            let usage_us = final_time.saturating_sub(initial_time);
+            let cpu_usage = usage_us as f32 * 100.0 / (SLEEP_DURATION * 1000) as f32;
 
             let status = service_statuses.get_mut(i).unwrap();
             status.cpu = cpu_usage;
         }
     }
 
-    cli_table::print_stdout(service_statuses.with_title())?;
+    if is_table() {
+        let outdated = service_statuses
+            .iter()
+            .any(|status| status.need_daemon_reload);
+
+        for status in service_statuses.iter_mut() {
+            if status.need_daemon_reload {
+                status.name.push('*');
+            }
+        }
+
+        cli_table::print_stdout(service_statuses.with_title())?;
+
+        if outdated {
+            let command = if is_user() {
+                "systemctl --user daemon-reload"
+            } else {
+                "sudo systemctl daemon-reload"
+            };
+            println!(
+                "* The .service file changed on disk since systemd loaded it. Run `{command}` to \
+                apply the changes"
+            );
+        }
+    } else {
+        print_document("status", &service_statuses)?;
+    }
 
     Ok(())
 }
 
-/// Get systemd services having an extension `.ser.service`. We only monitor services created by this tool
-fn get_servicer_services() -> Result<Vec<String>, std::io::Error> {
-    let folder_path = "/etc/systemd/system/";
+/// Collects the status of every servicer service. CPU usage is left at 0 because it has to be
+/// sampled over an interval with `get_cpu_times()`.
+///
+/// # Arguments
+///
+/// * `connection` - zbus connection
+///
+pub async fn get_service_statuses(
+    connection: &Connection,
+) -> Result<Vec<ServiceStatus>, Box<dyn std::error::Error>> {
+    let page_kb_size = get_page_size()?;
+    let unit_statuses = get_servicer_unit_statuses(connection).await?;
 
-    let folder_path = Path::new(folder_path);
+    let mut service_statuses: Vec<ServiceStatus> = vec![];
 
-    let mut files = Vec::<String>::new();
-    let dir = std::fs::read_dir(folder_path)?;
+    for unit_status in unit_statuses {
+        let unit_state = unit_status.unit_file_state;
+        let enabled_on_boot = unit_state == "enabled" || unit_state == "enabled-runtime";
 
-    for entry in dir.flatten() {
-        let path = entry.path();
+        let mut service_status = ServiceStatus {
+            pid: 0,
+            name: get_short_service_name(&unit_status.full_service_name),
+            active: unit_status.active_state,
+            enabled_on_boot,
+            restarts: unit_status.n_restarts,
+            cpu: 0f32,
+            memory: 0,
+            memory_peak: None,
+            tasks: 0,
+            io_read: 0,
+            io_write: 0,
+            need_daemon_reload: unit_status.need_daemon_reload,
+            control_group: unit_status.control_group,
+        };
 
-        if path.is_file() {
-            let name = path.file_name().unwrap().to_str().unwrap();
-            if is_full_name(name) {
-                files.push(name.to_string());
-            }
+        // PID, CPU and memory is 0 for inactive and errored processes
+        if service_status.active == "active" && unit_status.main_pid != 0 {
+            let pid = unit_status.main_pid;
+            let usage = get_cgroup_usage(&service_status.control_group);
+
+            let memory = match usage.memory_current.or(unit_status.memory_current) {
+                Some(memory) => memory,
+                None => ByteSize::kib(get_memory_usage(pid, page_kb_size as u64)?).as_u64(),
+            };
+
+            service_status.pid = pid;
+            service_status.memory = memory;
+            service_status.memory_peak = usage.memory_peak;
+            service_status.tasks = usage.tasks.or(unit_status.tasks_current).unwrap_or(0);
+            service_status.io_read = usage
+                .io_read_bytes
+                .or(unit_status.io_read_bytes)
+                .unwrap_or(0);
+            service_status.io_write = usage
+                .io_write_bytes
+                .or(unit_status.io_write_bytes)
+                .unwrap_or(0);
         }
+
+        service_statuses.push(service_status);
     }
 
-    Ok(files)
+    Ok(service_statuses)
+}
+
+/// Formats a size in bytes for the status table
+///
+/// # Arguments
+///
+/// * `bytes`
+///
+fn display_bytes(bytes: &u64) -> String {
+    if *bytes == 0 {
+        "0".to_string()
+    } else {
+        ByteSize::b(*bytes).to_string()
+    }
 }
 
 /// Get CPU clock ticks per second. This value is usually 100 on x86_64
@@ -129,18 +214,22 @@ pub fn clock_ticks_per_second() -> u64 {
     unsafe { libc::sysconf(libc::_SC_CLK_TCK) as u64 }
 }
 
-/// Get CPU time for a vector of services
+/// Get CPU time in microseconds for a vector of services. Uses the control group total and falls
+/// back to the main process if the cgroup can't be read.
 ///
 /// # Arguments
 ///
 /// * `service_statuses`
 ///
-pub fn get_cpu_times(service_statuses: Vec<ServiceStatus>) -> Vec<u64> {
+pub fn get_cpu_times(service_statuses: &[ServiceStatus]) -> Vec<u64> {
+    let tps = clock_ticks_per_second();
+
     service_statuses
         .iter()
         .map(|status| {
-            if status.active == "active" {
-                get_cpu_time(status.pid).unwrap_or(0)
+            if status.pid != 0 {
+                get_cgroup_cpu_time(&status.control_group)
+                    .unwrap_or_else(|| get_cpu_time(status.pid).unwrap_or(0) * 1_000_000 / tps)
             } else {
                 0
             }
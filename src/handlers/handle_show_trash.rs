@@ -0,0 +1,53 @@
+use cli_table::{Table, WithTitle};
+use serde::Serialize;
+
+use crate::utils::{
+    output::{display_timestamp, is_table, print_document, print_message},
+    trash::list_trash,
+};
+
+#[derive(Table, Serialize)]
+struct TrashRow {
+    /// The service name
+    name: String,
+
+    /// When the service was deleted, in RFC 3339 format. Tables show local time
+    #[table(title = "deleted", display_fn = "display_timestamp")]
+    deleted_at: String,
+
+    /// Whether the service started on boot
+    #[table(title = "enable on boot")]
+    enabled: bool,
+
+    /// Whether the service was running
+    active: bool,
+
+    /// Directory of the trash entry
+    path: String,
+}
+
+/// List the deleted services in the trash, oldest first
+pub fn handle_show_trash() -> Result<(), Box<dyn std::error::Error>> {
+    let rows: Vec<TrashRow> = list_trash()?
+        .into_iter()
+        .map(|entry| TrashRow {
+            name: entry.metadata.name,
+            deleted_at: entry.metadata.deleted_at,
+            enabled: entry.metadata.enabled,
+            active: entry.metadata.active,
+            path: entry.path.to_string_lossy().to_string(),
+        })
+        .collect();
+
+    if is_table() {
+        if rows.is_empty() {
+            print_message("The trash is empty");
+        } else {
+            cli_table::print_stdout(rows.with_title())?;
+        }
+    } else {
+        print_document("trash", &rows)?;
+    }
+
+    Ok(())
+}
@@ -1,6 +1,9 @@
 use crate::{
     utils::service_names::get_full_service_name,
     utils::{
+        error::ServicerError,
+        output::{is_table, print_result, ActionResult},
+        scope::connect,
         service_actions::start_service,
         systemd::{get_active_state, ManagerProxy},
     },
@@ -10,30 +13,51 @@ use super::handle_show_status::handle_show_status;
 
 /// Starts a systemd service. This is a no-op if the service is already running.
 ///
+/// Waits for the start job to finish and returns an error if the service did not come up.
+///
 /// # Arguments
 ///
 /// * `name` - The service name
+/// * `timeout` - Seconds to wait for the start job to finish
+/// * `show_status` - Print the status table after starting
 ///
 pub async fn handle_start_service(
     name: &str,
+    timeout: u64,
     show_status: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let connection = zbus::Connection::system().await?;
+    let connection = connect().await?;
     let manager_proxy = ManagerProxy::new(&connection).await?;
 
     let full_service_name = get_full_service_name(name);
 
-    let active_state = get_active_state(&connection, &full_service_name).await;
+    let active_state = get_active_state(&connection, &full_service_name).await?;
 
     if active_state == "active" || active_state == "reloading" {
-        eprintln!("No-op. Service {full_service_name} is already {active_state}");
+        print_result(ActionResult::new(
+            "start",
+            name,
+            format!("No-op. Service {full_service_name} is already {active_state}"),
+        ));
     } else {
-        let start_service_result = start_service(&manager_proxy, &full_service_name).await;
+        let outcome = start_service(&manager_proxy, &full_service_name, timeout).await?;
+
+        if !outcome.reached(&["active"]) {
+            return Err(ServicerError::JobFailed {
+                name: name.to_string(),
+                action: "start".to_string(),
+                outcome: outcome.to_string(),
+            }
+            .into());
+        }
 
-        println!("service started: {start_service_result}");
+        print_result(
+            ActionResult::new("start", name, format!("service started: {name}"))
+                .with_job(&outcome, true),
+        );
     };
 
-    if show_status {
+    if show_status && is_table() {
         handle_show_status().await?;
     }
 
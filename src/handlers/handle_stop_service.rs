@@ -1,7 +1,12 @@
 use crate::{
     handlers::handle_show_status::handle_show_status,
     utils::{
-        service_actions::stop_service, service_names::get_full_service_name, systemd::ManagerProxy,
+        error::ServicerError,
+        output::{is_table, print_result, ActionResult},
+        scope::connect,
+        service_actions::stop_service,
+        service_names::get_full_service_name,
+        systemd::ManagerProxy,
     },
 };
 
@@ -12,20 +17,34 @@ use crate::{
 /// # Arguments
 ///
 /// * `name`- Name of the service to stop
+/// * `timeout` - Seconds to wait for the stop job to finish
+/// * `show_status` - Print the status table after stopping
 ///
 pub async fn handle_stop_service(
     name: &str,
+    timeout: u64,
     show_status: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let full_service_name = get_full_service_name(name);
 
-    let connection = zbus::Connection::system().await?;
+    let connection = connect().await?;
     let manager_proxy = ManagerProxy::new(&connection).await?;
-    stop_service(&manager_proxy, &full_service_name).await;
+    let outcome = stop_service(&manager_proxy, &full_service_name, timeout).await?;
 
-    println!("Stopped {name}");
+    if !outcome.reached(&["inactive", "failed"]) {
+        return Err(ServicerError::JobFailed {
+            name: name.to_string(),
+            action: "stop".to_string(),
+            outcome: outcome.to_string(),
+        }
+        .into());
+    }
+
+    print_result(
+        ActionResult::new("stop", name, format!("Stopped {name}")).with_job(&outcome, true),
+    );
 
-    if show_status {
+    if show_status && is_table() {
         handle_show_status().await?;
     }
 
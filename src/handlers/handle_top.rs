@@ -0,0 +1,536 @@
+use std::{
+    collections::HashMap,
+    io::Write,
+    time::{Duration, Instant},
+};
+
+use bytesize::ByteSize;
+use crossterm::{
+    cursor,
+    event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::{Attribute, Print, SetAttribute},
+    terminal::{self, ClearType},
+};
+use futures_util::StreamExt;
+use zbus::{message::Type, Connection, MatchRule, MessageStream};
+
+use crate::{
+    handlers::{
+        handle_show_logs::{handle_show_logs, ShowLogsParams},
+        handle_show_status::{get_cpu_times, get_service_statuses, ServiceStatus},
+    },
+    utils::{
+        scope::connect,
+        service_actions::{restart_service, start_service, stop_service, DEFAULT_JOB_TIMEOUT},
+        service_names::get_full_service_name,
+        systemd::ManagerProxy,
+    },
+};
+
+/// Time between refreshes when no unit changes
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Time to wait after a unit change for more changes, so a restart emitting a burst of signals
+/// refreshes once
+const CHANGE_DELAY: Duration = Duration::from_millis(200);
+
+/// Start of the message shown while statuses can't be refreshed
+const REFRESH_FAILED: &str = "Failed to refresh";
+
+/// Columns the table can be sorted by
+const COLUMNS: [&str; 8] = [
+    "name", "pid", "active", "restarts", "cpu %", "memory", "tasks", "boot",
+];
+
+/// State of the dashboard between redraws
+struct Dashboard {
+    /// Latest statuses, unsorted and unfiltered
+    statuses: Vec<ServiceStatus>,
+
+    /// CPU time and sample time of the previous refresh, by service name
+    cpu_samples: HashMap<String, (u64, Instant)>,
+
+    /// Index into `COLUMNS`
+    sort_column: usize,
+
+    /// Sort in descending order
+    sort_descending: bool,
+
+    /// Only show services whose name contains this text
+    filter: String,
+
+    /// Whether keystrokes edit the filter
+    editing_filter: bool,
+
+    /// Name of the selected service
+    selected: Option<String>,
+
+    /// Feedback from the last action, shown above the key help
+    message: String,
+}
+
+/// Restores the terminal when the dashboard exits, including on errors
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> std::io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(
+            std::io::stdout(),
+            terminal::EnterAlternateScreen,
+            cursor::Hide
+        )?;
+
+        Ok(TerminalGuard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(
+            std::io::stdout(),
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Live full-screen view of your services, like `top`.
+///
+/// Refreshes every two seconds, and shortly after systemd reports `PropertiesChanged` signals
+/// for a servicer unit. CPU usage is only sampled every two seconds.
+///
+/// Keys: `↑`/`↓` select, `←`/`→` change the sort column, `i` inverts the order, `/` filters by
+/// name, `s` starts, `x` stops, `r` restarts, `l` shows logs of the selected service and `q`
+/// quits.
+pub async fn handle_top() -> Result<(), Box<dyn std::error::Error>> {
+    let connection = connect().await?;
+    let manager_proxy = ManagerProxy::new(&connection).await?;
+
+    // systemd only emits unit signals once a client subscribes
+    let _ = manager_proxy.subscribe().await;
+
+    let rule = MatchRule::builder()
+        .msg_type(Type::Signal)
+        .sender("org.freedesktop.systemd1")?
+        .interface("org.freedesktop.DBus.Properties")?
+        .member("PropertiesChanged")?
+        .path_namespace("/org/freedesktop/systemd1/unit")?
+        .build();
+    let mut unit_changes = MessageStream::for_match_rule(rule, &connection, None).await?;
+
+    let mut dashboard = Dashboard {
+        statuses: vec![],
+        cpu_samples: HashMap::new(),
+        sort_column: 4,
+        sort_descending: true,
+        filter: String::new(),
+        editing_filter: false,
+        selected: None,
+        message: String::new(),
+    };
+
+    let mut guard = Some(TerminalGuard::enter()?);
+    let mut events = EventStream::new();
+    let mut refresh = tokio::time::interval(REFRESH_INTERVAL);
+
+    // When to refresh after a unit change, if one is waiting
+    let mut change_deadline: Option<tokio::time::Instant> = None;
+
+    loop {
+        tokio::select! {
+            _ = refresh.tick() => {
+                change_deadline = None;
+                dashboard.update(&connection, true).await;
+            }
+            _ = tokio::time::sleep_until(change_deadline.unwrap_or_else(tokio::time::Instant::now)),
+                if change_deadline.is_some() =>
+            {
+                change_deadline = None;
+                dashboard.update(&connection, false).await;
+            }
+            Some(Ok(message)) = unit_changes.next() => {
+                let is_servicer_unit = message
+                    .header()
+                    .path()
+                    .is_some_and(|path| path.ends_with("_2eser_2eservice"));
+
+                if is_servicer_unit {
+                    change_deadline
+                        .get_or_insert_with(|| tokio::time::Instant::now() + CHANGE_DELAY);
+                }
+                continue;
+            }
+            event = events.next() => {
+                let Some(Event::Key(key)) = event.transpose()? else {
+                    continue;
+                };
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+
+                match dashboard.handle_key(key) {
+                    Command::None => {}
+                    Command::Quit => break,
+                    Command::Action(action, name) => {
+                        dashboard.message = format!("{action} {name}...");
+                        dashboard.draw()?;
+
+                        dashboard.message = run_action(&manager_proxy, action, &name).await;
+                        dashboard.update(&connection, false).await;
+                    }
+                    Command::Logs(name) => {
+                        drop(guard.take());
+
+                        let params = ShowLogsParams {
+                            lines: Some(30),
+                            ..Default::default()
+                        };
+                        if let Err(error) = handle_show_logs(&[name], params).await {
+                            eprintln!("Failed to show logs: {error}");
+                        }
+                        println!("\nPress enter to return to servicer top");
+
+                        // The reader thread of the old stream would swallow the key, so the
+                        // stream is dropped and the key read directly
+                        drop(events);
+                        wait_for_enter()?;
+
+                        guard = Some(TerminalGuard::enter()?);
+                        events = EventStream::new();
+                    }
+                }
+            }
+        }
+
+        dashboard.draw()?;
+    }
+
+    Ok(())
+}
+
+/// What to do after a key press
+enum Command {
+    None,
+    Quit,
+
+    /// Run `start`, `stop` or `restart` on a service
+    Action(&'static str, String),
+
+    /// Show logs of a service
+    Logs(String),
+}
+
+/// Blocks until the enter key is pressed
+fn wait_for_enter() -> std::io::Result<()> {
+    loop {
+        if let Event::Key(key) = crossterm::event::read()? {
+            if key.code == KeyCode::Enter && key.kind == KeyEventKind::Press {
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl Dashboard {
+    /// Refreshes statuses. A failure, eg. a D-Bus timeout, is shown on screen and the previous
+    /// statuses are kept until the next refresh.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection` - zbus connection
+    /// * `sample_cpu` - Compute CPU usage since the previous sample. Otherwise the previous usage
+    ///   is kept, since usage over a few milliseconds jumps around
+    ///
+    async fn update(&mut self, connection: &Connection, sample_cpu: bool) {
+        match self.refresh(connection, sample_cpu).await {
+            Ok(()) if self.message.starts_with(REFRESH_FAILED) => self.message.clear(),
+            Ok(()) => {}
+            Err(error) => self.message = format!("{REFRESH_FAILED}: {error}"),
+        }
+    }
+
+    /// Reloads statuses and, if `sample_cpu` is set, computes CPU usage since the previous sample
+    async fn refresh(
+        &mut self,
+        connection: &Connection,
+        sample_cpu: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut statuses = get_service_statuses(connection).await?;
+
+        if !sample_cpu {
+            let previous_cpu: HashMap<&str, f32> = self
+                .statuses
+                .iter()
+                .map(|status| (status.name.as_str(), status.cpu))
+                .collect();
+
+            for status in statuses.iter_mut() {
+                status.cpu = previous_cpu
+                    .get(status.name.as_str())
+                    .copied()
+                    .unwrap_or_default();
+            }
+
+            self.statuses = statuses;
+            return Ok(());
+        }
+
+        let cpu_times = get_cpu_times(&statuses);
+        let now = Instant::now();
+
+        for (status, cpu_time) in statuses.iter_mut().zip(cpu_times) {
+            if let Some((previous_time, previous_instant)) = self.cpu_samples.get(&status.name) {
+                let elapsed_us = now.duration_since(*previous_instant).as_micros().max(1);
+                status.cpu =
+                    cpu_time.saturating_sub(*previous_time) as f32 * 100.0 / elapsed_us as f32;
+            }
+
+            self.cpu_samples
+                .insert(status.name.clone(), (cpu_time, now));
+        }
+
+        self.statuses = statuses;
+
+        Ok(())
+    }
+
+    /// Statuses after filtering and sorting, as shown on screen
+    fn visible_statuses(&self) -> Vec<&ServiceStatus> {
+        let mut visible: Vec<&ServiceStatus> = self
+            .statuses
+            .iter()
+            .filter(|status| status.name.contains(&self.filter))
+            .collect();
+
+        visible.sort_by(|a, b| {
+            let ordering = match COLUMNS[self.sort_column] {
+                "pid" => a.pid.cmp(&b.pid),
+                "active" => a.active.cmp(&b.active),
+                "restarts" => a.restarts.cmp(&b.restarts),
+                "cpu %" => a.cpu.total_cmp(&b.cpu),
+                "memory" => a.memory.cmp(&b.memory),
+                "tasks" => a.tasks.cmp(&b.tasks),
+                "boot" => a.enabled_on_boot.cmp(&b.enabled_on_boot),
+                _ => a.name.cmp(&b.name),
+            };
+
+            if self.sort_descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+
+        visible
+    }
+
+    /// Updates the state for a key press
+    fn handle_key(&mut self, key: KeyEvent) -> Command {
+        if self.editing_filter {
+            match key.code {
+                KeyCode::Enter => self.editing_filter = false,
+                KeyCode::Esc => {
+                    self.editing_filter = false;
+                    self.filter.clear();
+                }
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                }
+                KeyCode::Char(c) => self.filter.push(c),
+                _ => {}
+            }
+
+            return Command::None;
+        }
+
+        let visible: Vec<String> = self
+            .visible_statuses()
+            .iter()
+            .map(|status| status.name.clone())
+            .collect();
+        let position = self
+            .selected
+            .as_ref()
+            .and_then(|selected| visible.iter().position(|name| name == selected));
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Command::Quit,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Command::Quit
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                let index = position.map_or(0, |index| index.saturating_sub(1));
+                self.selected = visible.get(index).cloned();
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                let index = position.map_or(0, |index| (index + 1).min(visible.len().max(1) - 1));
+                self.selected = visible.get(index).cloned();
+            }
+            KeyCode::Left | KeyCode::Char('<') => {
+                self.sort_column = (self.sort_column + COLUMNS.len() - 1) % COLUMNS.len();
+            }
+            KeyCode::Right | KeyCode::Char('>') => {
+                self.sort_column = (self.sort_column + 1) % COLUMNS.len();
+            }
+            KeyCode::Char('i') => self.sort_descending = !self.sort_descending,
+            KeyCode::Char('/') => {
+                self.editing_filter = true;
+                self.filter.clear();
+            }
+            KeyCode::Char(c @ ('s' | 'x' | 'r' | 'l')) => {
+                let Some(name) = position.map(|index| visible[index].clone()) else {
+                    self.message = "Select a service with the arrow keys first".to_string();
+                    return Command::None;
+                };
+
+                return match c {
+                    's' => Command::Action("start", name),
+                    'x' => Command::Action("stop", name),
+                    'r' => Command::Action("restart", name),
+                    _ => Command::Logs(name),
+                };
+            }
+            _ => {}
+        }
+
+        Command::None
+    }
+
+    /// Redraws the whole screen
+    fn draw(&self) -> std::io::Result<()> {
+        let mut stdout = std::io::stdout();
+        let (width, height) = terminal::size()?;
+        let width = width as usize;
+
+        queue!(
+            stdout,
+            terminal::Clear(ClearType::All),
+            cursor::MoveTo(0, 0)
+        )?;
+
+        let visible = self.visible_statuses();
+        let order = if self.sort_descending { "desc" } else { "asc" };
+        let filter = if self.editing_filter {
+            format!("  filter: {}_", self.filter)
+        } else if !self.filter.is_empty() {
+            format!("  filter: {}", self.filter)
+        } else {
+            String::new()
+        };
+        let title = format!(
+            "servicer top - {} of {} services - sort: {} {order}{filter}",
+            visible.len(),
+            self.statuses.len(),
+            COLUMNS[self.sort_column],
+        );
+        queue!(
+            stdout,
+            Print(truncate(&title, width)),
+            cursor::MoveToNextLine(2)
+        )?;
+
+        let header = format!(
+            "{:<24} {:>8} {:<12} {:>8} {:>7} {:>10} {:>6} {:>5}",
+            "name", "pid", "active", "restarts", "cpu %", "memory", "tasks", "boot"
+        );
+        queue!(
+            stdout,
+            SetAttribute(Attribute::Bold),
+            Print(truncate(&header, width)),
+            SetAttribute(Attribute::Reset),
+            cursor::MoveToNextLine(1)
+        )?;
+
+        // Title, blank line, header, message and key help
+        let rows = (height as usize).saturating_sub(6);
+
+        for status in visible.iter().take(rows) {
+            let memory = if status.memory == 0 {
+                "0".to_string()
+            } else {
+                ByteSize::b(status.memory).to_string()
+            };
+            let row = format!(
+                "{:<24} {:>8} {:<12} {:>8} {:>7.1} {:>10} {:>6} {:>5}",
+                truncate(&status.name, 24),
+                status.pid,
+                status.active,
+                status.restarts,
+                status.cpu,
+                memory,
+                status.tasks,
+                if status.enabled_on_boot { "yes" } else { "no" }
+            );
+
+            let is_selected = self.selected.as_deref() == Some(status.name.as_str());
+            if is_selected {
+                queue!(stdout, SetAttribute(Attribute::Reverse))?;
+            }
+            queue!(
+                stdout,
+                Print(truncate(&row, width)),
+                SetAttribute(Attribute::Reset),
+                cursor::MoveToNextLine(1)
+            )?;
+        }
+
+        let help =
+            "↑↓ select  ←→ sort  i invert  / filter  s start  x stop  r restart  l logs  q quit";
+        queue!(
+            stdout,
+            cursor::MoveTo(0, height.saturating_sub(2)),
+            Print(truncate(&self.message, width)),
+            cursor::MoveTo(0, height.saturating_sub(1)),
+            SetAttribute(Attribute::Dim),
+            Print(truncate(help, width)),
+            SetAttribute(Attribute::Reset)
+        )?;
+
+        stdout.flush()
+    }
+}
+
+/// Runs an action on a service and returns a message describing the outcome
+///
+/// # Arguments
+///
+/// * `manager_proxy` - Manager proxy object
+/// * `action` - `start`, `stop` or `restart`
+/// * `name` - The short service name
+///
+async fn run_action(manager_proxy: &ManagerProxy<'_>, action: &str, name: &str) -> String {
+    let full_service_name = get_full_service_name(name);
+
+    let outcome = match action {
+        "start" => start_service(manager_proxy, &full_service_name, DEFAULT_JOB_TIMEOUT).await,
+        "stop" => stop_service(manager_proxy, &full_service_name, DEFAULT_JOB_TIMEOUT).await,
+        _ => {
+            restart_service(
+                manager_proxy,
+                &full_service_name,
+                false,
+                DEFAULT_JOB_TIMEOUT,
+            )
+            .await
+        }
+    };
+
+    match outcome {
+        Ok(outcome) => format!("{action} {name}: {outcome}"),
+        Err(error) => format!("{action} {name} failed: {error}"),
+    }
+}
+
+/// Cuts text to fit the terminal width
+///
+/// # Arguments
+///
+/// * `text`
+/// * `width` - Maximum number of characters
+///
+fn truncate(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
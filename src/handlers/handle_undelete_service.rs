@@ -0,0 +1,73 @@
+use crate::{
+    handlers::{
+        handle_enable_service::handle_enable_service, handle_show_status::handle_show_status,
+        handle_start_service::handle_start_service,
+    },
+    utils::{
+        error::ServicerError,
+        history::record_change,
+        output::{is_table, print_result, ActionResult},
+        scope::connect,
+        service_actions::{daemon_reload, DEFAULT_JOB_TIMEOUT},
+        service_names::{get_full_service_name, get_service_file_path},
+        systemd::ManagerProxy,
+        trash::{list_trash, restore_from_trash},
+    },
+};
+
+/// Restores the most recently deleted service with a name from the trash. It is enabled and
+/// started again if it was when deleted.
+///
+/// # Arguments
+///
+/// * `name` - The service name
+///
+pub async fn handle_undelete_service(name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let full_service_name = get_full_service_name(name);
+    let service_file_path = get_service_file_path(&full_service_name)?;
+    let path = service_file_path.to_string_lossy().to_string();
+
+    let Some(entry) = list_trash()?
+        .into_iter()
+        .rfind(|entry| entry.metadata.name == name)
+    else {
+        return Err(ServicerError::NotFound(format!("service {name} in the trash")).into());
+    };
+
+    if service_file_path.exists() {
+        return Err(ServicerError::UnitExists {
+            name: name.to_string(),
+            path,
+        }
+        .into());
+    }
+
+    restore_from_trash(&entry)?;
+    record_change(name, None, &std::fs::read_to_string(&service_file_path)?)?;
+
+    let connection = connect().await?;
+    let manager_proxy = ManagerProxy::new(&connection).await?;
+    daemon_reload(&manager_proxy).await?;
+
+    print_result(
+        ActionResult::new(
+            "undelete",
+            name,
+            format!("Restored {path}, deleted at {}", entry.metadata.deleted_at),
+        )
+        .with_path(&path),
+    );
+
+    if entry.metadata.enabled {
+        handle_enable_service(name, false).await?;
+    }
+    if entry.metadata.active {
+        handle_start_service(name, DEFAULT_JOB_TIMEOUT, false).await?;
+    }
+
+    if is_table() {
+        handle_show_status().await?;
+    }
+
+    Ok(())
+}
@@ -1,13 +1,32 @@
+pub mod handle_apply_manifest;
 pub mod handle_create_service;
 pub mod handle_delete_service;
+pub mod handle_diff_service_file;
 pub mod handle_disable_service;
+pub mod handle_doctor;
 pub mod handle_edit_service_file;
 pub mod handle_enable_service;
+pub mod handle_explain_failure;
+pub mod handle_export_logs;
+pub mod handle_import_pm2;
+pub mod handle_import_procfile;
+pub mod handle_import_supervisord;
+pub mod handle_lint_service_file;
 pub mod handle_print_paths;
 pub mod handle_print_service_file;
+pub mod handle_purge_trash;
 pub mod handle_reload_service;
 pub mod handle_rename_service;
+pub mod handle_restart_service;
+pub mod handle_resurrect_snapshot;
+pub mod handle_rollback_service_file;
+pub mod handle_save_snapshot;
+pub mod handle_show_history;
 pub mod handle_show_logs;
+pub mod handle_show_service;
 pub mod handle_show_status;
+pub mod handle_show_trash;
 pub mod handle_start_service;
 pub mod handle_stop_service;
+pub mod handle_top;
+pub mod handle_undelete_service;
@@ -1,23 +1,50 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, process::ExitCode};
 
 use clap::{Parser, Subcommand};
 
 mod handlers;
 mod utils;
 
+use handlers::handle_apply_manifest::handle_apply_manifest;
 use handlers::handle_create_service::{handle_create_service, ServiceCreateParams};
 use handlers::handle_delete_service::handle_delete_service;
+use handlers::handle_diff_service_file::handle_diff_service_file;
 use handlers::handle_disable_service::handle_disable_service;
+use handlers::handle_doctor::handle_doctor;
 use handlers::handle_edit_service_file::handle_edit_service_file;
 use handlers::handle_enable_service::handle_enable_service;
+use handlers::handle_explain_failure::handle_explain_failure;
+use handlers::handle_export_logs::handle_export_logs;
+use handlers::handle_import_pm2::handle_import_pm2;
+use handlers::handle_import_procfile::handle_import_procfile;
+use handlers::handle_import_supervisord::handle_import_supervisord;
+use handlers::handle_lint_service_file::handle_lint_service_file;
 use handlers::handle_print_paths::handle_print_paths;
 use handlers::handle_print_service_file::handle_print_service_file;
+use handlers::handle_purge_trash::handle_purge_trash;
 use handlers::handle_reload_service::handle_reload_service;
 use handlers::handle_rename_service::handle_rename_service;
-use handlers::handle_show_logs::handle_show_logs;
+use handlers::handle_restart_service::handle_restart_service;
+use handlers::handle_resurrect_snapshot::handle_resurrect_snapshot;
+use handlers::handle_rollback_service_file::handle_rollback_service_file;
+use handlers::handle_save_snapshot::handle_save_snapshot;
+use handlers::handle_show_history::handle_show_history;
+use handlers::handle_show_logs::{handle_show_logs, LogFormat, ShowLogsParams};
+use handlers::handle_show_service::handle_show_service;
 use handlers::handle_show_status::handle_show_status;
+use handlers::handle_show_trash::handle_show_trash;
 use handlers::handle_start_service::handle_start_service;
 use handlers::handle_stop_service::handle_stop_service;
+use handlers::handle_top::handle_top;
+use handlers::handle_undelete_service::handle_undelete_service;
+use regex::Regex;
+use utils::{
+    error::ServicerError,
+    log_filter::{parse_time, PriorityRange},
+    output::{flush_results, set_output_format, OutputFormat},
+    scope::{load_config, set_scope, Scope},
+    service_actions::DEFAULT_JOB_TIMEOUT,
+};
 
 /// servicer process manager
 #[derive(Parser, Debug)]
@@ -25,6 +52,19 @@ use handlers::handle_stop_service::handle_stop_service;
 struct Args {
     #[command(subcommand)]
     command: Commands,
+
+    /// Output format. `json`, `yaml` and `csv` print versioned, machine readable documents
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Table)]
+    output: OutputFormat,
+
+    /// Manage your own services with the user's systemd manager, without sudo. Can be made the
+    /// default with `mode = "user"` in ~/.config/servicer/config.toml
+    #[arg(long, global = true, conflicts_with = "system")]
+    user: bool,
+
+    /// Manage system services, overriding `mode = "user"` in the config file
+    #[arg(long, global = true)]
+    system: bool,
 }
 
 #[derive(Debug, Subcommand)]
@@ -67,15 +107,37 @@ pub enum Commands {
         internal_args: Vec<String>,
     },
 
+    /// Create, update and delete services to match a manifest, then enable, start, stop or
+    /// restart them. Prints the plan first
+    Apply {
+        /// The manifest, eg. servicer.toml. Files ending with .yaml or .yml are read as YAML
+        #[arg(short, long, default_value = "servicer.toml")]
+        file: PathBuf,
+
+        /// Delete servicer services the manifest doesn't list. They are moved to the trash
+        #[arg(long)]
+        prune: bool,
+
+        /// Only print the plan
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Create services from the configuration of another process manager
+    Import {
+        #[command(subcommand)]
+        command: ImportCommand,
+    },
+
     /// Open a text editor to create or edit the .service file for a service
     #[command(arg_required_else_help = true)]
     Edit {
         /// The service name, eg. hello-world
         name: String,
 
-        /// Custom editor to use. Default nano
-        #[arg(short, long, default_value = "nano")]
-        editor: String,
+        /// Custom editor to use, eg. vim or "code --wait". Defaults to $VISUAL, $EDITOR, then nano
+        #[arg(short, long)]
+        editor: Option<String>,
     },
 
     /// Start a service
@@ -83,12 +145,40 @@ pub enum Commands {
     Start {
         /// The service name, eg. hello-world
         name: String,
+
+        /// Seconds to wait for systemd to finish the job
+        #[arg(short, long, default_value_t = DEFAULT_JOB_TIMEOUT)]
+        timeout: u64,
     },
     /// Stop a service
     #[command(arg_required_else_help = true)]
     Stop {
         /// The service name, eg. hello-world
         name: String,
+
+        /// Seconds to wait for systemd to finish the job
+        #[arg(short, long, default_value_t = DEFAULT_JOB_TIMEOUT)]
+        timeout: u64,
+    },
+
+    /// Restart one or more services. A stopped service is started unless `--if-running` is set
+    #[command(arg_required_else_help = true)]
+    Restart {
+        /// The service names, eg. hello-world
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        names: Vec<String>,
+
+        /// Restart every service managed by servicer
+        #[arg(short, long)]
+        all: bool,
+
+        /// Only restart services that are already running
+        #[arg(long)]
+        if_running: bool,
+
+        /// Seconds to wait for systemd to finish each job
+        #[arg(short, long, default_value_t = DEFAULT_JOB_TIMEOUT)]
+        timeout: u64,
     },
 
     /// Enable a service to start on boot. Doesn't immediately start the service. To do so use the `start` command.
@@ -105,37 +195,138 @@ pub enum Commands {
         name: String,
     },
 
-    /// Delete a service, stopping and disabling it if necessary and removing the .service file (alias: delete, rm, remove)
+    /// Delete a service, stopping and disabling it if necessary and moving the .service file to the trash (alias: delete, rm, remove)
     #[command(arg_required_else_help = true, alias = "rm", alias = "remove")]
     Delete {
         /// The service name, eg. hello-world
         name: String,
+
+        /// Delete without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Restore the most recently deleted service with a name from the trash, enabling and
+    /// starting it again if it was when deleted
+    #[command(arg_required_else_help = true)]
+    Undelete {
+        /// The service name, eg. hello-world
+        name: String,
+    },
+
+    /// Manage deleted services kept in the trash
+    #[command(arg_required_else_help = true)]
+    Trash {
+        #[command(subcommand)]
+        command: TrashCommand,
     },
 
     /// View the status of your services (alias: ls)
     #[command(alias = "ls")]
     Status {},
 
-    /// View logs for a service
+    /// Display state, processes, uptime, resource usage and recent logs of one service
     #[command(arg_required_else_help = true)]
-    Logs {
-        /// The service name
+    Show {
+        /// The service name, eg hello-world
         name: String,
+    },
+
+    /// Explain why a service failed or keeps restarting
+    #[command(arg_required_else_help = true)]
+    Why {
+        /// The service name, eg hello-world
+        name: String,
+    },
+
+    /// Live dashboard of your services. Select with arrow keys, sort with < and >, filter with /
+    Top {},
 
-        /// Output the last N lines, instead of the default 15
-        #[arg(short = 'n', long, default_value_t = 15)]
-        lines: u32,
+    /// View logs of services, merged by time
+    Logs {
+        /// Service names or glob patterns, eg. api-*. Shows every service if omitted
+        names: Vec<String>,
+
+        /// Output the last N lines. Defaults to 15, or every line when --since, --until, --boot
+        /// or --this-run is given
+        #[arg(short = 'n', long)]
+        lines: Option<u32>,
 
         /// Follow the logs as they change
         #[arg(short, long, default_value_t = false)]
         follow: bool,
+
+        /// Show entries on or after a time, eg. "2024-05-01 13:00", "yesterday" or "10m ago"
+        #[arg(short = 'S', long, value_parser = parse_time)]
+        since: Option<u64>,
+
+        /// Show entries on or before a time, in the same formats as --since
+        #[arg(short = 'U', long, value_parser = parse_time)]
+        until: Option<u64>,
+
+        /// Show entries with a priority, eg. "err", or a range of priorities, eg. "err..warning"
+        #[arg(short, long)]
+        priority: Option<PriorityRange>,
+
+        /// Show entries whose message matches a regular expression
+        #[arg(short, long)]
+        grep: Option<Regex>,
+
+        /// Lines of context around each entry matching --grep
+        #[arg(short = 'C', long, default_value_t = 0, requires = "grep")]
+        context: usize,
+
+        /// Show entries of the current boot
+        #[arg(short, long, default_value_t = false)]
+        boot: bool,
+
+        /// Show entries since the service was last started
+        #[arg(long, default_value_t = false)]
+        this_run: bool,
+
+        /// How entries are printed
+        #[arg(long, value_enum, default_value_t = LogFormat::Short)]
+        format: LogFormat,
+    },
+
+    /// Write the entries of a service, its .service file and its unit properties to a
+    /// .tar.zst archive
+    #[command(arg_required_else_help = true)]
+    ExportLogs {
+        /// The service name, eg. hello-world
+        name: String,
+
+        /// Export entries on or after a time, eg. "2024-05-01 13:00", "yesterday" or "10m ago"
+        #[arg(short = 'S', long, value_parser = parse_time)]
+        since: Option<u64>,
+
+        /// Export entries on or before a time, in the same formats as --since
+        #[arg(short = 'U', long, value_parser = parse_time)]
+        until: Option<u64>,
+
+        /// Path of the archive. Defaults to <name>-logs.tar.zst
+        #[arg(short = 'o', long)]
+        file: Option<PathBuf>,
+    },
+
+    /// Check .service files for mistakes. Exits with an error if any is found
+    Lint {
+        /// The service name, eg. hello-world. Checks every service if omitted
+        name: Option<String>,
     },
 
+    /// Check that servicer can manage services on this machine, with hints to fix problems
+    Doctor {},
+
     /// Reloads a service having an `ExecScript`
     #[command(arg_required_else_help = true)]
     Reload {
         /// The service name
         name: String,
+
+        /// Seconds to wait for systemd to finish the job
+        #[arg(short, long, default_value_t = DEFAULT_JOB_TIMEOUT)]
+        timeout: u64,
     },
 
     /// Display contents of the .service file of a service
@@ -161,12 +352,206 @@ pub enum Commands {
         /// The new name
         new_name: String,
     },
+
+    /// List the versions of a .service file written by servicer, with time, user and command
+    #[command(arg_required_else_help = true)]
+    History {
+        /// The service name, eg. hello-world
+        name: String,
+    },
+
+    /// Show the changes between a version of a .service file and the current file
+    #[command(arg_required_else_help = true)]
+    Diff {
+        /// The service name, eg. hello-world
+        name: String,
+
+        /// Revision from `ser history`. Defaults to the version before the latest change
+        revision: Option<u32>,
+    },
+
+    /// Restore a version of a .service file, then reload systemd
+    #[command(arg_required_else_help = true)]
+    Rollback {
+        /// The service name, eg. hello-world
+        name: String,
+
+        /// Revision from `ser history`. Defaults to the version before the latest change
+        revision: Option<u32>,
+
+        /// Restart the service if it is running, without asking
+        #[arg(short, long)]
+        restart: bool,
+    },
+
+    /// Save every service with its drop-ins and whether it is enabled and running to one file
+    Save {
+        /// Where to write the snapshot. Defaults to dump.json in /var/lib/servicer, or
+        /// ~/.local/state/servicer for user services
+        #[arg(short = 'o', long)]
+        file: Option<PathBuf>,
+    },
+
+    /// Recreate the services of a `ser save` file, eg. on another host, then enable and start
+    /// them as they were
+    Resurrect {
+        /// The snapshot. Defaults to the file written by `ser save` without --file
+        file: Option<PathBuf>,
+
+        /// Accept suggested rewrites of User= and interpreter paths, and overwrite services with
+        /// other contents, without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ImportCommand {
+    /// Import the process list of `pm2 save` or an ecosystem file in JSON or YAML. Prints the pm2
+    /// options which are not translated
+    Pm2 {
+        /// dump.pm2 or ecosystem.config.json. Defaults to ~/.pm2/dump.pm2 of the user who called
+        /// sudo
+        file: Option<PathBuf>,
+
+        /// Use the env_<name> variables of each app, like `pm2 start --env <name>`
+        #[arg(long)]
+        env: Option<String>,
+
+        /// Start the services. From a process list only apps which were online are started
+        #[arg(short, long)]
+        start: bool,
+
+        /// Enable the services to start on boot
+        #[arg(short, long)]
+        enable: bool,
+
+        /// Only print the plan
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Import the process types of a Procfile, each running in the Procfile's directory with the
+    /// variables of .env next to it
+    Procfile {
+        /// The Procfile
+        #[arg(default_value = "Procfile")]
+        file: PathBuf,
+
+        /// File with variables for every process. Defaults to .env next to the Procfile
+        #[arg(long)]
+        env_file: Option<PathBuf>,
+
+        /// Prefix of the service names, eg. shop for shop-web. Defaults to the name of the
+        /// Procfile's directory, pass "" for none
+        #[arg(short, long)]
+        prefix: Option<String>,
+
+        /// Start the services
+        #[arg(short, long)]
+        start: bool,
+
+        /// Enable the services to start on boot
+        #[arg(short, long)]
+        enable: bool,
+
+        /// Only print the plan
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Import the programs of a supervisord configuration and the files it includes. Prints the
+    /// supervisord options which are not translated
+    Supervisord {
+        /// supervisord.conf or a file of conf.d. Defaults to the one supervisord reads
+        file: Option<PathBuf>,
+
+        /// Prefix of the service names, eg. shop for shop-web. Defaults to the group of each
+        /// program, pass "" for none
+        #[arg(short, long)]
+        prefix: Option<String>,
+
+        /// Start the programs having autostart, which is the default
+        #[arg(short, long)]
+        start: bool,
+
+        /// Enable the programs having autostart to start on boot
+        #[arg(short, long)]
+        enable: bool,
+
+        /// Only print the plan
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TrashCommand {
+    /// List deleted services, oldest first
+    #[command(alias = "ls")]
+    List {},
+
+    /// Permanently remove deleted services from the trash
+    Purge {
+        /// Only remove deleted services with this name
+        name: Option<String>,
+
+        /// Remove without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
 }
 
 #[tokio::main(flavor = "multi_thread", worker_threads = 4)]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() -> ExitCode {
     let args = Args::parse();
+    set_output_format(args.output);
+
+    let result = match load_config() {
+        Ok(config) => {
+            set_scope(if args.user {
+                Scope::User
+            } else if args.system {
+                Scope::System
+            } else {
+                config.mode
+            });
+
+            run(args).await
+        }
+        Err(error) => Err(error),
+    };
+
+    if let Err(error) = flush_results() {
+        eprintln!("Error: {error}");
+    }
 
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => match ServicerError::from_boxed(error) {
+            Ok(error) => {
+                eprintln!("Error: {error}");
+                if let Some(hint) = error.hint() {
+                    eprintln!("Hint: {hint}");
+                }
+
+                error.exit_code()
+            }
+            Err(error) => {
+                eprintln!("Error: {error}");
+                ExitCode::FAILURE
+            }
+        },
+    }
+}
+
+/// Runs the handler for a command
+///
+/// # Arguments
+///
+/// * `args` - Parsed command line arguments
+///
+async fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
     match args.command {
         Commands::Create {
             path,
@@ -192,9 +577,68 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             handle_create_service(params).await?
         }
 
-        Commands::Start { name } => handle_start_service(&name, true).await?,
+        Commands::Apply {
+            file,
+            prune,
+            dry_run,
+        } => handle_apply_manifest(&file, prune, dry_run).await?,
 
-        Commands::Stop { name } => handle_stop_service(&name, true).await?,
+        Commands::Import { command } => match command {
+            ImportCommand::Pm2 {
+                file,
+                env,
+                start,
+                enable,
+                dry_run,
+            } => handle_import_pm2(file.as_deref(), env.as_deref(), start, enable, dry_run).await?,
+
+            ImportCommand::Procfile {
+                file,
+                env_file,
+                prefix,
+                start,
+                enable,
+                dry_run,
+            } => {
+                handle_import_procfile(
+                    &file,
+                    env_file.as_deref(),
+                    prefix.as_deref(),
+                    start,
+                    enable,
+                    dry_run,
+                )
+                .await?
+            }
+
+            ImportCommand::Supervisord {
+                file,
+                prefix,
+                start,
+                enable,
+                dry_run,
+            } => {
+                handle_import_supervisord(
+                    file.as_deref(),
+                    prefix.as_deref(),
+                    start,
+                    enable,
+                    dry_run,
+                )
+                .await?
+            }
+        },
+
+        Commands::Start { name, timeout } => handle_start_service(&name, timeout, true).await?,
+
+        Commands::Stop { name, timeout } => handle_stop_service(&name, timeout, true).await?,
+
+        Commands::Restart {
+            names,
+            all,
+            if_running,
+            timeout,
+        } => handle_restart_service(&names, all, if_running, timeout, true).await?,
 
         Commands::Enable { name } => handle_enable_service(&name, true).await?,
 
@@ -202,23 +646,88 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         Commands::Status {} => handle_show_status().await?,
 
+        Commands::Show { name } => handle_show_service(&name).await?,
+
+        Commands::Why { name } => handle_explain_failure(&name).await?,
+
+        Commands::Top {} => handle_top().await?,
+
         Commands::Logs {
-            name,
+            names,
             lines,
             follow,
-        } => handle_show_logs(&name, lines, follow)?,
+            since,
+            until,
+            priority,
+            grep,
+            context,
+            boot,
+            this_run,
+            format,
+        } => {
+            let params = ShowLogsParams {
+                lines,
+                follow,
+                since,
+                until,
+                priority,
+                grep,
+                context,
+                boot,
+                this_run,
+                format,
+            };
+
+            handle_show_logs(&names, params).await?
+        }
+
+        Commands::ExportLogs {
+            name,
+            since,
+            until,
+            file,
+        } => handle_export_logs(&name, since, until, file).await?,
+
+        Commands::Doctor {} => handle_doctor().await?,
+
+        Commands::Lint { name } => handle_lint_service_file(name.as_deref())?,
 
-        Commands::Edit { name, editor } => handle_edit_service_file(&name, &editor)?,
+        Commands::Edit { name, editor } => {
+            handle_edit_service_file(&name, editor.as_deref()).await?
+        }
 
-        Commands::Reload { name } => handle_reload_service(&name, true).await?,
+        Commands::Reload { name, timeout } => handle_reload_service(&name, timeout, true).await?,
 
         Commands::Cat { name } => handle_print_service_file(&name)?,
 
         Commands::Which { name } => handle_print_paths(&name)?,
 
-        Commands::Delete { name } => handle_delete_service(&name, true).await?,
+        Commands::Delete { name, yes } => handle_delete_service(&name, yes, true, true).await?,
+
+        Commands::Undelete { name } => handle_undelete_service(&name).await?,
+
+        Commands::Trash { command } => match command {
+            TrashCommand::List {} => handle_show_trash()?,
+            TrashCommand::Purge { name, yes } => handle_purge_trash(name.as_deref(), yes)?,
+        },
 
         Commands::Rename { name, new_name } => handle_rename_service(&name, &new_name).await?,
+
+        Commands::History { name } => handle_show_history(&name)?,
+
+        Commands::Diff { name, revision } => handle_diff_service_file(&name, revision)?,
+
+        Commands::Rollback {
+            name,
+            revision,
+            restart,
+        } => handle_rollback_service_file(&name, revision, restart).await?,
+
+        Commands::Save { file } => handle_save_snapshot(file.as_deref()).await?,
+
+        Commands::Resurrect { file, yes } => {
+            handle_resurrect_snapshot(file.as_deref(), yes).await?
+        }
     }
 
     Ok(())
@@ -0,0 +1,219 @@
+use std::{fmt, process::ExitCode};
+
+use zbus::DBusError;
+
+use super::scope::is_user;
+
+/// Errors surfaced to the user. Each variant maps to a stable exit code so scripts can branch on
+/// the kind of failure.
+///
+/// | Exit code | Meaning                                      |
+/// |-----------|----------------------------------------------|
+/// | 1         | Unexpected error                             |
+/// | 2         | Invalid command line arguments               |
+/// | 3         | Service or file not found                    |
+/// | 4         | Permission denied                            |
+/// | 5         | Service already exists                       |
+/// | 6         | Invalid service name                         |
+/// | 7         | A start, stop, restart or reload job failed  |
+/// | 8         | D-Bus transport error                        |
+/// | 9         | I/O error                                    |
+#[derive(Debug)]
+pub enum ServicerError {
+    /// A service, file or binary doesn't exist. Holds a description, eg. `service hello-world`
+    NotFound(String),
+
+    /// The caller may not perform an action, eg. `org.freedesktop.DBus.Error.AccessDenied`.
+    /// Holds the action, eg. `start hello-world.ser.service`
+    PermissionDenied(String),
+
+    /// A service file already exists at the path
+    UnitExists { name: String, path: String },
+
+    /// The name can't be used for a systemd unit
+    InvalidName { name: String, reason: String },
+
+    /// A systemd job finished but the service didn't reach the expected state
+    JobFailed {
+        name: String,
+        action: String,
+        outcome: String,
+    },
+
+    /// Any other D-Bus error, including the error name returned by systemd
+    DBus(zbus::Error),
+
+    /// Any other I/O error
+    Io(std::io::Error),
+}
+
+impl ServicerError {
+    /// Maps a D-Bus error from a call on a unit. Well-known error names become their own variant.
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - The error returned by the proxy
+    /// * `action` - What was attempted, eg. `start`
+    /// * `full_service_name` - Full name of the service, having '.ser.service' at the end
+    ///
+    pub fn from_dbus(error: zbus::Error, action: &str, full_service_name: &str) -> Self {
+        match dbus_error_name(&error).as_deref() {
+            Some("org.freedesktop.systemd1.NoSuchUnit")
+            | Some("org.freedesktop.systemd1.LoadFailed")
+            | Some("org.freedesktop.DBus.Error.FileNotFound") => {
+                ServicerError::NotFound(format!("service {full_service_name}"))
+            }
+            Some("org.freedesktop.DBus.Error.AccessDenied")
+            | Some("org.freedesktop.DBus.Error.InteractiveAuthorizationRequired") => {
+                ServicerError::PermissionDenied(format!("{action} {full_service_name}"))
+            }
+            _ => ServicerError::DBus(error),
+        }
+    }
+
+    /// Recovers a `ServicerError` from the boxed errors returned by handlers. Errors of other
+    /// types are returned unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - Error returned by a handler
+    ///
+    pub fn from_boxed(
+        error: Box<dyn std::error::Error>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let error = match error.downcast::<ServicerError>() {
+            Ok(error) => return Ok(*error),
+            Err(error) => error,
+        };
+
+        let error = match error.downcast::<zbus::Error>() {
+            Ok(error) => return Ok((*error).into()),
+            Err(error) => error,
+        };
+
+        match error.downcast::<std::io::Error>() {
+            Ok(error) => Ok((*error).into()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Documented exit code for this class of error
+    pub fn exit_code(&self) -> ExitCode {
+        ExitCode::from(match self {
+            ServicerError::NotFound(_) => 3,
+            ServicerError::PermissionDenied(_) => 4,
+            ServicerError::UnitExists { .. } => 5,
+            ServicerError::InvalidName { .. } => 6,
+            ServicerError::JobFailed { .. } => 7,
+            ServicerError::DBus(_) => 8,
+            ServicerError::Io(_) => 9,
+        })
+    }
+
+    /// A suggested fix shown below the error message
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            ServicerError::NotFound(_) => {
+                Some("Run `ser status` to list the services managed by servicer.".to_string())
+            }
+            // sudo doesn't help user services, they live in your home directory and session
+            ServicerError::PermissionDenied(_) if is_user() => Some(
+                "Check that you own the file, eg. under ~/.config/systemd/user, and that you run                 ser in your own login session, not through su or sudo."
+                    .to_string(),
+            ),
+            ServicerError::PermissionDenied(_) => Some("Retry with sudo.".to_string()),
+            ServicerError::UnitExists { name, .. } => Some(format!(
+                "Provide a custom name with --name or delete the existing service with `ser delete {name}`."
+            )),
+            ServicerError::InvalidName { .. } => Some(
+                "Service names may only contain ASCII letters, digits and the characters :-_.\\@"
+                    .to_string(),
+            ),
+            ServicerError::JobFailed { name, .. } => {
+                Some(format!("Run `ser logs {name}` for details."))
+            }
+            ServicerError::DBus(_) if is_user() => Some(
+                "Ensure your systemd user manager is running, eg. `systemctl --user status`, and \
+                $XDG_RUNTIME_DIR is set. Sessions opened with su or sudo have neither."
+                    .to_string(),
+            ),
+            ServicerError::DBus(_) => Some(
+                "Ensure systemd is running and the system bus is reachable at /run/dbus/system_bus_socket."
+                    .to_string(),
+            ),
+            ServicerError::Io(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for ServicerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServicerError::NotFound(what) => write!(f, "{what} not found"),
+            ServicerError::PermissionDenied(action) => write!(f, "Permission denied to {action}"),
+            ServicerError::UnitExists { name, path } => {
+                write!(f, "Service {name} already exists at {path}")
+            }
+            ServicerError::InvalidName { name, reason } => {
+                write!(f, "Invalid service name {name:?}: {reason}")
+            }
+            ServicerError::JobFailed {
+                name,
+                action,
+                outcome,
+            } => write!(f, "Failed to {action} {name}: {outcome}"),
+            ServicerError::DBus(error) => match dbus_error_name(error) {
+                Some(name) => write!(f, "D-Bus error {name}: {error}"),
+                None => write!(f, "D-Bus error: {error}"),
+            },
+            ServicerError::Io(error) => write!(f, "I/O error: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for ServicerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ServicerError::DBus(error) => Some(error),
+            ServicerError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<zbus::Error> for ServicerError {
+    fn from(error: zbus::Error) -> Self {
+        match dbus_error_name(&error).as_deref() {
+            Some("org.freedesktop.DBus.Error.AccessDenied")
+            | Some("org.freedesktop.DBus.Error.InteractiveAuthorizationRequired") => {
+                ServicerError::PermissionDenied("call systemd".to_string())
+            }
+            _ => ServicerError::DBus(error),
+        }
+    }
+}
+
+impl From<std::io::Error> for ServicerError {
+    fn from(error: std::io::Error) -> Self {
+        match error.kind() {
+            std::io::ErrorKind::PermissionDenied => {
+                ServicerError::PermissionDenied(format!("access a file ({error})"))
+            }
+            _ => ServicerError::Io(error),
+        }
+    }
+}
+
+/// Name of a D-Bus error reply, eg. `org.freedesktop.DBus.Error.AccessDenied`
+///
+/// # Arguments
+///
+/// * `error`
+///
+fn dbus_error_name(error: &zbus::Error) -> Option<String> {
+    match error {
+        zbus::Error::MethodError(name, _, _) => Some(name.to_string()),
+        zbus::Error::FDO(error) => Some(error.name().to_string()),
+        _ => None,
+    }
+}
@@ -1,3 +1,7 @@
+use std::{os::unix::fs::PermissionsExt, path::Path};
+
+use super::error::ServicerError;
+
 /**
  * Runs `which` as SUDO_USER to find the path of the given binary.
  *
@@ -27,8 +31,56 @@ pub fn find_binary_path(
     let stdout = String::from_utf8_lossy(&output.stdout).to_string();
 
     if !output.status.success() {
-        panic!("Failed to find {binary_name} in PATH")
+        return Err(ServicerError::NotFound(format!("{binary_name} in the PATH of {user}")).into());
     }
 
     Ok(stdout)
 }
+
+/// Finds a binary in the `PATH` of this process, like `which`. Used for user services, which
+/// run as the calling user.
+///
+/// # Arguments
+///
+/// * `binary_name` - Find path for this interpreter
+///
+pub fn find_in_path(binary_name: &str) -> Result<String, ServicerError> {
+    std::env::var_os("PATH")
+        .iter()
+        .flat_map(std::env::split_paths)
+        .map(|directory| directory.join(binary_name))
+        .find(|path| is_executable(path))
+        .map(|path| path.to_string_lossy().to_string())
+        .ok_or_else(|| ServicerError::NotFound(format!("{binary_name} in the PATH")))
+}
+
+/// Finds the absolute path of an interpreter for a service running as `user`. User services
+/// have no `user` and are looked up in the `PATH` of this process.
+///
+/// # Arguments
+///
+/// * `interpreter` - The executable's name, eg. `node`, or its path
+/// * `user` - User the service runs as
+///
+pub fn find_interpreter(
+    interpreter: &str,
+    user: Option<&str>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    match user {
+        Some(user) => Ok(find_binary_path(interpreter, user)?
+            .trim_end_matches("\n")
+            .to_string()),
+        None => Ok(find_in_path(interpreter)?),
+    }
+}
+
+/// Whether a path is a file with an execute permission bit set
+///
+/// # Arguments
+///
+/// * `path`
+///
+pub fn is_executable(path: &Path) -> bool {
+    std::fs::metadata(path)
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
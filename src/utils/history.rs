@@ -0,0 +1,303 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    error::ServicerError,
+    scope::{get_state_directory, get_user_name},
+    service_names::validate_path_name,
+};
+
+/// Command recorded for versions found on disk which servicer did not write, eg. hand edits
+const EXTERNAL_COMMAND: &str = "(changed outside servicer)";
+
+/// Metadata of one version of a .service file. The contents are stored next to it in
+/// `<revision>.service`.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Version {
+    /// Revision number, starting at 1
+    pub revision: u32,
+
+    /// When the version was written, in RFC 3339 format
+    pub timestamp: String,
+
+    /// Who ran the command. The user who invoked sudo if known
+    pub user: String,
+
+    /// The servicer command which wrote the version, eg. `ser edit hello-world`
+    pub command: String,
+}
+
+/// Returns the history directory of a service, eg. `/var/lib/servicer/history/hello-world`, or
+/// `~/.local/state/servicer/history/hello-world` for user services
+///
+/// # Arguments
+///
+/// * `name` - The service name without '.ser.service'
+///
+pub fn get_history_directory(name: &str) -> Result<PathBuf, ServicerError> {
+    validate_path_name(name)?;
+
+    Ok(get_state_directory().join("history").join(name))
+}
+
+/// Lists the recorded versions of a service, oldest first. Empty if there are none.
+///
+/// # Arguments
+///
+/// * `name` - The service name without '.ser.service'
+///
+pub fn list_versions(name: &str) -> Result<Vec<Version>, ServicerError> {
+    let directory = get_history_directory(name)?;
+    if !directory.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut versions = vec![];
+
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            let metadata = std::fs::read_to_string(&path)?;
+            if let Ok(version) = serde_json::from_str::<Version>(&metadata) {
+                versions.push(version);
+            }
+        }
+    }
+
+    versions.sort_by_key(|version| version.revision);
+
+    Ok(versions)
+}
+
+/// Reads the contents of a version
+///
+/// # Arguments
+///
+/// * `name` - The service name without '.ser.service'
+/// * `revision` - Revision number
+///
+pub fn read_version(name: &str, revision: u32) -> Result<String, ServicerError> {
+    let path = get_history_directory(name)?.join(format!("{revision}.service"));
+
+    if !path.exists() {
+        return Err(ServicerError::NotFound(format!(
+            "revision {revision} of service {name}"
+        )));
+    }
+
+    Ok(std::fs::read_to_string(path)?)
+}
+
+/// Returns the newest revision whose contents differ from the current .service file, ie. the
+/// version before the latest change
+///
+/// # Arguments
+///
+/// * `name` - The service name without '.ser.service'
+/// * `current` - Contents of the .service file. `None` if it doesn't exist
+///
+pub fn get_previous_revision(name: &str, current: Option<&str>) -> Result<u32, ServicerError> {
+    for version in list_versions(name)?.iter().rev() {
+        if read_version(name, version.revision).ok().as_deref() != current {
+            return Ok(version.revision);
+        }
+    }
+
+    Err(ServicerError::NotFound(format!(
+        "earlier version of service {name}"
+    )))
+}
+
+/// Records a .service file written by servicer. If the file on disk was changed outside servicer
+/// since the last recorded version, the overwritten contents are recorded first so they can be
+/// restored too. Versions identical to the latest one are not recorded again.
+///
+/// # Arguments
+///
+/// * `name` - The service name without '.ser.service'
+/// * `previous` - Contents of the file before it was written. `None` for new files
+/// * `contents` - Contents written to the file
+///
+pub fn record_change(
+    name: &str,
+    previous: Option<&str>,
+    contents: &str,
+) -> Result<(), ServicerError> {
+    if let Some(previous) = previous {
+        record_version(name, previous, EXTERNAL_COMMAND, "unknown")?;
+    }
+
+    record_version(name, contents, &get_command(), &get_user())
+}
+
+/// Stores a version unless it matches the latest one
+///
+/// # Arguments
+///
+/// * `name` - The service name without '.ser.service'
+/// * `contents` - Contents of the .service file
+/// * `command` - Command which wrote the file
+/// * `user` - Who ran the command
+///
+fn record_version(
+    name: &str,
+    contents: &str,
+    command: &str,
+    user: &str,
+) -> Result<(), ServicerError> {
+    let versions = list_versions(name)?;
+
+    if let Some(latest) = versions.last() {
+        if read_version(name, latest.revision).ok().as_deref() == Some(contents) {
+            return Ok(());
+        }
+    }
+
+    let version = Version {
+        revision: versions.last().map_or(1, |latest| latest.revision + 1),
+        timestamp: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        user: user.to_string(),
+        command: command.to_string(),
+    };
+
+    let directory = get_history_directory(name)?;
+    std::fs::create_dir_all(&directory)?;
+
+    // Contents first, so a version is never listed without them
+    std::fs::write(
+        directory.join(format!("{}.service", version.revision)),
+        contents,
+    )?;
+    std::fs::write(
+        directory.join(format!("{}.json", version.revision)),
+        serde_json::to_string_pretty(&version).map_err(std::io::Error::other)?,
+    )?;
+
+    Ok(())
+}
+
+/// The user who invoked sudo, else the name of the current user
+fn get_user() -> String {
+    std::env::var("SUDO_USER")
+        .ok()
+        .or_else(get_user_name)
+        .unwrap_or_else(|| unsafe { libc::getuid() }.to_string())
+}
+
+/// The command line of this process, eg. `ser edit hello-world`
+fn get_command() -> String {
+    let args = std::env::args().skip(1).map(|arg| {
+        if arg.is_empty() || arg.contains(char::is_whitespace) {
+            format!("{arg:?}")
+        } else {
+            arg
+        }
+    });
+
+    std::iter::once("ser".to_string())
+        .chain(args)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::scope::TEST_STATE_DIRECTORY;
+
+    /// Points the state directory of this test at a new temporary directory
+    fn use_temp_state_directory() -> tempfile::TempDir {
+        let directory = tempfile::tempdir().unwrap();
+        TEST_STATE_DIRECTORY.set(Some(directory.path().to_path_buf()));
+        directory
+    }
+
+    fn revisions(name: &str) -> Vec<u32> {
+        list_versions(name)
+            .unwrap()
+            .iter()
+            .map(|version| version.revision)
+            .collect()
+    }
+
+    #[test]
+    fn numbers_revisions_from_one() {
+        let _directory = use_temp_state_directory();
+        assert!(list_versions("api").unwrap().is_empty());
+
+        for index in 0..11 {
+            let previous = (index > 0).then(|| format!("v{}", index - 1));
+            record_change("api", previous.as_deref(), &format!("v{index}")).unwrap();
+        }
+
+        // Sorted by number, not by file name
+        assert_eq!(revisions("api"), (1..=11).collect::<Vec<_>>());
+        assert_eq!(read_version("api", 11).unwrap(), "v10");
+        assert!(matches!(
+            read_version("api", 12),
+            Err(ServicerError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn skips_identical_contents() {
+        let _directory = use_temp_state_directory();
+
+        record_change("api", None, "a").unwrap();
+        record_change("api", Some("a"), "a").unwrap();
+        record_change("api", Some("a"), "b").unwrap();
+
+        assert_eq!(revisions("api"), [1, 2]);
+        assert_eq!(read_version("api", 2).unwrap(), "b");
+    }
+
+    #[test]
+    fn records_changes_made_outside_servicer() {
+        let _directory = use_temp_state_directory();
+
+        record_change("api", None, "a").unwrap();
+        record_change("api", Some("hand edited"), "b").unwrap();
+
+        let versions = list_versions("api").unwrap();
+        assert_eq!(revisions("api"), [1, 2, 3]);
+        assert_eq!(read_version("api", 2).unwrap(), "hand edited");
+        assert_eq!(versions[1].command, EXTERNAL_COMMAND);
+        assert_eq!(versions[1].user, "unknown");
+        assert!(versions[2].command.starts_with("ser"));
+    }
+
+    #[test]
+    fn previous_revision_skips_the_current_contents() {
+        let _directory = use_temp_state_directory();
+        assert!(matches!(
+            get_previous_revision("api", Some("a")),
+            Err(ServicerError::NotFound(_))
+        ));
+
+        record_change("api", None, "a").unwrap();
+        record_change("api", Some("a"), "b").unwrap();
+
+        // The newest version is the current file, so the one before it is restored
+        assert_eq!(get_previous_revision("api", Some("b")).unwrap(), 1);
+
+        // The file changed since, or was deleted, so the newest version is restored
+        assert_eq!(get_previous_revision("api", Some("c")).unwrap(), 2);
+        assert_eq!(get_previous_revision("api", None).unwrap(), 2);
+
+        // Revision 3 is the current file again, so revision 2 is restored
+        record_change("api", Some("b"), "a").unwrap();
+        assert_eq!(get_previous_revision("api", Some("a")).unwrap(), 2);
+    }
+
+    #[test]
+    fn rejects_names_leaving_the_history_directory() {
+        let _directory = use_temp_state_directory();
+
+        assert!(record_change("../api", None, "a").is_err());
+        assert!(list_versions("..").is_err());
+    }
+}
@@ -0,0 +1,254 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use super::{find_binary_path::find_interpreter, manifest::ServiceManifest, output::print_message};
+
+/// Shell used for commands with pipes, redirections, variables and the like
+const SHELL: &str = "/bin/sh";
+
+/// Characters which make a command line need a shell, eg. `|` or `$`
+const SHELL_CHARACTERS: &[char] = &['|', '&', ';', '<', '>', '(', ')', '$', '`', '*', '?', '~'];
+
+/// Services translated from the configuration of another process manager
+#[derive(Default)]
+pub struct ImportedServices {
+    /// Services by name, in the form of a manifest
+    pub services: BTreeMap<String, ServiceManifest>,
+
+    /// Options which were not translated
+    pub unsupported: Vec<UnsupportedOption>,
+}
+
+/// An option of another process manager which servicer doesn't translate
+pub struct UnsupportedOption {
+    /// Name of the app in the imported file
+    pub app: String,
+
+    /// The option, eg. `watch`
+    pub option: String,
+
+    /// Why it isn't translated and what to do instead
+    pub reason: String,
+}
+
+impl UnsupportedOption {
+    /// # Arguments
+    ///
+    /// * `app` - Name of the app in the imported file
+    /// * `option` - The option, eg. `watch`
+    /// * `reason` - Why it isn't translated and what to do instead
+    ///
+    pub fn new(app: &str, option: &str, reason: String) -> Self {
+        UnsupportedOption {
+            app: app.to_string(),
+            option: option.to_string(),
+            reason,
+        }
+    }
+}
+
+/// Turns the name of an app into a service name by replacing characters systemd doesn't allow
+/// with `-`, eg. `my api` becomes `my-api`
+///
+/// # Arguments
+///
+/// * `name` - Name of the app
+///
+pub fn to_service_name(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || ":-_.@".contains(c) {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// Name of an imported service, `<prefix>-<name>`, or the name alone without a prefix
+///
+/// # Arguments
+///
+/// * `prefix` - Prefix of the app, eg. `shop`. Ignored if empty.
+/// * `name` - Name of the process, eg. `web`
+///
+pub fn prefixed_service_name(prefix: Option<&str>, name: &str) -> String {
+    match prefix.map(str::trim).filter(|prefix| !prefix.is_empty()) {
+        Some(prefix) => to_service_name(&format!("{prefix}-{name}")),
+        None => to_service_name(name),
+    }
+}
+
+/// Translates a command line to a service running its program without an interpreter. Commands
+/// needing a shell, eg. `bin/worker > log 2>&1` or `FOO=1 bin/worker`, run with `/bin/sh -c`.
+///
+/// # Arguments
+///
+/// * `command` - The command line, eg. `bundle exec puma -p 5000`
+/// * `directory` - Directory the command runs in. Relative programs are resolved from it.
+/// * `user` - User whose `PATH` has the program. Defaults to the user who called sudo.
+///
+pub fn command_to_service(
+    command: &str,
+    directory: &Path,
+    user: Option<&str>,
+) -> Result<ServiceManifest, Box<dyn std::error::Error>> {
+    let words = split_command_line(command);
+
+    let needs_shell = command.contains(SHELL_CHARACTERS)
+        || words.first().is_some_and(|program| program.contains('='));
+
+    let (path, args) = if needs_shell {
+        // `$$` keeps systemd from expanding variables itself, the shell expands them
+        (
+            PathBuf::from(SHELL),
+            vec!["-c".to_string(), command.replace('$', "$$")],
+        )
+    } else {
+        let Some((program, args)) = words.split_first() else {
+            return Err("Empty command".into());
+        };
+
+        let path = if program.contains('/') {
+            directory.join(program)
+        } else {
+            let lookup_user = user
+                .map(str::to_string)
+                .or_else(|| std::env::var("SUDO_USER").ok());
+            find_interpreter(program, lookup_user.as_deref())
+                .map(PathBuf::from)
+                .map_err(|_| format!("{program} of `{command}` was not found in the PATH"))?
+        };
+
+        (path, args.to_vec())
+    };
+
+    Ok(ServiceManifest {
+        path,
+        interpreter: Some("none".to_string()),
+        args,
+        working_directory: Some(directory.to_path_buf()),
+        user: user.map(str::to_string),
+        ..Default::default()
+    })
+}
+
+/// Splits a command line into words like a POSIX shell, honoring single and double quotes and
+/// backslashes. Variables and globs are not expanded.
+///
+/// # Arguments
+///
+/// * `line` - Eg. `node server.js --name "hello world"`
+///
+pub fn split_command_line(line: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut rest = line;
+
+    while let Some((word, remainder)) = split_first_word(rest) {
+        words.push(word);
+        rest = remainder;
+    }
+
+    words
+}
+
+/// Splits the first word off a command line, like `split_command_line()`. Returns the word
+/// without quotes and the rest of the line as written, starting with whitespace unless it is
+/// empty. `None` if the line has no words.
+///
+/// # Arguments
+///
+/// * `line` - Eg. `"/opt/my app/run" --port 80`
+///
+pub fn split_first_word(line: &str) -> Option<(String, &str)> {
+    let line = line.trim_start();
+    if line.is_empty() {
+        return None;
+    }
+
+    let mut word = String::new();
+    let mut chars = line.char_indices();
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\'' => word.extend(chars.by_ref().map(|(_, c)| c).take_while(|&c| c != '\'')),
+            '"' => {
+                while let Some((_, c)) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => word.extend(chars.next().map(|(_, c)| c)),
+                        c => word.push(c),
+                    }
+                }
+            }
+            '\\' => word.extend(chars.next().map(|(_, c)| c)),
+            c if c.is_whitespace() => return Some((word, &line[index..])),
+            c => word.push(c),
+        }
+    }
+
+    Some((word, ""))
+}
+
+/// Prints the options which were not translated, grouped by app
+///
+/// # Arguments
+///
+/// * `options` - The options
+///
+pub fn print_unsupported(options: &[UnsupportedOption]) {
+    if options.is_empty() {
+        return;
+    }
+
+    print_message("Options which were not translated:");
+    for option in options {
+        print_message(&format!(
+            "  {}: {}: {}",
+            option.app, option.option, option.reason
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_quoted_words() {
+        assert_eq!(
+            split_command_line(r#"node "my app.js" --name='a b' c\ d "say \"hi\"""#),
+            ["node", "my app.js", "--name=a b", "c d", "say \"hi\""]
+        );
+        assert_eq!(split_command_line("  a\t b  "), ["a", "b"]);
+        assert_eq!(split_command_line("a '' b"), ["a", "", "b"]);
+        assert!(split_command_line("").is_empty());
+        assert!(split_command_line("   ").is_empty());
+    }
+
+    #[test]
+    fn split_first_word_keeps_rest_of_line() {
+        assert_eq!(
+            split_first_word(r#" "/opt/my app/run" --port "8 0""#),
+            Some(("/opt/my app/run".to_string(), r#" --port "8 0""#))
+        );
+        assert_eq!(
+            split_first_word("/bin/true"),
+            Some(("/bin/true".to_string(), ""))
+        );
+        assert_eq!(split_first_word("''"), Some((String::new(), "")));
+        assert_eq!(split_first_word("  "), None);
+    }
+
+    #[test]
+    fn makes_service_names() {
+        assert_eq!(to_service_name(" my api/v2 "), "my-api-v2");
+        assert_eq!(to_service_name("worker@1.x"), "worker@1.x");
+        assert_eq!(prefixed_service_name(Some("pm2"), "api"), "pm2-api");
+        assert_eq!(prefixed_service_name(Some(" "), "api"), "api");
+    }
+}
@@ -0,0 +1,1049 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io,
+    os::unix::fs::FileExt,
+    path::{Path, PathBuf},
+};
+
+use glob::{Pattern, PatternError};
+
+use super::{log_filter::LogFilter, scope::is_user};
+
+/// Directories holding journal files. `/var/log/journal` is used when journald storage is
+/// persistent, `/run/log/journal` otherwise.
+pub const JOURNAL_DIRECTORIES: [&str; 2] = ["/var/log/journal", "/run/log/journal"];
+
+/// First bytes of every journal file
+const SIGNATURE: &[u8; 8] = b"LPKSHHRH";
+
+/// Incompatible header flag. Object offsets in entries and entry arrays are 32 bit.
+const HEADER_INCOMPATIBLE_COMPACT: u32 = 16;
+
+const OBJECT_DATA: u8 = 1;
+const OBJECT_FIELD: u8 = 2;
+const OBJECT_ENTRY: u8 = 3;
+const OBJECT_ENTRY_ARRAY: u8 = 6;
+
+const OBJECT_COMPRESSED_XZ: u8 = 1;
+const OBJECT_COMPRESSED_LZ4: u8 = 2;
+const OBJECT_COMPRESSED_ZSTD: u8 = 4;
+
+/// State of a journal file which journald closed for good and renamed. Its successor is a new
+/// file.
+const STATE_ARCHIVED: u8 = 2;
+
+/// Size of the header at the start of every object: type, flags, reserved bytes and size
+const OBJECT_HEADER_SIZE: u64 = 16;
+
+/// Objects larger than this are rejected as corrupt instead of being allocated
+const MAX_OBJECT_SIZE: u64 = 64 * 1024 * 1024;
+
+/// A log entry with its fields, eg. `MESSAGE`, `PRIORITY` and `_PID`
+#[derive(Clone)]
+pub struct JournalEntry {
+    /// Wall clock time in microseconds since the Unix epoch
+    pub realtime: u64,
+
+    /// Sequence number within the journal files written by one journald instance
+    pub seqnum: u64,
+
+    /// Boot ID in hexadecimal, eg. `7690884f4dc0400098cd8d9c68ef8c3e`
+    pub boot_id: String,
+
+    /// Fields in the order they were written. Values which aren't UTF-8 are converted lossily.
+    pub fields: Vec<(String, String)>,
+}
+
+impl JournalEntry {
+    /// Value of the first field with the name, eg. `MESSAGE`
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Field name
+    ///
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Reads entries of some units, or of the kernel, from the journal files on disk, without
+/// `journalctl`.
+///
+/// Entries are looked up through the data objects of the matching fields, so only entries of the
+/// requested units are read. Files journald may still append to stay open after `read_tail()`,
+/// which lets `read_new()` only read what was appended since. The journal directories are
+/// scanned again when journald archives a followed file and starts a new one.
+pub struct JournalReader {
+    /// Fields whose values are matched against the patterns
+    fields: &'static [&'static str],
+
+    /// Values to match, eg. unit names like `hello.ser.service` or `*.ser.service`
+    patterns: Vec<Pattern>,
+
+    /// Files which weren't archived yet, with the entries already returned
+    followed: Vec<FollowedFile>,
+
+    /// IDs of every file read so far. Archived files keep their ID under a new name.
+    file_ids: HashSet<[u8; 16]>,
+
+    /// Whether a followed file was archived and the file replacing it wasn't found yet
+    rotated: bool,
+}
+
+/// Fields naming the unit of an entry. `_SYSTEMD_UNIT` is set on output of the service, `UNIT`
+/// on messages systemd writes about it, eg. `Started hello.ser.service`.
+const UNIT_FIELDS: [&str; 2] = ["_SYSTEMD_UNIT", "UNIT"];
+
+/// Fields naming the unit of an entry of a user service
+const USER_UNIT_FIELDS: [&str; 2] = ["_SYSTEMD_USER_UNIT", "USER_UNIT"];
+
+/// Unit fields of the services servicer manages, system or user services
+fn get_unit_fields() -> &'static [&'static str] {
+    if is_user() {
+        &USER_UNIT_FIELDS
+    } else {
+        &UNIT_FIELDS
+    }
+}
+
+/// Field naming how an entry reached journald. Kernel messages have `kernel`.
+const TRANSPORT_FIELDS: [&str; 1] = ["_TRANSPORT"];
+
+impl JournalReader {
+    /// Creates a reader for the log entries of services. Matches messages written by the
+    /// service and messages systemd writes about it.
+    ///
+    /// # Arguments
+    ///
+    /// * `full_service_names` - Full names of the services, having '.ser.service' at the end.
+    ///   May contain glob patterns, eg. `api-*.ser.service`
+    ///
+    pub fn for_units(full_service_names: &[String]) -> Result<Self, PatternError> {
+        let patterns = full_service_names
+            .iter()
+            .map(|name| Pattern::new(name))
+            .collect::<Result<_, _>>()?;
+
+        Ok(JournalReader::new(get_unit_fields(), patterns))
+    }
+
+    /// Creates a reader for messages of the kernel, eg. those of the OOM killer
+    pub fn for_kernel() -> Self {
+        JournalReader::new(
+            &TRANSPORT_FIELDS,
+            Pattern::new("kernel").into_iter().collect(),
+        )
+    }
+
+    fn new(fields: &'static [&'static str], patterns: Vec<Pattern>) -> Self {
+        JournalReader {
+            fields,
+            patterns,
+            followed: vec![],
+            file_ids: HashSet::new(),
+            rotated: false,
+        }
+    }
+
+    /// The unit an entry belongs to, as matched by the patterns of the reader
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - An entry returned by the reader
+    ///
+    pub fn get_unit<'a>(&self, entry: &'a JournalEntry) -> Option<&'a str> {
+        get_unit_fields()
+            .iter()
+            .filter_map(|field| entry.field(field))
+            .find(|unit| self.matches_unit(unit))
+    }
+
+    /// Whether a unit name, or another value of the matched fields, matches any pattern of the
+    /// reader
+    ///
+    /// # Arguments
+    ///
+    /// * `unit` - Full unit name, eg. `hello.ser.service`
+    ///
+    pub fn matches_unit(&self, unit: &str) -> bool {
+        self.patterns.iter().any(|pattern| pattern.matches(unit))
+    }
+
+    /// Returns the last entries over every journal file which pass the filter, oldest first.
+    /// Later calls to `read_new()` only return entries written after this call.
+    ///
+    /// # Arguments
+    ///
+    /// * `lines` - Maximum number of entries
+    /// * `filter` - Conditions on time, priority, boot and invocation
+    ///
+    pub fn read_tail(&mut self, lines: usize, filter: &LogFilter) -> io::Result<Vec<JournalEntry>> {
+        let mut entries = vec![];
+
+        for file in self.open_new_files()? {
+            let mut file = FollowedFile::new(file);
+            let (offsets, archived) = file.read_new_offsets(self.fields, |value| {
+                self.patterns.iter().any(|pattern| pattern.matches(value))
+            })?;
+
+            // Files are append only, so offsets are in chronological order. Walk backwards
+            // until enough entries pass the filter.
+            let mut file_entries = vec![];
+
+            for offset in offsets.into_iter().rev() {
+                if file_entries.len() >= lines {
+                    break;
+                }
+
+                let Ok(entry) = file.file.read_entry(offset) else {
+                    continue;
+                };
+
+                if filter.since.is_some_and(|since| entry.realtime < since) {
+                    break;
+                }
+                if filter.matches(&entry) {
+                    file_entries.push(entry);
+                }
+            }
+
+            entries.extend(file_entries);
+
+            if !archived {
+                self.followed.push(file);
+            }
+        }
+
+        sort_entries(&mut entries);
+        let skip = entries.len().saturating_sub(lines);
+
+        Ok(entries.split_off(skip))
+    }
+
+    /// Returns entries written since the previous call which pass the filter, oldest first
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - Conditions on time, priority, boot and invocation
+    ///
+    pub fn read_new(&mut self, filter: &LogFilter) -> io::Result<Vec<JournalEntry>> {
+        let mut entries = vec![];
+
+        // Files created since the last call are new as a whole
+        if self.rotated {
+            let files = self.open_new_files()?;
+            self.rotated = files.is_empty();
+            self.followed
+                .extend(files.into_iter().map(FollowedFile::new));
+        }
+
+        let patterns = &self.patterns;
+        let mut archived_files = vec![];
+
+        for (index, file) in self.followed.iter_mut().enumerate() {
+            let (offsets, archived) = file.read_new_offsets(self.fields, |value| {
+                patterns.iter().any(|pattern| pattern.matches(value))
+            })?;
+
+            for offset in offsets {
+                match file.file.read_entry(offset) {
+                    Ok(entry) if filter.matches(&entry) => entries.push(entry),
+                    _ => continue,
+                }
+            }
+
+            // Archived files don't change anymore, journald writes to a new file instead
+            if archived {
+                archived_files.push(index);
+            }
+        }
+
+        for index in archived_files.into_iter().rev() {
+            self.followed.remove(index);
+            self.rotated = true;
+        }
+
+        sort_entries(&mut entries);
+
+        Ok(entries)
+    }
+
+    /// Opens the journal files which weren't read yet. The same file may be reachable twice, eg.
+    /// through a symlinked machine directory.
+    fn open_new_files(&mut self) -> io::Result<Vec<JournalFile>> {
+        let mut files = open_journal_files()?;
+        files.retain(|file| self.file_ids.insert(file.file_id));
+
+        Ok(files)
+    }
+}
+
+/// A journal file which is read again when journald appends to it
+struct FollowedFile {
+    file: JournalFile,
+
+    /// Number of entries in the file when it was last read
+    n_entries: u64,
+
+    /// Number of field objects in the file when it was last read
+    n_fields: u64,
+
+    /// Field objects of the matched fields, by offset, with the newest data object already
+    /// checked. journald links new data objects at the head of their field.
+    fields: HashMap<u64, u64>,
+
+    /// Entries of each matching data object already returned, by data object offset
+    data_objects: HashMap<u64, u64>,
+}
+
+impl FollowedFile {
+    fn new(file: JournalFile) -> Self {
+        FollowedFile {
+            file,
+            n_entries: 0,
+            n_fields: 0,
+            fields: HashMap::new(),
+            data_objects: HashMap::new(),
+        }
+    }
+
+    /// Returns the offsets of matching entries appended since the previous call in ascending
+    /// order, and whether the file is archived. The first call returns every matching entry.
+    ///
+    /// # Arguments
+    ///
+    /// * `field_names` - Field names, eg. `_SYSTEMD_UNIT`
+    /// * `matches` - Whether a value is wanted, eg. `hello.ser.service`
+    ///
+    fn read_new_offsets(
+        &mut self,
+        field_names: &[&str],
+        matches: impl Fn(&str) -> bool,
+    ) -> io::Result<(Vec<u64>, bool)> {
+        let header = self.file.read_header_counts()?;
+        let mut offsets = EntryOffsets::default();
+
+        if header.n_entries == self.n_entries {
+            return Ok((offsets.into_sorted(), header.archived));
+        }
+
+        // The field hash table only has to be walked when a field was added
+        if header.n_fields != self.n_fields {
+            for field_offset in self.file.find_fields(field_names)? {
+                self.fields.entry(field_offset).or_insert(0);
+            }
+        }
+
+        for (field_offset, head) in self.fields.iter_mut() {
+            let (new_head, data_objects) =
+                self.file
+                    .find_field_data_objects(*field_offset, *head, &matches)?;
+
+            *head = new_head;
+            for data_offset in data_objects {
+                self.data_objects.entry(data_offset).or_insert(0);
+            }
+        }
+
+        for (data_offset, seen) in self.data_objects.iter_mut() {
+            let n_entries = self.file.get_data_n_entries(*data_offset)?;
+
+            if n_entries > *seen {
+                offsets.extend(
+                    self.file
+                        .get_data_entry_offsets(*data_offset, *seen, n_entries)?,
+                );
+                *seen = n_entries;
+            }
+        }
+
+        self.n_entries = header.n_entries;
+        self.n_fields = header.n_fields;
+
+        Ok((offsets.into_sorted(), header.archived))
+    }
+}
+
+/// Entry offsets of one file without duplicates, eg. an entry matching both `_SYSTEMD_UNIT` and
+/// `UNIT`
+#[derive(Default)]
+struct EntryOffsets(HashSet<u64>);
+
+impl EntryOffsets {
+    fn extend(&mut self, offsets: Vec<u64>) {
+        self.0.extend(offsets);
+    }
+
+    /// The offsets in ascending order
+    fn into_sorted(self) -> Vec<u64> {
+        let mut offsets: Vec<u64> = self.0.into_iter().collect();
+        offsets.sort_unstable();
+        offsets
+    }
+}
+
+/// Sorts entries of several files by time. Sequence numbers break ties between entries
+/// written in the same microsecond.
+///
+/// # Arguments
+///
+/// * `entries`
+///
+fn sort_entries(entries: &mut [JournalEntry]) {
+    entries.sort_by_key(|entry| (entry.realtime, entry.seqnum));
+}
+
+/// Opens every `.journal` file below the journal directories. Files which can't be parsed, eg.
+/// because journald is creating them, are skipped, as are unreadable files for user services.
+fn open_journal_files() -> io::Result<Vec<JournalFile>> {
+    let mut files = vec![];
+
+    for directory in JOURNAL_DIRECTORIES {
+        let mut directories = vec![PathBuf::from(directory)];
+
+        while let Some(directory) = directories.pop() {
+            let entries = match std::fs::read_dir(&directory) {
+                Ok(entries) => entries,
+                Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+                Err(error) => return Err(error),
+            };
+
+            for entry in entries.flatten() {
+                let path = entry.path();
+
+                if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                    directories.push(path);
+                } else if path
+                    .extension()
+                    .is_some_and(|extension| extension == "journal")
+                {
+                    match JournalFile::open(&path) {
+                        Ok(file) => files.push(file),
+                        // Users may read their own `user-<uid>.journal` but not the system one
+                        Err(error)
+                            if error.kind() == io::ErrorKind::PermissionDenied && !is_user() =>
+                        {
+                            return Err(error)
+                        }
+                        Err(_) => continue,
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+/// Counters of a journal file header
+struct HeaderCounts {
+    /// Whether journald closed the file and writes to a new one
+    archived: bool,
+
+    n_entries: u64,
+
+    n_fields: u64,
+}
+
+/// A journal file in the format described at https://systemd.io/JOURNAL_FILE_FORMAT/
+struct JournalFile {
+    file: File,
+
+    /// Unique ID of the file, which is kept when journald archives it under a new name
+    file_id: [u8; 16],
+
+    /// Whether the file uses 32 bit offsets in entries and entry arrays
+    compact: bool,
+
+    /// Offset of the items of the field hash table
+    field_hash_table_offset: u64,
+
+    /// Size of the items of the field hash table in bytes
+    field_hash_table_size: u64,
+}
+
+impl JournalFile {
+    /// Opens a journal file and reads its header
+    ///
+    /// # Arguments
+    ///
+    /// * `path`
+    ///
+    fn open(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+
+        let mut header = [0u8; 136];
+        file.read_exact_at(&mut header, 0)?;
+
+        if &header[0..8] != SIGNATURE {
+            return Err(invalid_data("not a journal file"));
+        }
+
+        Ok(JournalFile {
+            file,
+            file_id: header[24..40].try_into().unwrap(),
+            compact: read_u32(&header, 12) & HEADER_INCOMPATIBLE_COMPACT != 0,
+            field_hash_table_offset: read_u64(&header, 120),
+            field_hash_table_size: read_u64(&header, 128),
+        })
+    }
+
+    /// Reads a whole object, including its header
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - Offset of the object
+    /// * `object_type` - Expected object type, eg. `OBJECT_DATA`
+    ///
+    fn read_object(&self, offset: u64, object_type: u8) -> io::Result<Vec<u8>> {
+        let mut header = [0u8; OBJECT_HEADER_SIZE as usize];
+        self.file.read_exact_at(&mut header, offset)?;
+
+        let size = read_u64(&header, 8);
+        if header[0] != object_type || !(OBJECT_HEADER_SIZE..=MAX_OBJECT_SIZE).contains(&size) {
+            return Err(invalid_data("unexpected object"));
+        }
+
+        let mut object = vec![0u8; size as usize];
+        self.file.read_exact_at(&mut object, offset)?;
+
+        Ok(object)
+    }
+
+    /// Reads the counters of the header which change when journald appends to the file
+    fn read_header_counts(&self) -> io::Result<HeaderCounts> {
+        let mut header = [0u8; 224];
+        self.file.read_exact_at(&mut header, 0)?;
+
+        Ok(HeaderCounts {
+            archived: header[16] == STATE_ARCHIVED,
+            n_entries: read_u64(&header, 152),
+            n_fields: read_u64(&header, 216),
+        })
+    }
+
+    /// Finds the data objects of some fields whose value matches. Returns their offsets and
+    /// number of entries.
+    ///
+    /// # Arguments
+    ///
+    /// * `field_names` - Field names, eg. `_SYSTEMD_UNIT`
+    /// * `matches` - Whether a value is wanted, eg. `hello.ser.service`
+    ///
+    #[cfg(test)]
+    fn find_data_objects(
+        &self,
+        field_names: &[&str],
+        matches: impl Fn(&str) -> bool,
+    ) -> io::Result<Vec<(u64, u64)>> {
+        let mut data_objects = vec![];
+
+        for field_offset in self.find_fields(field_names)? {
+            let (_, offsets) = self.find_field_data_objects(field_offset, 0, &matches)?;
+
+            for data_offset in offsets {
+                data_objects.push((data_offset, self.get_data_n_entries(data_offset)?));
+            }
+        }
+
+        Ok(data_objects)
+    }
+
+    /// Finds the field objects of some fields. Returns their offsets.
+    ///
+    /// Field objects are found by walking every chain of the small field hash table. This avoids
+    /// implementing the keyed hash functions of the data hash table.
+    ///
+    /// # Arguments
+    ///
+    /// * `field_names` - Field names, eg. `_SYSTEMD_UNIT`
+    ///
+    fn find_fields(&self, field_names: &[&str]) -> io::Result<Vec<u64>> {
+        let mut hash_table = vec![0u8; self.field_hash_table_size as usize];
+        self.file
+            .read_exact_at(&mut hash_table, self.field_hash_table_offset)?;
+
+        let mut fields = vec![];
+
+        // Each hash item holds the offsets of the first and last field object in a chain
+        for item in hash_table.chunks_exact(16) {
+            let mut field_offset = read_u64(item, 0);
+
+            while field_offset != 0 {
+                let field = self.read_object(field_offset, OBJECT_FIELD)?;
+
+                if field_names.contains(&String::from_utf8_lossy(&field[40..]).as_ref()) {
+                    fields.push(field_offset);
+                }
+
+                field_offset = read_u64(&field, 24);
+            }
+        }
+
+        Ok(fields)
+    }
+
+    /// Walks the data objects of a field, newest first, and finds those whose value matches.
+    /// Returns the offset of the newest data object and the offsets of the matching ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `field_offset` - Offset of the field object
+    /// * `until` - Offset of a data object where the walk stops, eg. the newest one of a
+    ///   previous walk. 0 to walk every data object.
+    /// * `matches` - Whether a value is wanted, eg. `hello.ser.service`
+    ///
+    fn find_field_data_objects(
+        &self,
+        field_offset: u64,
+        until: u64,
+        matches: impl Fn(&str) -> bool,
+    ) -> io::Result<(u64, Vec<u64>)> {
+        let field = self.read_object(field_offset, OBJECT_FIELD)?;
+        let name = String::from_utf8_lossy(&field[40..]);
+
+        let head = read_u64(&field, 32);
+        let mut data_offset = head;
+        let mut data_objects = vec![];
+
+        while data_offset != 0 && data_offset != until {
+            let data = self.read_object(data_offset, OBJECT_DATA)?;
+            let payload = self.get_data_payload(&data).unwrap_or_default();
+            let payload = String::from_utf8_lossy(&payload);
+
+            let value = payload
+                .strip_prefix(name.as_ref())
+                .and_then(|value| value.strip_prefix('='));
+            if value.is_some_and(&matches) {
+                data_objects.push(data_offset);
+            }
+
+            data_offset = read_u64(&data, 32);
+        }
+
+        Ok((head, data_objects))
+    }
+
+    /// Number of entries referencing a data object
+    ///
+    /// # Arguments
+    ///
+    /// * `data_offset` - Offset of the data object
+    ///
+    fn get_data_n_entries(&self, data_offset: u64) -> io::Result<u64> {
+        let mut data = [0u8; 64];
+        self.file.read_exact_at(&mut data, data_offset)?;
+
+        if data[0] != OBJECT_DATA {
+            return Err(invalid_data("unexpected object"));
+        }
+
+        Ok(read_u64(&data, 56))
+    }
+
+    /// Lists the offsets of entries referencing a data object, oldest first
+    ///
+    /// The first entry is stored in the data object itself, the rest in a chain of entry
+    /// arrays. Arrays before the start are skipped by their header.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_offset` - Offset of the data object
+    /// * `start` - Index of the first entry to return
+    /// * `end` - Index after the last entry to return, at most the number of entries
+    ///
+    fn get_data_entry_offsets(
+        &self,
+        data_offset: u64,
+        start: u64,
+        end: u64,
+    ) -> io::Result<Vec<u64>> {
+        let mut data = [0u8; 56];
+        self.file.read_exact_at(&mut data, data_offset)?;
+        if data[0] != OBJECT_DATA {
+            return Err(invalid_data("unexpected object"));
+        }
+
+        let mut offsets = vec![];
+
+        let first_entry = read_u64(&data, 40);
+        if first_entry == 0 || end == 0 {
+            return Ok(offsets);
+        }
+        if start == 0 {
+            offsets.push(first_entry);
+        }
+
+        let mut index = 1;
+        let mut array_offset = read_u64(&data, 48);
+        let item_size = if self.compact { 4 } else { 8 };
+
+        while array_offset != 0 && index < end {
+            // Type, flags, size and the offset of the next array
+            let mut header = [0u8; 24];
+            self.file.read_exact_at(&mut header, array_offset)?;
+            if header[0] != OBJECT_ENTRY_ARRAY {
+                return Err(invalid_data("unexpected object"));
+            }
+
+            let n_items = read_u64(&header, 8).saturating_sub(24) / item_size;
+
+            if index + n_items <= start {
+                index += n_items;
+                array_offset = read_u64(&header, 16);
+                continue;
+            }
+
+            let array = self.read_object(array_offset, OBJECT_ENTRY_ARRAY)?;
+
+            for item in array[24..].chunks_exact(item_size as usize) {
+                let offset = read_offset(item);
+                if offset == 0 || index >= end {
+                    break;
+                }
+                if index >= start {
+                    offsets.push(offset);
+                }
+                index += 1;
+            }
+
+            array_offset = read_u64(&array, 16);
+        }
+
+        Ok(offsets)
+    }
+
+    /// Reads an entry and the payloads of its data objects
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - Offset of the entry object
+    ///
+    fn read_entry(&self, offset: u64) -> io::Result<JournalEntry> {
+        let entry = self.read_object(offset, OBJECT_ENTRY)?;
+        let item_size = if self.compact { 4 } else { 16 };
+
+        let mut fields = vec![];
+
+        for item in entry[64..].chunks_exact(item_size) {
+            let data = self.read_object(read_offset(&item[..item_size.min(8)]), OBJECT_DATA)?;
+
+            // Skip fields which can't be decompressed rather than losing the whole entry
+            let Ok(payload) = self.get_data_payload(&data) else {
+                continue;
+            };
+
+            if let Some(separator) = payload.iter().position(|byte| *byte == b'=') {
+                fields.push((
+                    String::from_utf8_lossy(&payload[..separator]).into_owned(),
+                    String::from_utf8_lossy(&payload[separator + 1..]).into_owned(),
+                ));
+            }
+        }
+
+        Ok(JournalEntry {
+            seqnum: read_u64(&entry, 16),
+            realtime: read_u64(&entry, 24),
+            boot_id: entry[40..56]
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect(),
+            fields,
+        })
+    }
+
+    /// The `FIELD=value` payload of a data object, decompressed
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The data object
+    ///
+    fn get_data_payload(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let payload_offset = if self.compact { 72 } else { 64 };
+        let payload = data
+            .get(payload_offset..)
+            .ok_or_else(|| invalid_data("truncated data object"))?;
+
+        match data[1] {
+            0 => Ok(payload.to_vec()),
+            OBJECT_COMPRESSED_ZSTD => zstd::stream::decode_all(payload),
+            OBJECT_COMPRESSED_LZ4 => {
+                // The uncompressed size precedes the LZ4 block
+                let size = payload
+                    .get(..8)
+                    .map(|size| read_u64(size, 0) as usize)
+                    .ok_or_else(|| invalid_data("truncated LZ4 payload"))?;
+
+                lz4_flex::block::decompress(&payload[8..], size)
+                    .map_err(|error| invalid_data(&error.to_string()))
+            }
+            OBJECT_COMPRESSED_XZ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "XZ compressed journal fields are not supported",
+            )),
+            _ => Err(invalid_data("unknown compression")),
+        }
+    }
+}
+
+/// Reads a little endian 64 bit number
+fn read_u64(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+}
+
+/// Reads a little endian 32 bit number
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+/// Reads an object offset, which is 32 bit in compact files and 64 bit otherwise
+fn read_offset(item: &[u8]) -> u64 {
+    match item.len() {
+        4 => read_u32(item, 0) as u64,
+        _ => read_u64(item, 0),
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Journal files written by journald in a namespace, one with 64 bit offsets and one compact
+    /// file with zstd compressed fields. Both hold the same messages of `fixture.ser.service` and
+    /// `other.service`.
+    const FIXTURES: [&str; 2] = ["regular.journal", "compact-zstd.journal"];
+
+    const LONG_MESSAGE_PREFIX: &str = "a long line a long line";
+
+    fn open_fixture(name: &str) -> JournalFile {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/journal")
+            .join(name);
+
+        JournalFile::open(&path).unwrap()
+    }
+
+    /// Reads the entries whose unit fields match a pattern, like `JournalReader::read_tail()`
+    fn read_unit_entries(file: &JournalFile, pattern: &str) -> Vec<JournalEntry> {
+        let pattern = Pattern::new(pattern).unwrap();
+        let mut offsets = EntryOffsets::default();
+
+        for (data_offset, n_entries) in file
+            .find_data_objects(&UNIT_FIELDS, |value| pattern.matches(value))
+            .unwrap()
+        {
+            offsets.extend(
+                file.get_data_entry_offsets(data_offset, 0, n_entries)
+                    .unwrap(),
+            );
+        }
+
+        offsets
+            .into_sorted()
+            .into_iter()
+            .map(|offset| file.read_entry(offset).unwrap())
+            .collect()
+    }
+
+    fn messages(entries: &[JournalEntry]) -> Vec<&str> {
+        entries
+            .iter()
+            .map(|entry| entry.field("MESSAGE").unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn reads_entries_of_unit() {
+        for name in FIXTURES {
+            let file = open_fixture(name);
+            let entries = read_unit_entries(&file, "fixture.ser.service");
+
+            let seqnums: Vec<u64> = entries.iter().map(|entry| entry.seqnum).collect();
+            assert_eq!(seqnums, [3, 5, 6, 7], "{name}");
+
+            let messages = messages(&entries);
+            assert_eq!(messages[0], "Started fixture", "{name}");
+            assert!(messages[1].starts_with(LONG_MESSAGE_PREFIX), "{name}");
+            assert_eq!(
+                messages[2], "fixture.ser.service: Deactivated successfully.",
+                "{name}"
+            );
+            assert_eq!(messages[3], "Stopped fixture", "{name}");
+
+            assert!(entries
+                .windows(2)
+                .all(|pair| pair[0].realtime < pair[1].realtime));
+
+            let started = &entries[0];
+            assert_eq!(started.boot_id, "7690884f4dc0400098cd8d9c68ef8c3e");
+            assert_eq!(started.field("PRIORITY"), Some("6"));
+            assert_eq!(started.field("SYSLOG_IDENTIFIER"), Some("fixture"));
+            assert_eq!(started.field("_SYSTEMD_UNIT"), Some("fixture.ser.service"));
+            assert_eq!(started.field("UNIT"), None);
+        }
+    }
+
+    #[test]
+    fn reads_realtime() {
+        let realtimes = [
+            ("regular.journal", 1792198454223669),
+            ("compact-zstd.journal", 1792198457119353),
+        ];
+
+        for (name, realtime) in realtimes {
+            let entries = read_unit_entries(&open_fixture(name), "fixture.ser.service");
+            assert_eq!(entries[0].realtime, realtime, "{name}");
+        }
+    }
+
+    #[test]
+    fn matches_systemd_unit_and_unit_fields() {
+        for name in FIXTURES {
+            let file = open_fixture(name);
+
+            // `UNIT=` is set on the message about the unit, not written by it
+            let entries = read_unit_entries(&file, "fixture.ser.service");
+            let by_systemd: Vec<u64> = entries
+                .iter()
+                .filter(|entry| entry.field("UNIT") == Some("fixture.ser.service"))
+                .map(|entry| entry.seqnum)
+                .collect();
+            assert_eq!(by_systemd, [6], "{name}");
+
+            let other = read_unit_entries(&file, "other.service");
+            assert_eq!(messages(&other), ["Other unit says hi"], "{name}");
+
+            assert_eq!(read_unit_entries(&file, "*.ser.service").len(), 4, "{name}");
+            assert_eq!(read_unit_entries(&file, "*.service").len(), 5, "{name}");
+
+            // Values are matched whole
+            assert!(read_unit_entries(&file, "fixture.ser").is_empty(), "{name}");
+            assert!(read_unit_entries(&file, "missing.ser.service").is_empty());
+        }
+    }
+
+    #[test]
+    fn reads_entry_offsets_from_start() {
+        for name in FIXTURES {
+            let file = open_fixture(name);
+            let data_objects = file
+                .find_data_objects(&["_SYSTEMD_UNIT"], |value| value == "fixture.ser.service")
+                .unwrap();
+            assert_eq!(data_objects.len(), 1, "{name}");
+
+            let (data_offset, n_entries) = data_objects[0];
+            assert_eq!(n_entries, 3, "{name}");
+
+            let all = file
+                .get_data_entry_offsets(data_offset, 0, n_entries)
+                .unwrap();
+            assert_eq!(all.len(), 3, "{name}");
+            assert!(all.windows(2).all(|pair| pair[0] < pair[1]));
+
+            let new = file
+                .get_data_entry_offsets(data_offset, 1, n_entries)
+                .unwrap();
+            assert_eq!(new, all[1..], "{name}");
+
+            let none = file
+                .get_data_entry_offsets(data_offset, n_entries, n_entries)
+                .unwrap();
+            assert!(none.is_empty(), "{name}");
+        }
+    }
+
+    #[test]
+    fn decompresses_zstd_fields() {
+        let flags = [
+            ("regular.journal", false, 0),
+            ("compact-zstd.journal", true, OBJECT_COMPRESSED_ZSTD),
+        ];
+
+        for (name, compact, compression) in flags {
+            let file = open_fixture(name);
+            assert_eq!(file.compact, compact, "{name}");
+
+            let data_objects = file
+                .find_data_objects(&["MESSAGE"], |value| value.starts_with(LONG_MESSAGE_PREFIX))
+                .unwrap();
+            assert_eq!(data_objects.len(), 1, "{name}");
+
+            let data = file.read_object(data_objects[0].0, OBJECT_DATA).unwrap();
+            assert_eq!(data[1], compression, "{name}");
+
+            let payload = file.get_data_payload(&data).unwrap();
+            assert_eq!(
+                String::from_utf8(payload).unwrap(),
+                format!("MESSAGE={}", "a long line ".repeat(20)),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn follows_appended_entries() {
+        let matches = |value: &str| Pattern::new("*.ser.service").unwrap().matches(value);
+        let read_messages = |followed: &mut FollowedFile| {
+            let (offsets, archived) = followed.read_new_offsets(&UNIT_FIELDS, matches).unwrap();
+            assert!(!archived);
+
+            offsets
+                .into_iter()
+                .map(|offset| {
+                    let entry = followed.file.read_entry(offset).unwrap();
+                    entry.field("MESSAGE").unwrap().to_string()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut followed = FollowedFile::new(open_fixture("follow-before.journal"));
+        assert_eq!(read_messages(&mut followed), ["Started fixture"]);
+        assert!(read_messages(&mut followed).is_empty());
+
+        // journald appended to the file. `third.ser.service` has a new data object.
+        followed.file = open_fixture("follow-after.journal");
+        assert_eq!(
+            read_messages(&mut followed),
+            ["Third unit says hi", "Stopped fixture"]
+        );
+        assert!(read_messages(&mut followed).is_empty());
+    }
+
+    #[test]
+    fn detects_archived_files() {
+        let path = std::env::temp_dir().join(format!("servicer-{}.journal", std::process::id()));
+        let mut contents = std::fs::read(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/journal/regular.journal"),
+        )
+        .unwrap();
+
+        let mut followed = FollowedFile::new(open_fixture("regular.journal"));
+        let (_, archived) = followed.read_new_offsets(&UNIT_FIELDS, |_| true).unwrap();
+        assert!(!archived);
+
+        contents[16] = STATE_ARCHIVED;
+        std::fs::write(&path, contents).unwrap();
+        let file = JournalFile::open(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let mut followed = FollowedFile::new(file.unwrap());
+        let (offsets, archived) = followed.read_new_offsets(&UNIT_FIELDS, |_| true).unwrap();
+        assert_eq!(offsets.len(), 5);
+        assert!(archived);
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+        let error = JournalFile::open(&path).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,339 @@
+use std::{ops::RangeInclusive, str::FromStr, time::SystemTime};
+
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+
+use super::journal::JournalEntry;
+
+/// Syslog priority names, indexed by their level
+const PRIORITY_NAMES: [&str; 8] = [
+    "emerg", "alert", "crit", "err", "warning", "notice", "info", "debug",
+];
+
+/// Conditions a log entry must meet to be shown. Empty conditions match every entry.
+#[derive(Default)]
+pub struct LogFilter {
+    /// Earliest time in microseconds since the Unix epoch
+    pub since: Option<u64>,
+
+    /// Latest time in microseconds since the Unix epoch
+    pub until: Option<u64>,
+
+    /// Accepted priority levels
+    pub priority: Option<PriorityRange>,
+
+    /// Boot ID in hexadecimal. Only entries of this boot match
+    pub boot_id: Option<String>,
+
+    /// Invocation IDs in hexadecimal. Only entries of these runs of services match
+    pub invocation_ids: Option<Vec<String>>,
+}
+
+impl LogFilter {
+    /// Whether an entry meets every condition
+    ///
+    /// # Arguments
+    ///
+    /// * `entry`
+    ///
+    pub fn matches(&self, entry: &JournalEntry) -> bool {
+        if self.since.is_some_and(|since| entry.realtime < since)
+            || self.until.is_some_and(|until| entry.realtime > until)
+        {
+            return false;
+        }
+
+        if let Some(priority) = &self.priority {
+            let level = entry
+                .field("PRIORITY")
+                .and_then(|level| level.parse::<u8>().ok());
+
+            if !level.is_some_and(|level| priority.0.contains(&level)) {
+                return false;
+            }
+        }
+
+        if let Some(boot_id) = &self.boot_id {
+            if &entry.boot_id != boot_id {
+                return false;
+            }
+        }
+
+        if let Some(invocation_ids) = &self.invocation_ids {
+            // Output of the service has `_SYSTEMD_INVOCATION_ID`, messages of systemd about the
+            // service have `INVOCATION_ID`
+            let entry_invocation_id = entry
+                .field("_SYSTEMD_INVOCATION_ID")
+                .or(entry.field("INVOCATION_ID"));
+
+            if !entry_invocation_id.is_some_and(|id| invocation_ids.iter().any(|other| other == id))
+            {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// A range of syslog priority levels, eg. `err..warning`. A single level such as `err` includes
+/// every more important level, like `journalctl --priority`.
+#[derive(Clone, Debug)]
+pub struct PriorityRange(pub RangeInclusive<u8>);
+
+impl FromStr for PriorityRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_level = |level: &str| -> Result<u8, String> {
+            PRIORITY_NAMES
+                .iter()
+                .position(|name| *name == level)
+                .map(|level| level as u8)
+                .or_else(|| level.parse::<u8>().ok().filter(|level| *level <= 7))
+                .ok_or_else(|| {
+                    format!(
+                        "unknown priority {level:?}, expected one of {} or 0 to 7",
+                        PRIORITY_NAMES.join(", ")
+                    )
+                })
+        };
+
+        match s.split_once("..") {
+            Some((from, to)) => {
+                let (from, to) = (parse_level(from)?, parse_level(to)?);
+                Ok(PriorityRange(from.min(to)..=from.max(to)))
+            }
+            None => Ok(PriorityRange(0..=parse_level(s)?)),
+        }
+    }
+}
+
+/// Parses a time for `--since` and `--until` into microseconds since the Unix epoch.
+///
+/// Accepts absolute local times like `2024-05-01 13:00:00`, `2024-05-01` or `13:00`, the words
+/// `now`, `today`, `yesterday` and `tomorrow`, and relative times like `10m ago`, `-1h 30min`
+/// or `+5m`.
+///
+/// # Arguments
+///
+/// * `time`
+///
+pub fn parse_time(time: &str) -> Result<u64, String> {
+    let time = time.trim();
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_err(|error| error.to_string())?;
+
+    let relative = if let Some(duration) = time.strip_suffix("ago") {
+        Some((duration, false))
+    } else if let Some(duration) = time.strip_prefix('-') {
+        Some((duration, false))
+    } else {
+        time.strip_prefix('+').map(|duration| (duration, true))
+    };
+
+    if let Some((duration, future)) = relative {
+        let duration = humantime::parse_duration(duration.trim())
+            .map_err(|error| format!("invalid duration {duration:?}: {error}"))?;
+
+        let time = if future {
+            now.checked_add(duration)
+        } else {
+            now.checked_sub(duration)
+        };
+
+        return time
+            .map(|time| time.as_micros() as u64)
+            .ok_or_else(|| format!("{duration:?} is out of range"));
+    }
+
+    let today = Local::now().date_naive();
+    let date_time = match time {
+        "now" => return Ok(now.as_micros() as u64),
+        "today" => today.and_time(NaiveTime::MIN),
+        "yesterday" => today.pred_opt().unwrap_or(today).and_time(NaiveTime::MIN),
+        "tomorrow" => today.succ_opt().unwrap_or(today).and_time(NaiveTime::MIN),
+        _ => ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"]
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(time, format).ok())
+            .or_else(|| {
+                NaiveDate::parse_from_str(time, "%Y-%m-%d")
+                    .ok()
+                    .map(|date| date.and_time(NaiveTime::MIN))
+            })
+            .or_else(|| {
+                ["%H:%M:%S", "%H:%M"]
+                    .iter()
+                    .find_map(|format| NaiveTime::parse_from_str(time, format).ok())
+                    .map(|time| today.and_time(time))
+            })
+            .ok_or_else(|| {
+                format!("invalid time {time:?}, expected eg. \"2024-05-01 13:00\" or \"10m ago\"")
+            })?,
+    };
+
+    Local
+        .from_local_datetime(&date_time)
+        .earliest()
+        .map(|date_time| date_time.timestamp_micros() as u64)
+        .ok_or_else(|| format!("{time:?} doesn't exist in the local time zone"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(realtime: u64, fields: &[(&str, &str)]) -> JournalEntry {
+        JournalEntry {
+            realtime,
+            seqnum: 1,
+            boot_id: "7690884f4dc0400098cd8d9c68ef8c3e".to_string(),
+            fields: fields
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_micros() as u64
+    }
+
+    #[test]
+    fn parses_priority_ranges() {
+        let range = |s: &str| s.parse::<PriorityRange>().map(|range| range.0);
+
+        assert_eq!(range("err"), Ok(0..=3));
+        assert_eq!(range("debug"), Ok(0..=7));
+        assert_eq!(range("4"), Ok(0..=4));
+        assert_eq!(range("err..warning"), Ok(3..=4));
+        assert_eq!(range("warning..err"), Ok(3..=4));
+        assert_eq!(range("0..info"), Ok(0..=6));
+
+        assert!(range("8").is_err());
+        assert!(range("error").is_err());
+        assert!(range("err..").is_err());
+    }
+
+    #[test]
+    fn empty_filter_matches_everything() {
+        assert!(LogFilter::default().matches(&entry(0, &[])));
+    }
+
+    #[test]
+    fn matches_time_range() {
+        let filter = LogFilter {
+            since: Some(100),
+            until: Some(200),
+            ..Default::default()
+        };
+
+        assert!(!filter.matches(&entry(99, &[])));
+        assert!(filter.matches(&entry(100, &[])));
+        assert!(filter.matches(&entry(200, &[])));
+        assert!(!filter.matches(&entry(201, &[])));
+    }
+
+    #[test]
+    fn matches_priority() {
+        let filter = LogFilter {
+            priority: Some("err..warning".parse().unwrap()),
+            ..Default::default()
+        };
+
+        assert!(filter.matches(&entry(0, &[("PRIORITY", "3")])));
+        assert!(filter.matches(&entry(0, &[("PRIORITY", "4")])));
+        assert!(!filter.matches(&entry(0, &[("PRIORITY", "2")])));
+        assert!(!filter.matches(&entry(0, &[("PRIORITY", "6")])));
+
+        // Entries without a valid priority are hidden once a priority is asked for
+        assert!(!filter.matches(&entry(0, &[])));
+        assert!(!filter.matches(&entry(0, &[("PRIORITY", "high")])));
+    }
+
+    #[test]
+    fn matches_boot() {
+        let mut filter = LogFilter {
+            boot_id: Some("7690884f4dc0400098cd8d9c68ef8c3e".to_string()),
+            ..Default::default()
+        };
+        assert!(filter.matches(&entry(0, &[])));
+
+        filter.boot_id = Some("00000000000000000000000000000000".to_string());
+        assert!(!filter.matches(&entry(0, &[])));
+    }
+
+    #[test]
+    fn matches_invocation_ids() {
+        let filter = LogFilter {
+            invocation_ids: Some(vec!["aaaa".to_string(), "bbbb".to_string()]),
+            ..Default::default()
+        };
+
+        assert!(filter.matches(&entry(0, &[("_SYSTEMD_INVOCATION_ID", "aaaa")])));
+        assert!(filter.matches(&entry(0, &[("INVOCATION_ID", "bbbb")])));
+        assert!(!filter.matches(&entry(0, &[("_SYSTEMD_INVOCATION_ID", "cccc")])));
+        assert!(!filter.matches(&entry(0, &[])));
+    }
+
+    #[test]
+    fn parses_relative_times() {
+        let minute = 60 * 1_000_000;
+
+        for (time, offset) in [
+            ("10m ago", -10 * minute),
+            ("-1h 30min", -90 * minute),
+            ("+5m", 5 * minute),
+            ("now", 0),
+        ] {
+            let before = now();
+            let parsed = parse_time(time).unwrap();
+            let after = now();
+
+            assert!(
+                parsed as i64 >= before as i64 + offset && parsed as i64 <= after as i64 + offset,
+                "{time}"
+            );
+        }
+
+        assert!(parse_time("10 parsecs ago").is_err());
+    }
+
+    #[test]
+    fn parses_absolute_times() {
+        let local = |date_time: &str| {
+            let date_time = NaiveDateTime::parse_from_str(date_time, "%Y-%m-%d %H:%M:%S").unwrap();
+            Local
+                .from_local_datetime(&date_time)
+                .earliest()
+                .unwrap()
+                .timestamp_micros() as u64
+        };
+
+        assert_eq!(
+            parse_time("2024-05-01 13:00:05"),
+            Ok(local("2024-05-01 13:00:05"))
+        );
+        assert_eq!(
+            parse_time("2024-05-01T13:00:05"),
+            Ok(local("2024-05-01 13:00:05"))
+        );
+        assert_eq!(
+            parse_time("2024-05-01 13:00"),
+            Ok(local("2024-05-01 13:00:00"))
+        );
+        assert_eq!(parse_time(" 2024-05-01 "), Ok(local("2024-05-01 00:00:00")));
+
+        let today = parse_time("today").unwrap();
+        assert!(parse_time("yesterday").unwrap() < today);
+        assert!(today <= now());
+        assert!(parse_time("tomorrow").unwrap() > now());
+        assert_eq!(parse_time("00:00"), Ok(today));
+
+        assert!(parse_time("2024-13-01").is_err());
+        assert!(parse_time("soon").is_err());
+    }
+}
@@ -0,0 +1,45 @@
+use std::path::Path;
+
+use zbus::Connection;
+use zbus_macros::proxy;
+
+use super::error::ServicerError;
+
+/// Directory where logind marks users whose services keep running after logout
+const LINGER_DIRECTORY: &str = "/var/lib/systemd/linger";
+
+/// Proxy object for `org.freedesktop.login1.Manager`
+#[proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1",
+    gen_blocking = false
+)]
+pub trait LoginManager {
+    /// [📖](https://www.freedesktop.org/software/systemd/man/org.freedesktop.login1.html#SetUserLinger()) Call interface method `SetUserLinger`.
+    #[zbus(name = "SetUserLinger")]
+    fn set_user_linger(&self, uid: u32, enable: bool, interactive: bool) -> zbus::Result<()>;
+}
+
+/// Whether the user manager of a user runs without a login session, so that user services start
+/// on boot and survive logout
+///
+/// # Arguments
+///
+/// * `user` - User name
+///
+pub fn is_lingering(user: &str) -> bool {
+    Path::new(LINGER_DIRECTORY).join(user).exists()
+}
+
+/// Enables lingering for the calling user, like `loginctl enable-linger`. logind lives on the
+/// system bus even for user services. polkit may ask for a password.
+pub async fn enable_linger() -> Result<(), ServicerError> {
+    let connection = Connection::system().await?;
+    let login_manager_proxy = LoginManagerProxy::new(&connection).await?;
+
+    login_manager_proxy
+        .set_user_linger(unsafe { libc::getuid() }, true, true)
+        .await
+        .map_err(|error| ServicerError::from_dbus(error, "enable lingering for", "your user"))
+}
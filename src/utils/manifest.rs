@@ -0,0 +1,278 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Deserializer};
+
+/// A servicer manifest, eg. `servicer.toml`, describing the services of a host
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    /// Services by name, eg. `[services.api]`
+    #[serde(default)]
+    pub services: BTreeMap<String, ServiceManifest>,
+}
+
+/// A service in a manifest. Takes the options of `ser create` plus restart policy, resource
+/// limits and whether the service should be enabled and running.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServiceManifest {
+    /// The file to run. Relative paths start at the directory of the manifest
+    pub path: PathBuf,
+
+    /// Interpreter name or path, eg. `node`. Detected from the file extension if not set.
+    /// `none` runs the file itself
+    #[serde(default)]
+    pub interpreter: Option<String>,
+
+    /// Args passed to the interpreter before the file, eg. `--max-old-space-size=4096`
+    #[serde(default)]
+    pub interpreter_args: Vec<String>,
+
+    /// Args passed to the file
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// Environment variables
+    #[serde(default, deserialize_with = "deserialize_env")]
+    pub env: BTreeMap<String, String>,
+
+    /// Working directory. Defaults to the directory of `path`
+    #[serde(default)]
+    pub working_directory: Option<PathBuf>,
+
+    /// User the service runs as. Defaults to the user who called sudo. Not allowed for user
+    /// services
+    #[serde(default)]
+    pub user: Option<String>,
+
+    /// Restart whenever the service exits, like `ser create --auto-restart`
+    #[serde(default)]
+    pub auto_restart: bool,
+
+    /// `Restart=` policy, eg. `on-failure`. Overrides `auto_restart`
+    #[serde(default)]
+    pub restart: Option<String>,
+
+    /// `RestartSec=`, eg. `5s`
+    #[serde(default)]
+    pub restart_sec: Option<String>,
+
+    /// `MemoryMax=`, eg. `512M`
+    #[serde(default)]
+    pub memory_max: Option<String>,
+
+    /// `CPUQuota=`, eg. `50%`
+    #[serde(default)]
+    pub cpu_quota: Option<String>,
+
+    /// `TasksMax=`, eg. `100`
+    #[serde(default)]
+    pub tasks_max: Option<String>,
+
+    /// Seconds the service must keep running for a start to succeed, like `startsecs` of
+    /// supervisord
+    #[serde(default)]
+    pub start_secs: Option<u64>,
+
+    /// Signal which stops the service, eg. `SIGINT` or `INT`
+    #[serde(default)]
+    pub stop_signal: Option<String>,
+
+    /// Enable or disable the service. Left as it is if not set
+    #[serde(default)]
+    pub enable: Option<bool>,
+
+    /// Keep the service running or stopped. Left as it is if not set
+    #[serde(default)]
+    pub start: Option<bool>,
+}
+
+/// Value of an environment variable. Numbers and booleans are accepted so that `PORT = 8080`
+/// doesn't need quotes.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum EnvValue {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+}
+
+impl fmt::Display for EnvValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvValue::String(value) => f.write_str(value),
+            EnvValue::Integer(value) => write!(f, "{value}"),
+            EnvValue::Float(value) => write!(f, "{value}"),
+            EnvValue::Bool(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+/// Reads environment variables whose values may be strings, numbers or booleans
+fn deserialize_env<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<String, String>, D::Error> {
+    let env = BTreeMap::<String, EnvValue>::deserialize(deserializer)?;
+
+    Ok(env
+        .into_iter()
+        .map(|(key, value)| (key, value.to_string()))
+        .collect())
+}
+
+/// Reads a manifest. Files ending with `.yaml` or `.yml` are read as YAML, others as TOML.
+///
+/// # Arguments
+///
+/// * `path` - Path of the manifest
+///
+pub fn load_manifest(path: &Path) -> Result<Manifest, Box<dyn std::error::Error>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|error| format!("Failed to read {}: {error}", path.display()))?;
+
+    let manifest = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("yaml" | "yml") => serde_yaml::from_str(&contents)
+            .map_err(|error| format!("Invalid manifest {}: {error}", path.display()))?,
+        _ => toml::from_str(&contents)
+            .map_err(|error| format!("Invalid manifest {}: {error}", path.display()))?,
+    };
+
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOML: &str = r#"
+[services.api]
+path = "server.js"
+interpreter_args = ["--max-old-space-size=4096"]
+args = ["--port", "8080"]
+auto_restart = true
+memory_max = "512M"
+start = true
+
+[services.api.env]
+NODE_ENV = "production"
+PORT = 8080
+RATIO = 0.5
+DEBUG = false
+
+[services.worker]
+path = "/usr/local/bin/worker"
+interpreter = "none"
+enable = false
+"#;
+
+    const YAML: &str = r#"
+services:
+  api:
+    path: server.js
+    restart: on-failure
+    restart_sec: 5s
+    env:
+      PORT: 8080
+"#;
+
+    /// Writes a manifest to a temporary directory and loads it
+    fn load(file_name: &str, contents: &str) -> Result<Manifest, String> {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join(file_name);
+        std::fs::write(&path, contents).unwrap();
+
+        load_manifest(&path).map_err(|error| error.to_string())
+    }
+
+    #[test]
+    fn reads_toml() {
+        let manifest = load("servicer.toml", TOML).unwrap();
+        assert_eq!(
+            manifest.services.keys().collect::<Vec<_>>(),
+            ["api", "worker"]
+        );
+
+        let api = &manifest.services["api"];
+        assert_eq!(api.path, Path::new("server.js"));
+        assert_eq!(api.interpreter, None);
+        assert_eq!(api.interpreter_args, ["--max-old-space-size=4096"]);
+        assert_eq!(api.args, ["--port", "8080"]);
+        assert!(api.auto_restart);
+        assert_eq!(api.memory_max.as_deref(), Some("512M"));
+        assert_eq!(api.start, Some(true));
+        assert_eq!(api.enable, None);
+
+        let worker = &manifest.services["worker"];
+        assert_eq!(worker.interpreter.as_deref(), Some("none"));
+        assert!(!worker.auto_restart);
+        assert_eq!(worker.enable, Some(false));
+        assert!(worker.env.is_empty());
+    }
+
+    #[test]
+    fn reads_env_values_of_any_type() {
+        let manifest = load("servicer.toml", TOML).unwrap();
+        let env: Vec<(&str, &str)> = manifest.services["api"]
+            .env
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
+
+        assert_eq!(
+            env,
+            [
+                ("DEBUG", "false"),
+                ("NODE_ENV", "production"),
+                ("PORT", "8080"),
+                ("RATIO", "0.5")
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_yaml() {
+        for file_name in ["servicer.yaml", "servicer.yml"] {
+            let manifest = load(file_name, YAML).unwrap();
+            let api = &manifest.services["api"];
+
+            assert_eq!(api.restart.as_deref(), Some("on-failure"));
+            assert_eq!(api.restart_sec.as_deref(), Some("5s"));
+            assert_eq!(api.env["PORT"], "8080");
+        }
+    }
+
+    #[test]
+    fn reads_empty_manifest() {
+        assert!(load("servicer.toml", "").unwrap().services.is_empty());
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        let error = load(
+            "servicer.toml",
+            "[services.api]\npath = \"a.js\"\nauto_restrat = true\n",
+        )
+        .unwrap_err();
+        assert!(error.contains("auto_restrat"), "{error}");
+
+        let error = load("servicer.toml", "[service.api]\npath = \"a.js\"\n").unwrap_err();
+        assert!(error.contains("service"), "{error}");
+    }
+
+    #[test]
+    fn rejects_services_without_path() {
+        let error = load("servicer.toml", "[services.api]\nargs = []\n").unwrap_err();
+        assert!(error.contains("path"), "{error}");
+    }
+
+    #[test]
+    fn rejects_missing_files() {
+        let error = load_manifest(Path::new("/nonexistent/servicer.toml")).unwrap_err();
+        assert!(error.to_string().starts_with("Failed to read"));
+    }
+}
@@ -1,5 +1,23 @@
+pub mod error;
 pub mod find_binary_path;
+pub mod history;
+pub mod import;
+pub mod journal;
+pub mod log_filter;
+pub mod logind;
+pub mod manifest;
+pub mod output;
+pub mod pm2;
+pub mod polkit;
 pub mod process_status;
+pub mod procfile;
+pub mod prompt;
+pub mod scope;
 pub mod service_actions;
 pub mod service_names;
+pub mod snapshot;
+pub mod supervisord;
 pub mod systemd;
+pub mod trash;
+pub mod unit_file;
+pub mod unit_lint;
@@ -0,0 +1,282 @@
+use std::{
+    io::IsTerminal,
+    sync::{Mutex, OnceLock},
+};
+
+use clap::ValueEnum;
+use crossterm::style::Stylize;
+use serde::Serialize;
+use similar::TextDiff;
+
+use super::service_actions::JobOutcome;
+
+/// Version of the JSON and YAML documents. Bumped when fields are removed or change meaning.
+pub const DOCUMENT_VERSION: u32 = 1;
+
+/// Output format selected with the global `--output` flag
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable tables and messages
+    #[default]
+    Table,
+
+    /// A versioned JSON document
+    Json,
+
+    /// A versioned YAML document
+    Yaml,
+
+    /// Comma separated values with a header row
+    Csv,
+}
+
+static OUTPUT_FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+
+/// Results of commands which don't print a table. Printed as one document by `flush_results()`
+/// so that commands calling other commands, eg. `create --start`, still emit a single document.
+static RESULTS: Mutex<Vec<ActionResult>> = Mutex::new(Vec::new());
+
+/// A versioned document wrapping the records of a command
+#[derive(Serialize)]
+struct Document<'a, T: Serialize> {
+    /// Format version, see `DOCUMENT_VERSION`
+    version: u32,
+
+    /// What the items are, eg. `status` or `result`
+    kind: &'a str,
+
+    items: &'a [T],
+}
+
+/// Result of a command which changes a service, eg. `start` or `delete`
+#[derive(Serialize, Clone)]
+pub struct ActionResult {
+    /// What was done, eg. `start`
+    pub action: String,
+
+    /// The short service name
+    pub service: String,
+
+    /// Whether the action succeeded
+    pub success: bool,
+
+    /// Human readable summary, printed as is in table mode
+    pub message: String,
+
+    /// File created or removed by the action
+    pub path: Option<String>,
+
+    /// Result of the systemd job, eg. `done`
+    pub job_result: Option<String>,
+
+    /// Active state after the action
+    pub active_state: Option<String>,
+
+    /// Sub-state after the action
+    pub sub_state: Option<String>,
+}
+
+impl ActionResult {
+    /// A successful result without job details
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - What was done, eg. `enable`
+    /// * `service` - The short service name
+    /// * `message` - Human readable summary
+    ///
+    pub fn new(action: &str, service: &str, message: String) -> Self {
+        ActionResult {
+            action: action.to_string(),
+            service: service.to_string(),
+            success: true,
+            message,
+            path: None,
+            job_result: None,
+            active_state: None,
+            sub_state: None,
+        }
+    }
+
+    /// Attaches the file created or removed by the action
+    pub fn with_path(mut self, path: &str) -> Self {
+        self.path = Some(path.to_string());
+        self
+    }
+
+    /// Attaches the outcome of a systemd job
+    ///
+    /// # Arguments
+    ///
+    /// * `outcome` - The job outcome
+    /// * `success` - Whether the outcome is the expected one
+    ///
+    pub fn with_job(mut self, outcome: &JobOutcome, success: bool) -> Self {
+        self.success = success;
+        self.job_result = Some(outcome.result.clone());
+        self.active_state = Some(outcome.active_state.clone());
+        self.sub_state = Some(outcome.sub_state.clone());
+        self
+    }
+}
+
+/// Sets the output format. Must be called once before any output is printed.
+pub fn set_output_format(format: OutputFormat) {
+    OUTPUT_FORMAT
+        .set(format)
+        .expect("Output format is already set");
+}
+
+/// The output format selected with `--output`
+pub fn output_format() -> OutputFormat {
+    OUTPUT_FORMAT.get().copied().unwrap_or_default()
+}
+
+/// Whether human readable tables are printed
+pub fn is_table() -> bool {
+    output_format() == OutputFormat::Table
+}
+
+/// Prints a progress message. Goes to stderr in structured modes to keep stdout parseable.
+///
+/// # Arguments
+///
+/// * `message`
+///
+pub fn print_message(message: &str) {
+    if is_table() {
+        println!("{message}");
+    } else {
+        eprintln!("{message}");
+    }
+}
+
+/// Prints the message of a result in table mode, otherwise stores it for `flush_results()`
+///
+/// # Arguments
+///
+/// * `result`
+///
+pub fn print_result(result: ActionResult) {
+    if is_table() {
+        if result.success {
+            println!("{}", result.message);
+        } else {
+            eprintln!("{}", result.message);
+        }
+    } else {
+        RESULTS.lock().unwrap().push(result);
+    }
+}
+
+/// Prints the results stored by `print_result()` as a single document
+pub fn flush_results() -> Result<(), Box<dyn std::error::Error>> {
+    let results = std::mem::take(&mut *RESULTS.lock().unwrap());
+
+    if results.is_empty() {
+        return Ok(());
+    }
+
+    print_document("result", &results)
+}
+
+/// Prints records as a versioned JSON or YAML document, or as CSV rows. Table mode is handled
+/// by the caller.
+///
+/// # Arguments
+///
+/// * `kind` - What the records are, eg. `status`
+/// * `items` - The records
+///
+pub fn print_document<T: Serialize>(
+    kind: &str,
+    items: &[T],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let document = Document {
+        version: DOCUMENT_VERSION,
+        kind,
+        items,
+    };
+
+    match output_format() {
+        OutputFormat::Table | OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&document)?)
+        }
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(&document)?),
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(std::io::stdout());
+            for item in items {
+                writer.serialize(item)?;
+            }
+            writer.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns a unified diff between two versions of a file with 3 lines of context
+///
+/// # Arguments
+///
+/// * `old` - Contents of the old version
+/// * `new` - Contents of the new version
+/// * `old_label` - Name of the old version in the header, eg. a path
+/// * `new_label` - Name of the new version in the header
+///
+pub fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str) -> String {
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(old_label, new_label)
+        .to_string()
+}
+
+/// Prints a unified diff between two versions of a file, colored when stdout is a terminal
+///
+/// # Arguments
+///
+/// * `old` - Contents of the old version
+/// * `new` - Contents of the new version
+/// * `old_label` - Name of the old version in the header, eg. a path
+/// * `new_label` - Name of the new version in the header
+///
+pub fn print_diff(old: &str, new: &str, old_label: &str, new_label: &str) {
+    print_unified_diff(&unified_diff(old, new, old_label, new_label));
+}
+
+/// Prints a unified diff from `unified_diff()`, colored when stdout is a terminal
+///
+/// # Arguments
+///
+/// * `diff` - The unified diff
+///
+pub fn print_unified_diff(diff: &str) {
+    let color = std::io::stdout().is_terminal();
+
+    for line in diff.lines() {
+        if !color || line.starts_with("---") || line.starts_with("+++") {
+            println!("{line}");
+        } else if line.starts_with('+') {
+            println!("{}", line.green());
+        } else if line.starts_with('-') {
+            println!("{}", line.red());
+        } else if line.starts_with("@@") {
+            println!("{}", line.cyan());
+        } else {
+            println!("{line}");
+        }
+    }
+}
+
+/// Formats an RFC 3339 timestamp for tables, eg. `2024-05-01 13:00:00`
+///
+/// # Arguments
+///
+/// * `timestamp`
+///
+pub fn display_timestamp(timestamp: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or(timestamp.to_string())
+}
@@ -0,0 +1,792 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde_json::{Map, Value};
+
+use super::{
+    find_binary_path::find_interpreter,
+    import::{split_command_line, to_service_name, ImportedServices, UnsupportedOption},
+    manifest::ServiceManifest,
+    scope::{get_home_directory, get_user_home, is_user},
+};
+
+/// An app of a pm2 process list or ecosystem file. pm2 accepts many aliases and types for its
+/// options, so apps are read as JSON objects.
+type App = Map<String, Value>;
+
+/// Variables of pm2 and of the shell pm2 was started from, which `pm2 save` writes into the
+/// environment of every app
+const SHELL_VARIABLES: &[&str] = &[
+    "_",
+    "COLORTERM",
+    "DISPLAY",
+    "EDITOR",
+    "HOME",
+    "HOSTNAME",
+    "LANG",
+    "LANGUAGE",
+    "LESSCLOSE",
+    "LESSOPEN",
+    "LOGNAME",
+    "LS_COLORS",
+    "MAIL",
+    "MOTD_SHOWN",
+    "NODE_APP_INSTANCE",
+    "OLDPWD",
+    "PAGER",
+    "PATH",
+    "PWD",
+    "SHELL",
+    "SHLVL",
+    "TERM",
+    "USER",
+    "VISUAL",
+    "unique_id",
+];
+
+/// Prefixes of shell and pm2 variables in the environment of apps in a process list
+const SHELL_VARIABLE_PREFIXES: &[&str] = &[
+    "LC_", "SSH_", "XDG_", "SUDO_", "NVM_", "TERM_", "DBUS_", "PM2_", "pm_", "npm_", "axm_",
+];
+
+/// Options which are not translated, with what to do instead
+const UNSUPPORTED_OPTIONS: &[(&str, &str)] = &[
+    (
+        "watch",
+        "systemd doesn't restart on file changes. Run `ser restart` after deploying",
+    ),
+    (
+        "max_restarts",
+        "systemd limits restarts with StartLimitBurst=, add it with `ser edit`",
+    ),
+    (
+        "min_uptime",
+        "systemd limits restarts with StartLimitIntervalSec=, add it with `ser edit`",
+    ),
+    ("kill_timeout", "add TimeoutStopSec= with `ser edit`"),
+    (
+        "exp_backoff_restart_delay",
+        "add RestartSteps= and RestartMaxDelaySec= with `ser edit`, on systemd 254 or newer",
+    ),
+    (
+        "stop_exit_codes",
+        "add RestartPreventExitStatus= with `ser edit`",
+    ),
+    (
+        "wait_ready",
+        "use Type=notify and call sd_notify(\"READY=1\") from the app",
+    ),
+    ("listen_timeout", "add TimeoutStartSec= with `ser edit`"),
+    (
+        "shutdown_with_message",
+        "systemd stops services with a signal, see KillSignal=",
+    ),
+    ("gid", "add Group= with `ser edit`"),
+    (
+        "log_date_format",
+        "journald timestamps every line, view them with `ser logs`",
+    ),
+    (
+        "merge_logs",
+        "journald keeps one log per service, view several with `ser logs`",
+    ),
+    (
+        "combine_logs",
+        "journald keeps one log per service, view several with `ser logs`",
+    ),
+    (
+        "log_type",
+        "view logs as JSON with `ser logs --format json`",
+    ),
+    (
+        "source_map_support",
+        "pass --enable-source-maps in interpreter_args",
+    ),
+];
+
+/// Options of ecosystem files which are translated or reported. Others are reported as unknown.
+const KNOWN_OPTIONS: &[&str] = &[
+    "name",
+    "script",
+    "cwd",
+    "interpreter",
+    "exec_interpreter",
+    "interpreter_args",
+    "node_args",
+    "args",
+    "env",
+    "autorestart",
+    "restart_delay",
+    "max_memory_restart",
+    "cron_restart",
+    "instances",
+    "exec_mode",
+    "uid",
+    "user",
+    "out_file",
+    "error_file",
+    "log_file",
+    "output",
+    "error",
+    "log",
+    "ignore_watch",
+    "watch_options",
+    "instance_var",
+];
+
+/// The process list written by `pm2 save`: `$PM2_HOME/dump.pm2`, else `~/.pm2/dump.pm2` of the
+/// user who called sudo
+pub fn get_default_dump_path() -> PathBuf {
+    if let Some(pm2_home) = std::env::var_os("PM2_HOME") {
+        return PathBuf::from(pm2_home).join("dump.pm2");
+    }
+
+    std::env::var("SUDO_USER")
+        .ok()
+        .and_then(|user| get_user_home(&user))
+        .unwrap_or_else(get_home_directory)
+        .join(".pm2/dump.pm2")
+}
+
+/// Translates the apps of a pm2 process list, `dump.pm2`, or an ecosystem file in JSON or YAML
+/// to services
+///
+/// # Arguments
+///
+/// * `path` - The file
+/// * `env_name` - Merge `env_<name>` of each app into `env`, like `pm2 start --env <name>`
+/// * `start` - Start the apps. From a process list, only apps which were online are started
+/// * `enable` - Enable the services
+///
+pub fn import_pm2(
+    path: &Path,
+    env_name: Option<&str>,
+    start: bool,
+    enable: bool,
+) -> Result<ImportedServices, Box<dyn std::error::Error>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|error| format!("Failed to read {}: {error}", path.display()))?;
+    let invalid = |error: &dyn std::fmt::Display| format!("Invalid {}: {error}", path.display());
+
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    let is_dump = extension == Some("pm2");
+    let document: Value = match extension {
+        Some("js" | "cjs" | "mjs") => {
+            return Err(format!(
+                "{} is JavaScript. Convert it to JSON with `node -e \
+                'console.log(JSON.stringify(require(\"./{}\"), null, 2))' > ecosystem.config.json`",
+                path.display(),
+                path.file_name().unwrap_or_default().to_string_lossy()
+            )
+            .into())
+        }
+        Some("yaml" | "yml") => serde_yaml::from_str(&contents).map_err(|error| invalid(&error))?,
+        _ => serde_json::from_str(&contents).map_err(|error| invalid(&error))?,
+    };
+
+    // A process list is an array of apps, an ecosystem file has them under `apps`
+    let apps = match document {
+        Value::Array(apps) => apps,
+        Value::Object(mut object) => match object.remove("apps") {
+            Some(Value::Array(apps)) => apps,
+            Some(app @ Value::Object(_)) => vec![app],
+            _ => return Err(invalid(&"expected a list of apps under `apps`").into()),
+        },
+        _ => return Err(invalid(&"expected a list of apps").into()),
+    };
+
+    let base_directory = std::fs::canonicalize(path)?
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+
+    let mut imported = ImportedServices::default();
+
+    for app in apps {
+        let Value::Object(app) = app else {
+            return Err(invalid(&"every app must be an object").into());
+        };
+
+        translate_app(
+            &app,
+            &base_directory,
+            is_dump,
+            env_name,
+            start,
+            enable,
+            &mut imported,
+        )?;
+    }
+
+    Ok(imported)
+}
+
+/// Translates one app, adding a service per instance to `imported`
+///
+/// # Arguments
+///
+/// * `app` - The app
+/// * `base_directory` - Directory of the imported file
+/// * `is_dump` - Whether the app comes from a process list, which holds pm2 internals and the
+///   environment of the shell pm2 was started from
+/// * `env_name` - Merge `env_<name>` into `env`
+/// * `start` - Start the service
+/// * `enable` - Enable the service
+/// * `imported` - Translated services and unsupported options
+///
+fn translate_app(
+    app: &App,
+    base_directory: &Path,
+    is_dump: bool,
+    env_name: Option<&str>,
+    start: bool,
+    enable: bool,
+    imported: &mut ImportedServices,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(script) = get_string(app, &["script", "pm_exec_path"]) else {
+        return Err("Every pm2 app needs a script".into());
+    };
+
+    let app_name = get_string(app, &["name"]).unwrap_or_else(|| {
+        Path::new(&script)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or(script.clone())
+    });
+    let report = |option: &str, reason: String| UnsupportedOption::new(&app_name, option, reason);
+
+    let mut unsupported = vec![];
+
+    // User services run as the calling user
+    let user = match get_string(app, &["uid", "user"]) {
+        Some(_) if is_user() => {
+            unsupported.push(report("uid", "user services run as your user".to_string()));
+            None
+        }
+        user => user,
+    };
+
+    let working_directory = get_string(app, &["cwd", "pm_cwd"]).map(|cwd| base_directory.join(cwd));
+    let script_directory = working_directory.as_deref().unwrap_or(base_directory);
+
+    let mut interpreter = get_string(app, &["interpreter", "exec_interpreter"]);
+
+    // Like pm2, a script which isn't a file is looked up in the PATH, eg. `npm`
+    let mut path = script_directory.join(&script);
+    if !path.is_file() && !script.contains('/') {
+        let lookup_user = user.clone().or_else(|| std::env::var("SUDO_USER").ok());
+        if let Ok(found) = find_interpreter(&script, lookup_user.as_deref()) {
+            path = PathBuf::from(found);
+            interpreter.get_or_insert("none".to_string());
+        }
+    }
+
+    let interpreter = interpreter.or_else(|| {
+        let extension = path.extension().and_then(|extension| extension.to_str());
+        get_default_interpreter(extension).map(str::to_string)
+    });
+
+    let mut env: BTreeMap<String, String> = get_env(app.get("env"), is_dump);
+    if let Some(env_name) = env_name {
+        env.extend(get_env(app.get(&format!("env_{env_name}")), false));
+    }
+
+    let max_memory_restart = app.get("max_memory_restart").and_then(|value| match value {
+        Value::Number(bytes) => Some(bytes.to_string()),
+        Value::String(size) => Some(size.trim().to_uppercase().trim_end_matches('B').to_string()),
+        _ => None,
+    });
+
+    let mut service = ServiceManifest {
+        path,
+        interpreter,
+        interpreter_args: get_words(app, &["interpreter_args", "node_args"]),
+        args: get_words(app, &["args"]),
+        env,
+        working_directory,
+        user,
+        auto_restart: app
+            .get("autorestart")
+            .and_then(Value::as_bool)
+            .unwrap_or(true),
+        restart_sec: app
+            .get("restart_delay")
+            .and_then(Value::as_u64)
+            .filter(|delay| *delay > 0)
+            .map(|delay| format!("{delay}ms")),
+        memory_max: max_memory_restart,
+        enable: enable.then_some(true),
+        start: (start && app.get("status").is_none_or(|status| status == "online")).then_some(true),
+        ..Default::default()
+    };
+
+    // Turned off values are not reported, eg. `watch: false`
+    for (option, reason) in UNSUPPORTED_OPTIONS {
+        if app.get(*option).is_some_and(is_set) {
+            unsupported.push(report(option, reason.to_string()));
+        }
+    }
+
+    if let Some(cron) = get_string(app, &["cron_restart"]) {
+        let hint = match cron_to_on_calendar(&cron) {
+            Some(calendar) => format!(
+                "restart with a systemd timer having OnCalendar={calendar} and running \
+                `systemctl restart`"
+            ),
+            None => "restart with a systemd timer running `systemctl restart`".to_string(),
+        };
+        unsupported.push(report("cron_restart", format!("{cron:?}, {hint}")));
+    }
+
+    for (options, stream) in [
+        (["out_file", "pm_out_log_path", "output"], "output"),
+        (["error_file", "pm_err_log_path", "error"], "errors"),
+        (["log_file", "pm_log_path", "log"], "combined logs"),
+    ] {
+        // pm2 always writes a default path into the process list
+        if let Some(file) = get_string(app, &options).filter(|file| !file.contains("/.pm2/logs/")) {
+            unsupported.push(report(
+                options[0],
+                format!("journald keeps the {stream} instead of {file}, view with `ser logs`"),
+            ));
+        }
+    }
+
+    if !is_dump {
+        for option in app.keys() {
+            let known = KNOWN_OPTIONS.contains(&option.as_str())
+                || UNSUPPORTED_OPTIONS.iter().any(|(known, _)| known == option)
+                || option.starts_with("env_");
+
+            if !known {
+                unsupported.push(report(option, "unknown pm2 option".to_string()));
+            }
+        }
+    }
+
+    let instances = get_instances(app.get("instances"));
+    let cluster = get_string(app, &["exec_mode"]).is_some_and(|mode| mode.starts_with("cluster"));
+    if cluster {
+        unsupported.push(report(
+            "exec_mode",
+            "systemd has no cluster mode. Instances are separate services which can't share a \
+            port, give each its own port, eg. from NODE_APP_INSTANCE"
+                .to_string(),
+        ));
+    }
+
+    let service_name = to_service_name(&app_name);
+    imported.unsupported.extend(unsupported);
+
+    if instances <= 1 {
+        imported.services.insert(service_name, service);
+        return Ok(());
+    }
+
+    // One service per instance, numbered like pm2 does in NODE_APP_INSTANCE
+    let instance_var =
+        get_string(app, &["instance_var"]).unwrap_or("NODE_APP_INSTANCE".to_string());
+    for instance in 0..instances {
+        service
+            .env
+            .insert(instance_var.clone(), instance.to_string());
+        imported
+            .services
+            .insert(format!("{service_name}-{instance}"), service.clone());
+    }
+
+    Ok(())
+}
+
+/// Returns the first of several aliases of an option which is a string or number
+///
+/// # Arguments
+///
+/// * `app` - The app
+/// * `options` - Names of the option
+///
+fn get_string(app: &App, options: &[&str]) -> Option<String> {
+    options.iter().find_map(|option| match app.get(*option)? {
+        Value::String(value) if !value.is_empty() => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        _ => None,
+    })
+}
+
+/// Returns args given as a list or as a command line
+///
+/// # Arguments
+///
+/// * `app` - The app
+/// * `options` - Names of the option
+///
+fn get_words(app: &App, options: &[&str]) -> Vec<String> {
+    options
+        .iter()
+        .find_map(|option| match app.get(*option)? {
+            Value::String(line) => Some(split_command_line(line)),
+            Value::Array(words) => Some(
+                words
+                    .iter()
+                    .filter_map(|word| match word {
+                        Value::String(word) => Some(word.clone()),
+                        Value::Number(word) => Some(word.to_string()),
+                        Value::Bool(word) => Some(word.to_string()),
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// Returns environment variables with scalar values
+///
+/// # Arguments
+///
+/// * `env` - The `env` object of an app
+/// * `skip_shell` - Skip variables of the shell pm2 was started from
+///
+fn get_env(env: Option<&Value>, skip_shell: bool) -> BTreeMap<String, String> {
+    let Some(Value::Object(env)) = env else {
+        return BTreeMap::new();
+    };
+
+    env.iter()
+        .filter(|(key, _)| {
+            !skip_shell
+                || !(SHELL_VARIABLES.contains(&key.as_str())
+                    || SHELL_VARIABLE_PREFIXES
+                        .iter()
+                        .any(|prefix| key.starts_with(prefix)))
+        })
+        .filter_map(|(key, value)| {
+            let value = match value {
+                Value::String(value) => value.clone(),
+                Value::Number(value) => value.to_string(),
+                Value::Bool(value) => value.to_string(),
+                _ => return None,
+            };
+            Some((key.clone(), value))
+        })
+        .collect()
+}
+
+/// Number of instances of an app. `max`, `0` and `-1` mean one per CPU, `-n` one per CPU but n
+///
+/// # Arguments
+///
+/// * `instances` - The `instances` option
+///
+fn get_instances(instances: Option<&Value>) -> u64 {
+    let cpus = std::thread::available_parallelism()
+        .map(|cpus| cpus.get() as i64)
+        .unwrap_or(1);
+
+    let instances = match instances {
+        Some(Value::Number(number)) => number.as_i64().unwrap_or(1),
+        Some(Value::String(value)) if value == "max" => 0,
+        Some(Value::String(value)) => value.parse().unwrap_or(1),
+        _ => 1,
+    };
+
+    match instances {
+        0 => cpus as u64,
+        instances if instances < 0 => (cpus + instances).max(1) as u64,
+        instances => instances as u64,
+    }
+}
+
+/// Interpreter pm2 uses for a file extension. Files without an extension run by themselves and
+/// others with node.
+///
+/// # Arguments
+///
+/// * `extension` - The file extension
+///
+fn get_default_interpreter(extension: Option<&str>) -> Option<&'static str> {
+    let interpreter = match extension? {
+        "py" => "python3",
+        "sh" => "bash",
+        "rb" => "ruby",
+        "php" => "php",
+        "pl" => "perl",
+        "coffee" => "coffee",
+        "ts" | "tsx" => "ts-node",
+        _ => "node",
+    };
+
+    Some(interpreter)
+}
+
+/// Whether an option is turned on. `false`, `0`, empty strings and empty lists are off.
+fn is_set(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(value) => *value,
+        Value::Number(value) => value.as_f64() != Some(0.0),
+        Value::String(value) => !value.is_empty(),
+        Value::Array(values) => !values.is_empty(),
+        Value::Object(_) => true,
+    }
+}
+
+/// Translates a cron expression of `cron_restart` to a systemd calendar event, eg. `0 3 * * *`
+/// to `*-*-* 3:0:0`. Returns `None` for expressions it doesn't understand.
+///
+/// # Arguments
+///
+/// * `cron` - Five fields, six with seconds first, or a shortcut like `@daily`
+///
+fn cron_to_on_calendar(cron: &str) -> Option<String> {
+    let shortcut = match cron.trim() {
+        "@hourly" => "hourly",
+        "@daily" | "@midnight" => "daily",
+        "@weekly" => "weekly",
+        "@monthly" => "monthly",
+        "@yearly" | "@annually" => "yearly",
+        _ => "",
+    };
+    if !shortcut.is_empty() {
+        return Some(shortcut.to_string());
+    }
+
+    let fields: Vec<&str> = cron.split_whitespace().collect();
+    let (second, rest) = match fields.len() {
+        5 => ("0", &fields[..]),
+        6 => (fields[0], &fields[1..]),
+        _ => return None,
+    };
+    let [minute, hour, day, month, weekday] = rest else {
+        return None;
+    };
+
+    // `*/15` repeats from the first value, `1-5` is a range
+    let convert = |field: &str, first: &str| -> Option<String> {
+        if !field
+            .chars()
+            .all(|c| c.is_ascii_digit() || ",-/*".contains(c))
+        {
+            return None;
+        }
+
+        Some(
+            field
+                .split(',')
+                .map(|part| {
+                    part.replacen("*/", &format!("{first}/"), 1)
+                        .replace('-', "..")
+                })
+                .collect::<Vec<_>>()
+                .join(","),
+        )
+    };
+
+    const WEEKDAYS: [&str; 8] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+    // Numbers or names, eg. `1` or `MON`
+    let weekday_name = |day: &str| -> Option<&str> {
+        match day.parse::<usize>() {
+            Ok(day) => WEEKDAYS.get(day).copied(),
+            Err(_) => WEEKDAYS
+                .iter()
+                .find(|name| name.eq_ignore_ascii_case(day))
+                .copied(),
+        }
+    };
+
+    let weekdays = if *weekday == "*" {
+        String::new()
+    } else {
+        let mut names = vec![];
+        for part in weekday.split(',') {
+            match part.split_once('-') {
+                Some((from, to)) => {
+                    names.push(format!("{}..{}", weekday_name(from)?, weekday_name(to)?))
+                }
+                None => names.push(weekday_name(part)?.to_string()),
+            }
+        }
+        format!("{} ", names.join(","))
+    };
+
+    Some(format!(
+        "{weekdays}*-{}-{} {}:{}:{}",
+        convert(month, "1")?,
+        convert(day, "1")?,
+        convert(hour, "0")?,
+        convert(minute, "0")?,
+        convert(second, "0")?
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn translate(app: Value) -> ImportedServices {
+        let Value::Object(app) = app else {
+            panic!("apps are objects");
+        };
+        let mut imported = ImportedServices::default();
+        translate_app(
+            &app,
+            Path::new("/srv/shop"),
+            false,
+            Some("production"),
+            true,
+            false,
+            &mut imported,
+        )
+        .unwrap();
+        imported
+    }
+
+    #[test]
+    fn converts_cron_fields() {
+        let calendar = |cron: &str| cron_to_on_calendar(cron);
+
+        assert_eq!(calendar("0 3 * * *").as_deref(), Some("*-*-* 3:0:0"));
+        assert_eq!(calendar("*/15 * * * *").as_deref(), Some("*-*-* *:0/15:0"));
+        assert_eq!(
+            calendar("30 */2 1-15 * *").as_deref(),
+            Some("*-*-1..15 0/2:30:0")
+        );
+        assert_eq!(
+            calendar("0,30 9-17 * 1,7 *").as_deref(),
+            Some("*-1,7-* 9..17:0,30:0")
+        );
+    }
+
+    #[test]
+    fn converts_cron_weekdays() {
+        let calendar = |cron: &str| cron_to_on_calendar(cron);
+
+        assert_eq!(
+            calendar("0 9 * * 1-5").as_deref(),
+            Some("Mon..Fri *-*-* 9:0:0")
+        );
+        assert_eq!(
+            calendar("0 9 * * MON-FRI").as_deref(),
+            Some("Mon..Fri *-*-* 9:0:0")
+        );
+        assert_eq!(
+            calendar("0 9 * * sat,0").as_deref(),
+            Some("Sat,Sun *-*-* 9:0:0")
+        );
+        assert_eq!(calendar("0 9 * * 7").as_deref(), Some("Sun *-*-* 9:0:0"));
+        assert_eq!(calendar("0 9 * * 8"), None);
+        assert_eq!(calendar("0 9 * * Funday"), None);
+    }
+
+    #[test]
+    fn converts_cron_with_seconds_and_shortcuts() {
+        let calendar = |cron: &str| cron_to_on_calendar(cron);
+
+        assert_eq!(
+            calendar("*/10 * * * * *").as_deref(),
+            Some("*-*-* *:*:0/10")
+        );
+        assert_eq!(calendar("15 0 4 * * *").as_deref(), Some("*-*-* 4:0:15"));
+        assert_eq!(calendar("@daily").as_deref(), Some("daily"));
+        assert_eq!(calendar(" @midnight ").as_deref(), Some("daily"));
+        assert_eq!(calendar("@reboot"), None);
+        assert_eq!(calendar("0 3 * *"), None);
+        assert_eq!(calendar("0 3 * JAN *"), None);
+    }
+
+    #[test]
+    fn translates_an_app() {
+        let imported = translate(json!({
+            "name": "api",
+            "script": "/srv/shop/server.js",
+            "node_args": "--max-old-space-size=4096",
+            "args": ["--port", 8080],
+            "env": { "NODE_ENV": "development", "DEBUG": false },
+            "env_production": { "NODE_ENV": "production" },
+            "restart_delay": 3000,
+            "max_memory_restart": "300MB",
+            "cron_restart": "0 3 * * *",
+            "watch": false,
+            "max_restarts": 10,
+            "colour": "blue"
+        }));
+
+        let api = &imported.services["api"];
+        assert_eq!(api.path, Path::new("/srv/shop/server.js"));
+        assert_eq!(api.interpreter.as_deref(), Some("node"));
+        assert_eq!(api.interpreter_args, ["--max-old-space-size=4096"]);
+        assert_eq!(api.args, ["--port", "8080"]);
+        assert_eq!(api.env["NODE_ENV"], "production");
+        assert_eq!(api.env["DEBUG"], "false");
+        assert!(api.auto_restart);
+        assert_eq!(api.restart_sec.as_deref(), Some("3000ms"));
+        assert_eq!(api.memory_max.as_deref(), Some("300M"));
+        assert_eq!(api.start, Some(true));
+        assert_eq!(api.enable, None);
+
+        let reported: Vec<&str> = imported
+            .unsupported
+            .iter()
+            .map(|option| option.option.as_str())
+            .collect();
+        assert_eq!(reported, ["max_restarts", "cron_restart", "colour"]);
+
+        let cron_restart = &imported.unsupported[1];
+        assert!(
+            cron_restart.reason.contains("OnCalendar=*-*-* 3:0:0"),
+            "{}",
+            cron_restart.reason
+        );
+    }
+
+    #[test]
+    fn translates_instances() {
+        let imported = translate(json!({
+            "name": "worker",
+            "script": "/srv/shop/worker.py",
+            "instances": 2,
+            "instance_var": "INSTANCE_ID"
+        }));
+
+        assert_eq!(
+            imported.services.keys().collect::<Vec<_>>(),
+            ["worker-0", "worker-1"]
+        );
+        assert_eq!(imported.services["worker-1"].env["INSTANCE_ID"], "1");
+        assert_eq!(
+            imported.services["worker-0"].interpreter.as_deref(),
+            Some("python3")
+        );
+    }
+
+    #[test]
+    fn skips_shell_variables_of_process_lists() {
+        let env = json!({ "PATH": "/usr/bin", "LC_ALL": "C", "pm_id": 3, "PORT": 80 });
+
+        assert_eq!(
+            get_env(Some(&env), true).into_iter().collect::<Vec<_>>(),
+            [("PORT".to_string(), "80".to_string())]
+        );
+        assert_eq!(get_env(Some(&env), false).len(), 4);
+        assert!(get_env(None, true).is_empty());
+    }
+
+    #[test]
+    fn counts_instances() {
+        let cpus = std::thread::available_parallelism()
+            .map(|cpus| cpus.get() as u64)
+            .unwrap_or(1);
+
+        assert_eq!(get_instances(None), 1);
+        assert_eq!(get_instances(Some(&json!(3))), 3);
+        assert_eq!(get_instances(Some(&json!("4"))), 4);
+        assert_eq!(get_instances(Some(&json!("max"))), cpus);
+        assert_eq!(get_instances(Some(&json!(0))), cpus);
+        assert_eq!(get_instances(Some(&json!(-1))), (cpus - 1).max(1));
+    }
+}
@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+
+use zbus::zvariant::Value;
+use zbus::{Connection, Result};
+use zbus_macros::proxy;
+
+/// Proxy object for `org.freedesktop.PolicyKit1.Authority`, which decides whether unprivileged
+/// callers may use systemd Manager methods
+#[proxy(
+    interface = "org.freedesktop.PolicyKit1.Authority",
+    default_service = "org.freedesktop.PolicyKit1",
+    default_path = "/org/freedesktop/PolicyKit1/Authority",
+    gen_blocking = false
+)]
+pub trait Authority {
+    /// Call interface method `CheckAuthorization`. Returns whether the subject is authorized,
+    /// whether it could be after authenticating, and details.
+    #[zbus(name = "CheckAuthorization")]
+    fn check_authorization(
+        &self,
+        subject: &(&str, HashMap<&str, Value<'_>>),
+        action_id: &str,
+        details: HashMap<&str, &str>,
+        flags: u32,
+        cancellation_id: &str,
+    ) -> zbus::Result<(bool, bool, HashMap<String, String>)>;
+}
+
+/// Whether polkit allows this connection to perform an action, without prompting for a password
+///
+/// Returns `(authorized, challenge)`. `challenge` is true if the action is allowed after the
+/// user authenticates.
+///
+/// # Arguments
+///
+/// * `connection`: zbus connection
+/// * `action_id`: Eg. `org.freedesktop.systemd1.manage-units`
+///
+pub async fn check_authorization(connection: &Connection, action_id: &str) -> Result<(bool, bool)> {
+    let authority_proxy = AuthorityProxy::new(connection).await?;
+
+    let unique_name = connection
+        .unique_name()
+        .map(|name| name.to_string())
+        .unwrap_or_default();
+    let subject = (
+        "system-bus-name",
+        HashMap::from([("name", Value::from(unique_name.as_str()))]),
+    );
+
+    let (authorized, challenge, _) = authority_proxy
+        .check_authorization(&subject, action_id, HashMap::new(), 0, "")
+        .await?;
+
+    Ok((authorized, challenge))
+}
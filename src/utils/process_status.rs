@@ -1,4 +1,7 @@
-use std::io::BufRead;
+use std::{
+    io::BufRead,
+    path::{Path, PathBuf},
+};
 
 /// Gets the kernel page size of the system in KB
 pub fn get_page_size() -> Result<usize, Box<dyn std::error::Error>> {
@@ -66,3 +69,323 @@ pub fn get_cpu_time(pid: u32) -> Result<u64, Box<dyn std::error::Error>> {
 
     Ok(utime + stime)
 }
+
+/// Resource usage of every process in a control group, read from the cgroup v2 hierarchy. Fields
+/// are `None` if the file is missing, eg. when the controller is not enabled.
+#[derive(Default)]
+pub struct CgroupUsage {
+    /// `memory.current` in bytes
+    pub memory_current: Option<u64>,
+
+    /// `memory.peak` in bytes. Needs Linux 5.19 or newer
+    pub memory_peak: Option<u64>,
+
+    /// `pids.current`, the number of tasks
+    pub tasks: Option<u64>,
+
+    /// Sum of `rbytes` over every device in `io.stat`
+    pub io_read_bytes: Option<u64>,
+
+    /// Sum of `wbytes` over every device in `io.stat`
+    pub io_write_bytes: Option<u64>,
+}
+
+/// Reads memory, task and IO totals of a control group
+///
+/// # Arguments
+///
+/// * `control_group` - The `ControlGroup` property of a unit, eg. `/system.slice/hello.ser.service`
+///
+pub fn get_cgroup_usage(control_group: &str) -> CgroupUsage {
+    if control_group.is_empty() {
+        return CgroupUsage::default();
+    }
+
+    let read_u64 = |file: &str| -> Option<u64> {
+        std::fs::read_to_string(get_cgroup_file_path(control_group, file))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    };
+
+    let (io_read_bytes, io_write_bytes) = match get_cgroup_io_bytes(control_group) {
+        Some((read, write)) => (Some(read), Some(write)),
+        None => (None, None),
+    };
+
+    CgroupUsage {
+        memory_current: read_u64("memory.current"),
+        memory_peak: read_u64("memory.peak"),
+        tasks: read_u64("pids.current"),
+        io_read_bytes,
+        io_write_bytes,
+    }
+}
+
+/// Gets the CPU time used by every process in a control group, in microseconds
+///
+/// # Arguments
+///
+/// * `control_group` - The `ControlGroup` property of a unit
+///
+pub fn get_cgroup_cpu_time(control_group: &str) -> Option<u64> {
+    if control_group.is_empty() {
+        return None;
+    }
+
+    let contents = std::fs::read_to_string(get_cgroup_file_path(control_group, "cpu.stat")).ok()?;
+
+    contents
+        .lines()
+        .find_map(|line| line.strip_prefix("usage_usec "))
+        .and_then(|usage| usage.trim().parse().ok())
+}
+
+/// Sums read and written bytes over every device in `io.stat`
+///
+/// Each line has the format `8:0 rbytes=1024 wbytes=512 rios=3 wios=1 dbytes=0 dios=0`
+///
+/// # Arguments
+///
+/// * `control_group` - The `ControlGroup` property of a unit
+///
+fn get_cgroup_io_bytes(control_group: &str) -> Option<(u64, u64)> {
+    let contents = std::fs::read_to_string(get_cgroup_file_path(control_group, "io.stat")).ok()?;
+
+    let mut read_bytes = 0;
+    let mut write_bytes = 0;
+
+    for field in contents.split_whitespace() {
+        if let Some(value) = field.strip_prefix("rbytes=") {
+            read_bytes += value.parse::<u64>().unwrap_or(0);
+        } else if let Some(value) = field.strip_prefix("wbytes=") {
+            write_bytes += value.parse::<u64>().unwrap_or(0);
+        }
+    }
+
+    Some((read_bytes, write_bytes))
+}
+
+/// Path of a file in the cgroup v2 hierarchy
+///
+/// # Arguments
+///
+/// * `control_group` - The `ControlGroup` property of a unit
+/// * `file` - File name, eg. `memory.current`
+///
+fn get_cgroup_file_path(control_group: &str, file: &str) -> PathBuf {
+    Path::new("/sys/fs/cgroup")
+        .join(control_group.trim_start_matches('/'))
+        .join(file)
+}
+
+/// A process in the control group of a service
+#[derive(serde::Serialize, Clone)]
+pub struct ProcessInfo {
+    /// Process ID
+    pub pid: u32,
+
+    /// Parent process ID
+    pub ppid: u32,
+
+    /// Command line, or the process name in brackets for kernel threads
+    pub command: String,
+}
+
+/// Lists every process in a control group, including its child groups, sorted by PID
+///
+/// # Arguments
+///
+/// * `control_group` - The `ControlGroup` property of a unit
+///
+pub fn get_cgroup_processes(control_group: &str) -> Vec<ProcessInfo> {
+    if control_group.is_empty() {
+        return vec![];
+    }
+
+    let mut pids = vec![];
+    let mut directories = vec![get_cgroup_file_path(control_group, "")];
+
+    while let Some(directory) = directories.pop() {
+        if let Ok(contents) = std::fs::read_to_string(directory.join("cgroup.procs")) {
+            pids.extend(
+                contents
+                    .lines()
+                    .filter_map(|pid| pid.trim().parse::<u32>().ok()),
+            );
+        }
+
+        if let Ok(entries) = std::fs::read_dir(&directory) {
+            for entry in entries.flatten() {
+                if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                    directories.push(entry.path());
+                }
+            }
+        }
+    }
+
+    pids.sort_unstable();
+    pids.dedup();
+
+    pids.into_iter()
+        .filter_map(|pid| {
+            let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+
+            // The process name is in parentheses and may itself contain spaces or parentheses
+            let name_start = stat.find('(')?;
+            let name_end = stat.rfind(')')?;
+            let name = &stat[name_start + 1..name_end];
+            let ppid = stat[name_end + 1..]
+                .split_whitespace()
+                .nth(1)?
+                .parse()
+                .ok()?;
+
+            let cmdline = std::fs::read(format!("/proc/{pid}/cmdline")).unwrap_or_default();
+            let command = String::from_utf8_lossy(&cmdline)
+                .trim_end_matches('\0')
+                .replace('\0', " ");
+
+            Some(ProcessInfo {
+                pid,
+                ppid,
+                command: if command.is_empty() {
+                    format!("[{name}]")
+                } else {
+                    command
+                },
+            })
+        })
+        .collect()
+}
+
+/// Describes how the main process of a service last exited, from the `ExecMainCode` and
+/// `ExecMainStatus` properties. Returns `None` if the process has not exited yet.
+///
+/// # Arguments
+///
+/// * `code` - `ExecMainCode`, a `CLD_*` code from `waitid()`
+/// * `status` - `ExecMainStatus`, the exit status or signal number
+///
+pub fn describe_exit(code: i32, status: i32) -> Option<String> {
+    let signal = || match get_signal_name(status) {
+        Some(name) => format!("{status} ({name})"),
+        None => status.to_string(),
+    };
+
+    match code {
+        libc::CLD_EXITED => Some(match get_exit_status_name(status) {
+            Some(name) => format!("exited with status {status} ({name})"),
+            None => format!("exited with status {status}"),
+        }),
+        libc::CLD_KILLED => Some(format!("killed by signal {}", signal())),
+        libc::CLD_DUMPED => Some(format!("dumped core on signal {}", signal())),
+        _ => None,
+    }
+}
+
+/// Name of an exit status with a meaning in systemd, eg. `EXEC` for 203. Statuses from 200 are
+/// set by systemd when it fails to set up the process.
+///
+/// # Arguments
+///
+/// * `status` - Exit status
+///
+pub fn get_exit_status_name(status: i32) -> Option<&'static str> {
+    let name = match status {
+        1 => "FAILURE",
+        2 => "INVALIDARGUMENT",
+        3 => "NOTIMPLEMENTED",
+        4 => "NOPERMISSION",
+        5 => "NOTINSTALLED",
+        6 => "NOTCONFIGURED",
+        7 => "NOTRUNNING",
+        126 => "NOEXEC",
+        127 => "NOTFOUND",
+        200 => "CHDIR",
+        201 => "NICE",
+        202 => "FDS",
+        203 => "EXEC",
+        204 => "MEMORY",
+        205 => "LIMITS",
+        206 => "OOM_ADJUST",
+        207 => "SIGNAL_MASK",
+        208 => "STDIN",
+        209 => "STDOUT",
+        210 => "CHROOT",
+        211 => "IOPRIO",
+        212 => "TIMERSLACK",
+        213 => "SECUREBITS",
+        214 => "SETSCHEDULER",
+        215 => "CPUAFFINITY",
+        216 => "GROUP",
+        217 => "USER",
+        218 => "CAPABILITIES",
+        219 => "CGROUP",
+        220 => "SETSID",
+        221 => "CONFIRM",
+        222 => "STDERR",
+        224 => "PAM",
+        225 => "NETWORK",
+        226 => "NAMESPACE",
+        227 => "NO_NEW_PRIVILEGES",
+        228 => "SECCOMP",
+        229 => "SELINUX_CONTEXT",
+        230 => "PERSONALITY",
+        231 => "APPARMOR_PROFILE",
+        232 => "ADDRESS_FAMILIES",
+        233 => "RUNTIME_DIRECTORY",
+        235 => "CHOWN",
+        236 => "SMACK_PROCESS_LABEL",
+        237 => "KEYRING",
+        238 => "STATE_DIRECTORY",
+        239 => "CACHE_DIRECTORY",
+        240 => "LOGS_DIRECTORY",
+        241 => "CONFIGURATION_DIRECTORY",
+        242 => "NUMA_POLICY",
+        243 => "CREDENTIALS",
+        245 => "BPF",
+        _ => return None,
+    };
+
+    Some(name)
+}
+
+/// Name of a Linux signal, eg. `SIGKILL` for 9
+///
+/// # Arguments
+///
+/// * `signal` - Signal number
+///
+pub fn get_signal_name(signal: i32) -> Option<&'static str> {
+    let name = match signal {
+        libc::SIGHUP => "SIGHUP",
+        libc::SIGINT => "SIGINT",
+        libc::SIGQUIT => "SIGQUIT",
+        libc::SIGILL => "SIGILL",
+        libc::SIGTRAP => "SIGTRAP",
+        libc::SIGABRT => "SIGABRT",
+        libc::SIGBUS => "SIGBUS",
+        libc::SIGFPE => "SIGFPE",
+        libc::SIGKILL => "SIGKILL",
+        libc::SIGUSR1 => "SIGUSR1",
+        libc::SIGSEGV => "SIGSEGV",
+        libc::SIGUSR2 => "SIGUSR2",
+        libc::SIGPIPE => "SIGPIPE",
+        libc::SIGALRM => "SIGALRM",
+        libc::SIGTERM => "SIGTERM",
+        libc::SIGCHLD => "SIGCHLD",
+        libc::SIGCONT => "SIGCONT",
+        libc::SIGSTOP => "SIGSTOP",
+        libc::SIGTSTP => "SIGTSTP",
+        libc::SIGTTIN => "SIGTTIN",
+        libc::SIGTTOU => "SIGTTOU",
+        libc::SIGXCPU => "SIGXCPU",
+        libc::SIGXFSZ => "SIGXFSZ",
+        libc::SIGSYS => "SIGSYS",
+        _ => return None,
+    };
+
+    Some(name)
+}
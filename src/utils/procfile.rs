@@ -0,0 +1,263 @@
+use std::{collections::BTreeMap, path::Path};
+
+use super::import::{
+    command_to_service, prefixed_service_name, ImportedServices, UnsupportedOption,
+};
+
+/// Port of the first process type, like foreman and `heroku local`. Each next process type gets
+/// 100 more.
+const BASE_PORT: u32 = 5000;
+
+/// Translates the process types of a Procfile to services running in the Procfile's directory.
+/// Variables of a `.env` file next to it are added to every service.
+///
+/// # Arguments
+///
+/// * `path` - The Procfile
+/// * `env_file` - File with variables. Defaults to `.env` next to the Procfile, if it exists.
+/// * `prefix` - Prefix of the service names. Defaults to the name of the Procfile's directory.
+///   Empty for no prefix.
+/// * `start` - Start the services
+/// * `enable` - Enable the services
+///
+pub fn import_procfile(
+    path: &Path,
+    env_file: Option<&Path>,
+    prefix: Option<&str>,
+    start: bool,
+    enable: bool,
+) -> Result<ImportedServices, Box<dyn std::error::Error>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|error| format!("Failed to read {}: {error}", path.display()))?;
+
+    let directory = std::fs::canonicalize(path)?
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+
+    let default_prefix = directory
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string());
+    let prefix = prefix.or(default_prefix.as_deref());
+
+    let default_env_file = directory.join(".env");
+    let env = match env_file {
+        Some(env_file) => read_env_file(env_file)?,
+        None if default_env_file.is_file() => read_env_file(&default_env_file)?,
+        None => BTreeMap::new(),
+    };
+
+    let mut imported = ImportedServices::default();
+    let mut port = BASE_PORT;
+
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let Some((process_type, command)) = line
+            .split_once(':')
+            .filter(|(process_type, _)| is_process_type(process_type))
+        else {
+            return Err(format!(
+                "Invalid line {} of {}: expected `<process type>: <command>`",
+                index + 1,
+                path.display()
+            )
+            .into());
+        };
+        let command = command.trim();
+
+        // Heroku runs `release` once per deploy, it isn't a long running process
+        if process_type == "release" {
+            imported.unsupported.push(UnsupportedOption::new(
+                process_type,
+                "process type",
+                "runs once per deploy, run it yourself before `ser restart`".to_string(),
+            ));
+            continue;
+        }
+
+        let mut service = command_to_service(command, &directory, None)?;
+        service.env = env.clone();
+        service
+            .env
+            .entry("PORT".to_string())
+            .or_insert(port.to_string());
+        service.auto_restart = true;
+        service.enable = enable.then_some(true);
+        service.start = start.then_some(true);
+
+        imported
+            .services
+            .insert(prefixed_service_name(prefix, process_type), service);
+        port += 100;
+    }
+
+    Ok(imported)
+}
+
+/// Whether a Procfile line starts with a process type, eg. `web` or `worker_2`
+///
+/// # Arguments
+///
+/// * `name` - Text before the first `:`
+///
+fn is_process_type(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Reads `KEY=value` lines of a `.env` file. `export` and quotes around values are removed, lines
+/// starting with `#` are skipped.
+///
+/// # Arguments
+///
+/// * `path` - The file
+///
+fn read_env_file(path: &Path) -> Result<BTreeMap<String, String>, Box<dyn std::error::Error>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|error| format!("Failed to read {}: {error}", path.display()))?;
+
+    let mut env = BTreeMap::new();
+
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(format!(
+                "Invalid line {} of {}: expected `KEY=value`",
+                index + 1,
+                path.display()
+            )
+            .into());
+        };
+
+        let value = value.trim();
+        let value = ['"', '\'']
+            .iter()
+            .find_map(|quote| value.strip_prefix(*quote)?.strip_suffix(*quote))
+            .unwrap_or(value);
+
+        env.insert(key.trim().to_string(), value.to_string());
+    }
+
+    Ok(env)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn reads_env_files() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join(".env");
+        std::fs::write(
+            &path,
+            "# database\nDATABASE_URL=postgres://localhost/shop\n\nexport SECRET='a b'\n\
+            GREETING=\"hello = world\"\n  PORT = 80 \nEMPTY=\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            read_env_file(&path).unwrap(),
+            env(&[
+                ("DATABASE_URL", "postgres://localhost/shop"),
+                ("EMPTY", ""),
+                ("GREETING", "hello = world"),
+                ("PORT", "80"),
+                ("SECRET", "a b"),
+            ])
+        );
+
+        std::fs::write(&path, "A=1\nnot a variable\n").unwrap();
+        let error = read_env_file(&path).unwrap_err().to_string();
+        assert!(error.starts_with("Invalid line 2 of"), "{error}");
+    }
+
+    #[test]
+    fn imports_process_types() {
+        let temp_directory = tempfile::tempdir().unwrap();
+        // The directory name is the default prefix
+        let directory = temp_directory.path().join("shop");
+        std::fs::create_dir(&directory).unwrap();
+        let procfile = directory.join("Procfile");
+        std::fs::write(
+            &procfile,
+            "# processes\nweb: bin/web -p $PORT\nworker_2: ./bin/worker --queue \"mail jobs\"\n\
+            release: bin/migrate\n",
+        )
+        .unwrap();
+        std::fs::write(directory.join(".env"), "PORT=8000\nRAILS_ENV=production\n").unwrap();
+
+        let imported = import_procfile(&procfile, None, None, true, false).unwrap();
+        let directory = std::fs::canonicalize(&directory).unwrap();
+
+        assert_eq!(
+            imported.services.keys().collect::<Vec<_>>(),
+            ["shop-web", "shop-worker_2"]
+        );
+
+        let web = &imported.services["shop-web"];
+        assert_eq!(web.path, Path::new("/bin/sh"));
+        assert_eq!(web.args, ["-c", "bin/web -p $$PORT"]);
+        assert_eq!(web.env["PORT"], "8000");
+        assert_eq!(web.working_directory.as_deref(), Some(directory.as_path()));
+        assert!(web.auto_restart);
+        assert_eq!(web.start, Some(true));
+        assert_eq!(web.enable, None);
+
+        let worker = &imported.services["shop-worker_2"];
+        assert_eq!(worker.path, directory.join("./bin/worker"));
+        assert_eq!(worker.args, ["--queue", "mail jobs"]);
+        assert_eq!(worker.env["RAILS_ENV"], "production");
+
+        assert_eq!(imported.unsupported.len(), 1);
+        assert_eq!(imported.unsupported[0].app, "release");
+    }
+
+    #[test]
+    fn numbers_ports_without_env_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let procfile = directory.path().join("Procfile");
+        std::fs::write(&procfile, "web: ./web\napi: ./api\n").unwrap();
+
+        let imported = import_procfile(&procfile, None, Some(""), false, true).unwrap();
+
+        assert_eq!(imported.services["web"].env["PORT"], "5000");
+        assert_eq!(imported.services["api"].env["PORT"], "5100");
+        assert_eq!(imported.services["api"].enable, Some(true));
+    }
+
+    #[test]
+    fn rejects_lines_without_process_type() {
+        let directory = tempfile::tempdir().unwrap();
+        let procfile = directory.path().join("Procfile");
+        std::fs::write(&procfile, "web: ./web\nnode server.js\n").unwrap();
+
+        let error = import_procfile(&procfile, None, None, false, false)
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(error.starts_with("Invalid line 2 of"), "{error}");
+
+        assert!(is_process_type("worker-2_b"));
+        assert!(!is_process_type(""));
+        assert!(!is_process_type("web app"));
+    }
+}
@@ -0,0 +1,59 @@
+use std::io::{self, IsTerminal, Write};
+
+/// Asks a yes or no question on the terminal. Returns `default` without asking if stdin is not a
+/// terminal, eg. in scripts. The question goes to stderr to keep stdout parseable.
+///
+/// # Arguments
+///
+/// * `question` - Eg. `Restart hello-world?`
+/// * `default` - Answer used for an empty reply
+///
+pub fn confirm(question: &str, default: bool) -> io::Result<bool> {
+    if !io::stdin().is_terminal() {
+        return Ok(default);
+    }
+
+    let choices = if default { "[Y/n]" } else { "[y/N]" };
+    eprint!("{question} {choices} ");
+    io::stderr().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+
+    Ok(match answer.trim().to_lowercase().as_str() {
+        "y" | "yes" => true,
+        "n" | "no" => false,
+        _ => default,
+    })
+}
+
+/// Asks for a line of text on the terminal. Returns `default`, or an empty string without a
+/// default, if stdin is not a terminal or the reply is empty.
+///
+/// # Arguments
+///
+/// * `question` - Eg. `Run hello-world as`
+/// * `default` - Answer used for an empty reply, shown in brackets
+///
+pub fn ask(question: &str, default: Option<&str>) -> io::Result<String> {
+    let default = default.unwrap_or_default();
+
+    if !io::stdin().is_terminal() {
+        return Ok(default.to_string());
+    }
+
+    if default.is_empty() {
+        eprint!("{question}: ");
+    } else {
+        eprint!("{question} [{default}]: ");
+    }
+    io::stderr().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+
+    Ok(match answer.trim() {
+        "" => default.to_string(),
+        answer => answer.to_string(),
+    })
+}
@@ -0,0 +1,194 @@
+use std::{
+    ffi::{CStr, CString},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use serde::Deserialize;
+use zbus::Connection;
+
+/// Which systemd manager servicer talks to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// The system manager on the system bus. Needs root
+    #[default]
+    System,
+
+    /// The manager of the calling user on the session bus, like `systemctl --user`
+    User,
+}
+
+/// Settings read from `~/.config/servicer/config.toml`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Scope used if neither `--user` nor `--system` is passed, eg. `mode = "user"`
+    pub mode: Scope,
+}
+
+static SCOPE: OnceLock<Scope> = OnceLock::new();
+
+#[cfg(test)]
+thread_local! {
+    /// State directory of the running test, so tests don't write to `/var/lib/servicer`. Tests
+    /// run on their own threads.
+    pub static TEST_STATE_DIRECTORY: std::cell::RefCell<Option<PathBuf>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+/// Sets the scope. Must be called once before any service is touched.
+pub fn set_scope(scope: Scope) {
+    SCOPE.set(scope).expect("Scope is already set");
+}
+
+/// Whether servicer manages user services, selected with `--user` or the config file
+pub fn is_user() -> bool {
+    SCOPE.get().copied().unwrap_or_default() == Scope::User
+}
+
+/// Connects to the bus of the systemd manager in use: the session bus in user mode, else the
+/// system bus
+pub async fn connect() -> zbus::Result<Connection> {
+    if is_user() {
+        Connection::session().await
+    } else {
+        Connection::system().await
+    }
+}
+
+/// Name of the bus used by `connect()`, for messages
+pub fn get_bus_name() -> &'static str {
+    if is_user() {
+        "session bus"
+    } else {
+        "system bus"
+    }
+}
+
+/// Directory of the .service files, `/etc/systemd/system` or `~/.config/systemd/user`
+pub fn get_unit_directory() -> PathBuf {
+    if is_user() {
+        get_config_home().join("systemd/user")
+    } else {
+        PathBuf::from("/etc/systemd/system")
+    }
+}
+
+/// Directory where servicer keeps history and the trash, `/var/lib/servicer` or
+/// `~/.local/state/servicer`
+pub fn get_state_directory() -> PathBuf {
+    #[cfg(test)]
+    if let Some(directory) = TEST_STATE_DIRECTORY.with_borrow(Clone::clone) {
+        return directory;
+    }
+
+    if is_user() {
+        std::env::var_os("XDG_STATE_HOME")
+            .filter(|directory| Path::new(directory).is_absolute())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| get_home_directory().join(".local/state"))
+            .join("servicer")
+    } else {
+        PathBuf::from("/var/lib/servicer")
+    }
+}
+
+/// Target started on boot, or on login for user services
+pub fn get_default_target() -> &'static str {
+    if is_user() {
+        "default.target"
+    } else {
+        "multi-user.target"
+    }
+}
+
+/// Reads `config.toml` from `$XDG_CONFIG_HOME/servicer`, else `~/.config/servicer`. Defaults
+/// are used if the file doesn't exist.
+pub fn load_config() -> Result<Config, Box<dyn std::error::Error>> {
+    let path = get_config_home().join("servicer/config.toml");
+
+    match std::fs::read_to_string(&path) {
+        Ok(contents) => toml::from_str(&contents)
+            .map_err(|error| format!("Invalid config {}: {error}", path.display()).into()),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Name of the calling user, from the password database
+pub fn get_user_name() -> Option<String> {
+    let passwd = unsafe { libc::getpwuid(libc::getuid()) };
+
+    if passwd.is_null() {
+        None
+    } else {
+        Some(
+            unsafe { CStr::from_ptr((*passwd).pw_name) }
+                .to_string_lossy()
+                .to_string(),
+        )
+    }
+}
+
+/// Whether a user exists in the password database
+///
+/// # Arguments
+///
+/// * `name` - User name
+///
+pub fn user_exists(name: &str) -> bool {
+    let Ok(name) = CString::new(name) else {
+        return false;
+    };
+
+    !unsafe { libc::getpwnam(name.as_ptr()) }.is_null()
+}
+
+/// Home directory of a user from the password database
+///
+/// # Arguments
+///
+/// * `name` - User name
+///
+pub fn get_user_home(name: &str) -> Option<PathBuf> {
+    let name = CString::new(name).ok()?;
+    let passwd = unsafe { libc::getpwnam(name.as_ptr()) };
+
+    if passwd.is_null() {
+        None
+    } else {
+        Some(PathBuf::from(
+            unsafe { CStr::from_ptr((*passwd).pw_dir) }
+                .to_string_lossy()
+                .to_string(),
+        ))
+    }
+}
+
+/// `$XDG_CONFIG_HOME`, else `~/.config`
+fn get_config_home() -> PathBuf {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|directory| Path::new(directory).is_absolute())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| get_home_directory().join(".config"))
+}
+
+/// `$HOME`, else the home directory in the password database
+pub fn get_home_directory() -> PathBuf {
+    if let Some(home) = std::env::var_os("HOME").filter(|home| !home.is_empty()) {
+        return PathBuf::from(home);
+    }
+
+    let passwd = unsafe { libc::getpwuid(libc::getuid()) };
+
+    if passwd.is_null() {
+        PathBuf::from("/")
+    } else {
+        PathBuf::from(
+            unsafe { CStr::from_ptr((*passwd).pw_dir) }
+                .to_string_lossy()
+                .to_string(),
+        )
+    }
+}
@@ -1,18 +1,83 @@
-use super::systemd::ManagerProxy;
+use std::fmt;
 
-/// Starts a service
+use futures_util::StreamExt;
+use zbus::zvariant::OwnedObjectPath;
+
+use super::{
+    error::ServicerError,
+    systemd::{get_active_state, get_sub_state, JobRemovedStream, ManagerProxy},
+};
+
+/// Default time in seconds to wait for a systemd job to finish
+pub const DEFAULT_JOB_TIMEOUT: u64 = 90;
+
+/// Outcome of a start, stop, restart or reload job
+pub struct JobOutcome {
+    /// Job result from the `JobRemoved` signal: `done`, `canceled`, `timeout`, `failed`,
+    /// `dependency` or `skipped`. Also `timeout` if servicer stopped waiting for the job.
+    pub result: String,
+
+    /// Active state of the service after the job finished
+    pub active_state: String,
+
+    /// Sub-state of the service after the job finished, eg. `running`
+    pub sub_state: String,
+}
+
+impl JobOutcome {
+    /// Whether the job finished and the service ended up in one of the expected active states
+    ///
+    /// # Arguments
+    ///
+    /// * `expected_states` - Acceptable active states, eg. `active`
+    ///
+    pub fn reached(&self, expected_states: &[&str]) -> bool {
+        self.result == "done" && expected_states.contains(&self.active_state.as_str())
+    }
+}
+
+impl fmt::Display for JobOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "job {}, service is {} ({})",
+            self.result, self.active_state, self.sub_state
+        )
+    }
+}
+
+/// Starts a service and waits for the job to finish
 ///
 /// # Arguments
 ///
 /// * `manager_proxy`: Manager proxy object
 /// * `full_service_name`: Full name of the service, having '.ser.service' at the end
+/// * `timeout`: Seconds to wait for the job
 ///
-pub async fn start_service(manager_proxy: &ManagerProxy<'_>, full_service_name: &String) -> String {
-    manager_proxy
-        .start_unit(full_service_name.clone(), "replace".into())
+pub async fn start_service(
+    manager_proxy: &ManagerProxy<'_>,
+    full_service_name: &str,
+    timeout: u64,
+) -> Result<JobOutcome, ServicerError> {
+    let job_removed = receive_job_removed(manager_proxy).await?;
+
+    let job = manager_proxy
+        .start_unit(full_service_name.to_owned(), "replace".into())
         .await
-        .unwrap_or_else(|_| panic!("Failed to start service {full_service_name}"))
-        .to_string()
+        .map_err(|e| ServicerError::from_dbus(e, "start", full_service_name))?;
+
+    wait_for_job(manager_proxy, job_removed, job, full_service_name, timeout).await
+}
+
+/// Reloads every unit file, like `systemctl daemon-reload`. Needed after a .service file is
+/// created, changed or removed, otherwise systemd keeps using the copy it loaded before.
+///
+/// # Arguments
+///
+/// * `manager_proxy`: Manager proxy object
+///
+pub async fn daemon_reload(manager_proxy: &ManagerProxy<'_>) -> Result<(), ServicerError> {
+    manager_proxy.reload().await.map_err(ServicerError::from)
 }
 
 /// Enables a service on boot
@@ -25,34 +90,57 @@ pub async fn start_service(manager_proxy: &ManagerProxy<'_>, full_service_name:
 pub async fn enable_service(
     manager_proxy: &ManagerProxy<'_>,
     full_service_name: &str,
-) -> (bool, Vec<(String, String, String)>) {
+) -> Result<(bool, Vec<(String, String, String)>), ServicerError> {
     manager_proxy
         .enable_unit_files(vec![full_service_name.to_owned()], false, true)
         .await
-        .unwrap_or_else(|_| {
-            panic!("Failed to enable service {full_service_name}. Retry in sudo mode.")
-        })
+        .map_err(|e| ServicerError::from_dbus(e, "enable", full_service_name))
 }
 
-pub async fn stop_service(manager_proxy: &ManagerProxy<'_>, full_service_name: &str) {
-    manager_proxy
+/// Stops a service and waits for the job to finish
+///
+/// # Arguments
+///
+/// * `manager_proxy`: Manager proxy object
+/// * `full_service_name`: Full name of the service, having '.ser.service' at the end
+/// * `timeout`: Seconds to wait for the job
+///
+pub async fn stop_service(
+    manager_proxy: &ManagerProxy<'_>,
+    full_service_name: &str,
+    timeout: u64,
+) -> Result<JobOutcome, ServicerError> {
+    let job_removed = receive_job_removed(manager_proxy).await?;
+
+    let job = manager_proxy
         .stop_unit(full_service_name.to_string(), "replace".into())
         .await
-        .unwrap_or_else(|_| panic!("Failed to stop service {full_service_name}"));
+        .map_err(|e| ServicerError::from_dbus(e, "stop", full_service_name))?;
+
+    wait_for_job(manager_proxy, job_removed, job, full_service_name, timeout).await
 }
 
-/// Reloads the unit of a failed service
+/// Reloads the unit of a failed service and waits for the job to finish
 ///
 /// # Arguments
 ///
 /// * `manager_proxy`: Manager proxy object
 /// * `full_service_name`: Full name of the service, having '.ser.service' at the end
+/// * `timeout`: Seconds to wait for the job
 ///
-pub async fn reload_service(manager_proxy: &ManagerProxy<'_>, full_service_name: &str) {
-    manager_proxy
+pub async fn reload_service(
+    manager_proxy: &ManagerProxy<'_>,
+    full_service_name: &str,
+    timeout: u64,
+) -> Result<JobOutcome, ServicerError> {
+    let job_removed = receive_job_removed(manager_proxy).await?;
+
+    let job = manager_proxy
         .reload_unit(full_service_name.to_owned(), "replace".into())
         .await
-        .unwrap_or_else(|_| panic!("Failed to reload service {full_service_name}. Ensure it has an ExecReload statement"));
+        .map_err(|e| ServicerError::from_dbus(e, "reload", full_service_name))?;
+
+    wait_for_job(manager_proxy, job_removed, job, full_service_name, timeout).await
 }
 
 /// Disables a service on boot
@@ -62,11 +150,106 @@ pub async fn reload_service(manager_proxy: &ManagerProxy<'_>, full_service_name:
 /// * `manager_proxy`: Manager proxy object
 /// * `full_service_name`: Full name of the service, having '.ser.service' at the end
 ///
-pub async fn disable_service(manager_proxy: &ManagerProxy<'_>, full_service_name: &str) {
+pub async fn disable_service(
+    manager_proxy: &ManagerProxy<'_>,
+    full_service_name: &str,
+) -> Result<(), ServicerError> {
     manager_proxy
         .disable_unit_files(vec![full_service_name.to_owned()], false)
         .await
-        .unwrap_or_else(|_| {
-            panic!("Failed to disable service {full_service_name}. Retry in sudo mode.")
-        });
+        .map_err(|e| ServicerError::from_dbus(e, "disable", full_service_name))?;
+
+    Ok(())
+}
+
+/// Restarts a service and waits for the job to finish. A stopped service is started unless
+/// `if_running` is set, in which case systemd leaves it alone.
+///
+/// # Arguments
+///
+/// * `manager_proxy`: Manager proxy object
+/// * `full_service_name`: Full name of the service, having '.ser.service' at the end
+/// * `if_running`: Only restart the service if it is already running
+/// * `timeout`: Seconds to wait for the job
+///
+pub async fn restart_service(
+    manager_proxy: &ManagerProxy<'_>,
+    full_service_name: &str,
+    if_running: bool,
+    timeout: u64,
+) -> Result<JobOutcome, ServicerError> {
+    let job_removed = receive_job_removed(manager_proxy).await?;
+
+    let result = if if_running {
+        manager_proxy
+            .try_restart_unit(full_service_name.to_owned(), "replace".into())
+            .await
+    } else {
+        manager_proxy
+            .restart_unit(full_service_name.to_owned(), "replace".into())
+            .await
+    };
+
+    let job = result.map_err(|e| ServicerError::from_dbus(e, "restart", full_service_name))?;
+
+    wait_for_job(manager_proxy, job_removed, job, full_service_name, timeout).await
+}
+
+/// Subscribes to `JobRemoved` signals. Must be called before queueing the job, otherwise a job
+/// which finishes quickly is missed.
+///
+/// # Arguments
+///
+/// * `manager_proxy`: Manager proxy object
+///
+async fn receive_job_removed(manager_proxy: &ManagerProxy<'_>) -> zbus::Result<JobRemovedStream> {
+    let job_removed = manager_proxy.receive_job_removed().await?;
+
+    // systemd only emits job signals once a client subscribes. This fails harmlessly if the
+    // connection is already subscribed.
+    let _ = manager_proxy.subscribe().await;
+
+    Ok(job_removed)
+}
+
+/// Waits until systemd removes a job, then reads the resulting state of the service
+///
+/// # Arguments
+///
+/// * `manager_proxy`: Manager proxy object
+/// * `job_removed`: Stream from `receive_job_removed()`, created before queueing the job
+/// * `job`: Object path of the queued job
+/// * `full_service_name`: Full name of the service, having '.ser.service' at the end
+/// * `timeout`: Seconds to wait for the job
+///
+async fn wait_for_job(
+    manager_proxy: &ManagerProxy<'_>,
+    mut job_removed: JobRemovedStream,
+    job: OwnedObjectPath,
+    full_service_name: &str,
+    timeout: u64,
+) -> Result<JobOutcome, ServicerError> {
+    let job_result = async {
+        while let Some(signal) = job_removed.next().await {
+            if let Ok(args) = signal.args() {
+                if args.job == job {
+                    return args.result;
+                }
+            }
+        }
+
+        "canceled".to_string()
+    };
+
+    let result = tokio::time::timeout(std::time::Duration::from_secs(timeout), job_result)
+        .await
+        .unwrap_or_else(|_| "timeout".to_string());
+
+    let connection = manager_proxy.inner().connection();
+
+    Ok(JobOutcome {
+        result,
+        active_state: get_active_state(connection, full_service_name).await?,
+        sub_state: get_sub_state(connection, full_service_name).await?,
+    })
 }
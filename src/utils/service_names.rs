@@ -1,4 +1,6 @@
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
+
+use super::{error::ServicerError, scope::get_unit_directory};
 
 /// Shortens the service name from `example.ser.service` to `example`.
 ///
@@ -41,12 +43,149 @@ pub fn is_full_name(name: &str) -> bool {
     name.ends_with(&service_extension)
 }
 
+/// Checks that a short service name can be used in a systemd unit name
+///
+/// # Arguments
+///
+/// * `name` - The service name without '.ser.service'
+///
+pub fn validate_service_name(name: &str) -> Result<(), ServicerError> {
+    let invalid = |reason: &str| {
+        Err(ServicerError::InvalidName {
+            name: name.to_string(),
+            reason: reason.to_string(),
+        })
+    };
+
+    validate_path_name(name)?;
+
+    // systemd limits unit names to 255 characters including the suffix
+    if get_full_service_name(name).len() > 255 {
+        return invalid("the name is too long");
+    }
+
+    if let Some(c) = name
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || ":-_.\\@".contains(*c)))
+    {
+        return invalid(&format!("{c:?} is not allowed"));
+    }
+
+    Ok(())
+}
+
+/// Checks that a name joined to a directory stays inside it, eg. that `../../etc/foo` doesn't
+/// point outside the unit or history directory
+///
+/// # Arguments
+///
+/// * `name` - The service name, with or without '.ser.service'
+///
+pub fn validate_path_name(name: &str) -> Result<(), ServicerError> {
+    let invalid = |reason: &str| {
+        Err(ServicerError::InvalidName {
+            name: name.to_string(),
+            reason: reason.to_string(),
+        })
+    };
+
+    if name.is_empty() {
+        return invalid("the name is empty");
+    }
+
+    if name == "." || name == ".." {
+        return invalid("the name is a relative path");
+    }
+
+    if let Some(c) = name.chars().find(|c| *c == '/' || *c == '\0') {
+        return invalid(&format!("{c:?} is not allowed"));
+    }
+
+    Ok(())
+}
+
 /// Get the path to a service file
 ///
 /// # Arguments
 ///
 /// * `full_service_name`
 ///
-pub fn get_service_file_path(full_service_name: &str) -> PathBuf {
-    Path::new("/etc/systemd/system/").join(full_service_name)
+pub fn get_service_file_path(full_service_name: &str) -> Result<PathBuf, ServicerError> {
+    validate_path_name(full_service_name)?;
+
+    Ok(get_unit_directory().join(full_service_name))
+}
+
+/// Get the directory of drop-in files overriding a service, eg.
+/// `/etc/systemd/system/hello-world.ser.service.d`
+///
+/// # Arguments
+///
+/// * `full_service_name`
+///
+pub fn get_drop_in_directory(full_service_name: &str) -> Result<PathBuf, ServicerError> {
+    validate_path_name(full_service_name)?;
+
+    Ok(get_unit_directory().join(format!("{full_service_name}.d")))
+}
+
+/// Get systemd services having an extension `.ser.service`. We only monitor services created by this tool
+pub fn get_servicer_services() -> Result<Vec<String>, std::io::Error> {
+    let folder_path = get_unit_directory();
+
+    let mut files = Vec::<String>::new();
+    let dir = match std::fs::read_dir(folder_path) {
+        Ok(dir) => dir,
+        // ~/.config/systemd/user doesn't exist until the first user service is created
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(files),
+        Err(error) => return Err(error),
+    };
+
+    for entry in dir.flatten() {
+        let path = entry.path();
+
+        // Names which aren't UTF-8 can't be servicer services
+        if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+            if path.is_file() && is_full_name(name) {
+                files.push(name.to_string());
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_names_leaving_the_directory() {
+        for name in ["", ".", "..", "../../etc/foo", "a/b", "a\0b"] {
+            assert!(validate_path_name(name).is_err(), "{name:?}");
+            assert!(validate_service_name(name).is_err(), "{name:?}");
+        }
+
+        assert!(get_service_file_path("../../etc/foo.ser.service").is_err());
+        assert!(get_drop_in_directory("../foo.ser.service").is_err());
+    }
+
+    #[test]
+    fn accepts_service_names() {
+        for name in ["hello-world", "api_2", "a.b", "...", "worker@1", "x:y"] {
+            assert!(validate_path_name(name).is_ok(), "{name:?}");
+            assert!(validate_service_name(name).is_ok(), "{name:?}");
+        }
+
+        assert!(validate_service_name("hello world").is_err());
+        assert!(validate_service_name(&"a".repeat(250)).is_err());
+    }
+
+    #[test]
+    fn converts_names() {
+        assert_eq!(get_full_service_name("hello"), "hello.ser.service");
+        assert_eq!(get_short_service_name("hello.ser.service"), "hello");
+        assert!(is_full_name("hello.ser.service"));
+        assert!(!is_full_name("hello.service"));
+    }
 }
@@ -0,0 +1,153 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    error::ServicerError, scope::get_state_directory, service_names::get_drop_in_directory,
+};
+
+/// Version of the snapshot format. Bumped when fields are removed or change meaning.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Every servicer service of a host, written by `ser save` and read by `ser resurrect`
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Format version, see `SNAPSHOT_VERSION`
+    pub version: u32,
+
+    /// When the snapshot was saved, in RFC 3339 format
+    pub saved_at: String,
+
+    /// Host the snapshot was saved on
+    pub hostname: String,
+
+    pub services: Vec<ServiceSnapshot>,
+}
+
+/// A service in a snapshot
+#[derive(Serialize, Deserialize)]
+pub struct ServiceSnapshot {
+    /// The service name without '.ser.service'
+    pub name: String,
+
+    /// Contents of the .service file
+    pub service_file: String,
+
+    /// Contents of the `.conf` files in the drop-in directory by file name
+    #[serde(default)]
+    pub drop_ins: BTreeMap<String, String>,
+
+    /// Whether the service started on boot
+    pub enabled: bool,
+
+    /// Whether the service was running
+    pub active: bool,
+}
+
+/// File used by `ser save` and `ser resurrect` if none is given, `/var/lib/servicer/dump.json`
+/// or `~/.local/state/servicer/dump.json` for user services
+pub fn get_default_snapshot_path() -> PathBuf {
+    get_state_directory().join("dump.json")
+}
+
+/// Name of this host
+pub fn get_hostname() -> String {
+    let mut buffer = [0u8; 256];
+
+    if unsafe { libc::gethostname(buffer.as_mut_ptr().cast(), buffer.len()) } != 0 {
+        return "unknown".to_string();
+    }
+
+    let length = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
+    String::from_utf8_lossy(&buffer[..length]).to_string()
+}
+
+/// Reads a snapshot written by `ser save`
+///
+/// # Arguments
+///
+/// * `path` - Path of the snapshot
+///
+pub fn read_snapshot(path: &Path) -> Result<Snapshot, Box<dyn std::error::Error>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|error| format!("Failed to read {}: {error}", path.display()))?;
+    let snapshot: Snapshot = serde_json::from_str(&contents)
+        .map_err(|error| format!("Invalid snapshot {}: {error}", path.display()))?;
+
+    if snapshot.version > SNAPSHOT_VERSION {
+        return Err(format!(
+            "{} was saved by a newer servicer, with snapshot version {}. Upgrade servicer to \
+            resurrect it",
+            path.display(),
+            snapshot.version
+        )
+        .into());
+    }
+
+    Ok(snapshot)
+}
+
+/// Reads the `.conf` drop-in files of a service by file name. Empty if the service has none.
+///
+/// # Arguments
+///
+/// * `full_service_name` - Full name of the service, having '.ser.service' at the end
+///
+pub fn read_drop_ins(full_service_name: &str) -> Result<BTreeMap<String, String>, ServicerError> {
+    let mut drop_ins = BTreeMap::new();
+
+    let entries = match std::fs::read_dir(get_drop_in_directory(full_service_name)?) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(drop_ins),
+        Err(error) => return Err(error.into()),
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        if path.is_file()
+            && path
+                .extension()
+                .is_some_and(|extension| extension == "conf")
+        {
+            drop_ins.insert(
+                entry.file_name().to_string_lossy().to_string(),
+                std::fs::read_to_string(&path)?,
+            );
+        }
+    }
+
+    Ok(drop_ins)
+}
+
+/// Writes drop-in files of a service, replacing files with the same name
+///
+/// # Arguments
+///
+/// * `full_service_name` - Full name of the service, having '.ser.service' at the end
+/// * `drop_ins` - Contents by file name
+///
+pub fn write_drop_ins(
+    full_service_name: &str,
+    drop_ins: &BTreeMap<String, String>,
+) -> Result<(), ServicerError> {
+    if drop_ins.is_empty() {
+        return Ok(());
+    }
+
+    let directory = get_drop_in_directory(full_service_name)?;
+    std::fs::create_dir_all(&directory)?;
+
+    for (file_name, contents) in drop_ins {
+        // Names come from the snapshot file, keep them inside the drop-in directory
+        let Some(file_name) = Path::new(file_name).file_name() else {
+            continue;
+        };
+        std::fs::write(directory.join(file_name), contents)?;
+    }
+
+    Ok(())
+}
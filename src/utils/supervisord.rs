@@ -0,0 +1,724 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use regex::{Captures, Regex};
+
+use super::{
+    import::{
+        command_to_service, prefixed_service_name, split_command_line, ImportedServices,
+        UnsupportedOption,
+    },
+    scope::is_user,
+    snapshot::get_hostname,
+};
+
+/// Files supervisord reads if none is given, in order
+const DEFAULT_CONFIG_PATHS: &[&str] = &[
+    "supervisord.conf",
+    "etc/supervisord.conf",
+    "/etc/supervisord.conf",
+    "/etc/supervisor/supervisord.conf",
+];
+
+/// Options of a program which are not translated, with what to do instead
+const UNSUPPORTED_OPTIONS: &[(&str, &str)] = &[
+    (
+        "startretries",
+        "systemd limits restarts with StartLimitBurst=, add it with `ser edit`",
+    ),
+    ("stopwaitsecs", "add TimeoutStopSec= with `ser edit`"),
+    (
+        "exitcodes",
+        "add SuccessExitStatus= with `ser edit`, Restart=on-failure restarts on other codes",
+    ),
+    (
+        "priority",
+        "order services with After= and Before=, add them with `ser edit`",
+    ),
+    ("umask", "add UMask= with `ser edit`"),
+];
+
+/// Options of a program which are translated, or need nothing because systemd does the same,
+/// eg. `killasgroup`. Others are reported as unknown.
+const KNOWN_OPTIONS: &[&str] = &[
+    "command",
+    "directory",
+    "environment",
+    "user",
+    "autorestart",
+    "autostart",
+    "startsecs",
+    "stopsignal",
+    "numprocs",
+    "numprocs_start",
+    "process_name",
+    "redirect_stderr",
+    "stopasgroup",
+    "killasgroup",
+    "serverurl",
+];
+
+/// A section of a supervisord configuration, eg. `[program:web]`
+struct Section {
+    /// Text between the brackets, eg. `program:web`
+    name: String,
+
+    /// Options by lowercase name
+    options: BTreeMap<String, String>,
+
+    /// Directory of the file having the section, `%(here)s`
+    here: PathBuf,
+}
+
+/// The first configuration supervisord would read: `supervisord.conf` in the current directory,
+/// `/etc/supervisord.conf` or `/etc/supervisor/supervisord.conf`
+pub fn get_default_config_path() -> Option<PathBuf> {
+    DEFAULT_CONFIG_PATHS
+        .iter()
+        .map(PathBuf::from)
+        .find(|path| path.is_file())
+}
+
+/// Translates the programs of a supervisord configuration and the files it includes to
+/// services. A program with `numprocs` becomes a service per process.
+///
+/// # Arguments
+///
+/// * `path` - The configuration, eg. `/etc/supervisor/supervisord.conf`
+/// * `prefix` - Prefix of the service names. Defaults to the group of each program, if it's in
+///   one. Empty for no prefix.
+/// * `start` - Start the programs having `autostart`, which is the default
+/// * `enable` - Enable the programs having `autostart`
+///
+pub fn import_supervisord(
+    path: &Path,
+    prefix: Option<&str>,
+    start: bool,
+    enable: bool,
+) -> Result<ImportedServices, Box<dyn std::error::Error>> {
+    let mut sections = read_sections(path)?;
+
+    // Like supervisord, only the main file can include others
+    let includes: Vec<(String, PathBuf)> = sections
+        .iter()
+        .filter(|section| section.name == "include")
+        .filter_map(|section| Some((section.options.get("files")?.clone(), section.here.clone())))
+        .collect();
+    for (files, here) in includes {
+        for pattern in files.split_whitespace() {
+            let pattern = here.join(pattern).to_string_lossy().to_string();
+            let mut paths: Vec<PathBuf> = glob::glob(&pattern)
+                .map_err(|error| format!("Invalid include {pattern}: {error}"))?
+                .flatten()
+                .collect();
+            paths.sort();
+
+            for path in paths {
+                sections.extend(read_sections(&path)?);
+            }
+        }
+    }
+
+    // Programs by the group they are in
+    let mut groups = BTreeMap::new();
+    for section in &sections {
+        let Some(group) = section.name.strip_prefix("group:") else {
+            continue;
+        };
+        for program in section
+            .options
+            .get("programs")
+            .into_iter()
+            .flat_map(|p| p.split(','))
+        {
+            groups.insert(program.trim().to_string(), group.to_string());
+        }
+    }
+
+    let mut imported = ImportedServices::default();
+
+    for section in &sections {
+        let Some((kind, name)) = section.name.split_once(':') else {
+            continue;
+        };
+
+        match kind {
+            "program" => {
+                let group = groups.get(name).map(String::as_str);
+                translate_program(
+                    name,
+                    section,
+                    group,
+                    prefix.or(group),
+                    start,
+                    enable,
+                    &mut imported,
+                )?;
+            }
+            "eventlistener" | "fcgi-program" => imported.unsupported.push(UnsupportedOption::new(
+                name,
+                &format!("[{kind}]"),
+                "not supported, only [program] sections are imported".to_string(),
+            )),
+            _ => {}
+        }
+    }
+
+    Ok(imported)
+}
+
+/// Translates one program, adding a service per process to `imported`
+///
+/// # Arguments
+///
+/// * `name` - Name of the program, eg. `web` for `[program:web]`
+/// * `section` - The program's section
+/// * `group` - Group the program is in
+/// * `prefix` - Prefix of the service names
+/// * `start` - Start the service if the program has `autostart`
+/// * `enable` - Enable the service if the program has `autostart`
+/// * `imported` - Translated services and unsupported options
+///
+fn translate_program(
+    name: &str,
+    section: &Section,
+    group: Option<&str>,
+    prefix: Option<&str>,
+    start: bool,
+    enable: bool,
+    imported: &mut ImportedServices,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let options = &section.options;
+    let report = |option: &str, reason: String| UnsupportedOption::new(name, option, reason);
+    let invalid = |option: &str, error: &dyn std::fmt::Display| {
+        format!("Invalid {option} of [program:{name}]: {error}")
+    };
+
+    let Some(command) = options.get("command") else {
+        return Err(format!("[program:{name}] has no command").into());
+    };
+
+    let mut variables = BTreeMap::from([
+        ("program_name".to_string(), name.to_string()),
+        ("group_name".to_string(), group.unwrap_or(name).to_string()),
+        (
+            "here".to_string(),
+            section.here.to_string_lossy().to_string(),
+        ),
+        ("host_node_name".to_string(), get_hostname()),
+    ]);
+
+    // User services run as the calling user
+    let user = match options.get("user") {
+        Some(_) if is_user() => {
+            imported
+                .unsupported
+                .push(report("user", "user services run as your user".to_string()));
+            None
+        }
+        Some(user) => Some(user.clone()),
+        // supervisord runs programs as root unless they set `user`, so don't default to the user
+        // who called sudo like `ser create`
+        None if is_user() => None,
+        None => Some("root".to_string()),
+    };
+
+    let restart = match options.get("autorestart").map(|value| value.to_lowercase()) {
+        Some(value) if value == "unexpected" => Some("on-failure"),
+        Some(value) => match parse_boolean(&value) {
+            Some(true) => Some("always"),
+            Some(false) => None,
+            None => return Err(invalid("autorestart", &value).into()),
+        },
+        None => Some("on-failure"),
+    };
+
+    let autostart = match options.get("autostart") {
+        Some(value) => parse_boolean(value).ok_or_else(|| invalid("autostart", value))?,
+        None => true,
+    };
+
+    let start_secs = options
+        .get("startsecs")
+        .map(|seconds| seconds.parse::<u64>())
+        .transpose()
+        .map_err(|error| invalid("startsecs", &error))?;
+
+    let numprocs = options
+        .get("numprocs")
+        .map_or(Ok(1), |count| count.parse::<u32>())
+        .map_err(|error| invalid("numprocs", &error))?;
+    let numprocs_start = options
+        .get("numprocs_start")
+        .map_or(Ok(0), |first| first.parse::<u32>())
+        .map_err(|error| invalid("numprocs_start", &error))?;
+
+    for (option, reason) in UNSUPPORTED_OPTIONS {
+        if options.contains_key(*option) {
+            imported
+                .unsupported
+                .push(report(option, reason.to_string()));
+        }
+    }
+
+    for (option, stream) in [("stdout_logfile", "output"), ("stderr_logfile", "errors")] {
+        if let Some(file) = options
+            .get(option)
+            .filter(|file| !matches!(file.as_str(), "AUTO" | "NONE"))
+        {
+            imported.unsupported.push(report(
+                option,
+                format!("journald keeps the {stream} instead of {file}, view with `ser logs`"),
+            ));
+        }
+    }
+
+    for option in options.keys() {
+        // Log sizes, rotation and events are up to journald
+        let known = KNOWN_OPTIONS.contains(&option.as_str())
+            || UNSUPPORTED_OPTIONS.iter().any(|(known, _)| known == option)
+            || option.starts_with("stdout_")
+            || option.starts_with("stderr_");
+
+        if !known {
+            imported
+                .unsupported
+                .push(report(option, "unknown supervisord option".to_string()));
+        }
+    }
+
+    let process_name = options
+        .get("process_name")
+        .map_or("%(program_name)s", String::as_str);
+
+    for process_num in numprocs_start..numprocs_start + numprocs {
+        variables.insert("process_num".to_string(), process_num.to_string());
+
+        let command = expand(command, &variables).map_err(|error| invalid("command", &error))?;
+        let directory = match options.get("directory") {
+            Some(directory) => PathBuf::from(
+                expand(directory, &variables).map_err(|error| invalid("directory", &error))?,
+            ),
+            None => PathBuf::from("/"),
+        };
+
+        let mut service = command_to_service(&command, &directory, user.as_deref())?;
+        if let Some(environment) = options.get("environment") {
+            let environment =
+                expand(environment, &variables).map_err(|error| invalid("environment", &error))?;
+            service.env = parse_environment(&environment);
+        }
+        service.restart = restart.map(str::to_string);
+        service.start_secs = start_secs;
+        service.stop_signal = options.get("stopsignal").cloned();
+        service.enable = (enable && autostart).then_some(true);
+        service.start = (start && autostart).then_some(true);
+
+        let mut process_name =
+            expand(process_name, &variables).map_err(|error| invalid("process_name", &error))?;
+
+        // supervisord refuses several processes with the same name, number them instead
+        if numprocs > 1
+            && !options
+                .get("process_name")
+                .is_some_and(|n| n.contains("process_num"))
+        {
+            process_name = format!("{process_name}-{process_num}");
+        }
+
+        imported
+            .services
+            .insert(prefixed_service_name(prefix, &process_name), service);
+    }
+
+    Ok(())
+}
+
+/// Reads the sections of one configuration file like supervisord, which uses Python's
+/// configparser: `key = value` or `key: value`, comments starting with `;` or `#`, and indented
+/// lines continuing the previous value
+///
+/// # Arguments
+///
+/// * `path` - The file
+///
+fn read_sections(path: &Path) -> Result<Vec<Section>, Box<dyn std::error::Error>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|error| format!("Failed to read {}: {error}", path.display()))?;
+    let here = std::fs::canonicalize(path)?
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let invalid = |index: usize, reason: &str| {
+        format!("Invalid line {} of {}: {reason}", index + 1, path.display())
+    };
+
+    let mut sections: Vec<Section> = vec![];
+    let mut last_option: Option<String> = None;
+
+    for (index, line) in contents.lines().enumerate() {
+        let trimmed = strip_inline_comment(line.trim());
+        if trimmed.is_empty() || trimmed.starts_with([';', '#']) {
+            continue;
+        }
+
+        if line.starts_with(char::is_whitespace) {
+            if let (Some(section), Some(option)) = (sections.last_mut(), &last_option) {
+                if let Some(value) = section.options.get_mut(option) {
+                    value.push('\n');
+                    value.push_str(trimmed);
+                    continue;
+                }
+            }
+        }
+
+        if let Some(name) = trimmed.strip_prefix('[') {
+            let Some(name) = name.strip_suffix(']') else {
+                return Err(invalid(index, "expected `]`").into());
+            };
+            sections.push(Section {
+                name: name.trim().to_string(),
+                options: BTreeMap::new(),
+                here: here.clone(),
+            });
+            last_option = None;
+            continue;
+        }
+
+        let Some(section) = sections.last_mut() else {
+            return Err(invalid(index, "option outside of a section").into());
+        };
+
+        let Some(delimiter) = trimmed.find(['=', ':']) else {
+            return Err(invalid(index, "expected `key = value`").into());
+        };
+        let option = trimmed[..delimiter].trim().to_lowercase();
+        let value = trimmed[delimiter + 1..].trim().to_string();
+
+        section.options.insert(option.clone(), value);
+        last_option = Some(option);
+    }
+
+    Ok(sections)
+}
+
+/// Removes a comment after a value, which starts with `;` or `#` after whitespace
+///
+/// # Arguments
+///
+/// * `line` - A trimmed line
+///
+fn strip_inline_comment(line: &str) -> &str {
+    let comment = line
+        .char_indices()
+        .zip(line.chars().skip(1))
+        .find(|((_, c), next)| c.is_whitespace() && matches!(next, ';' | '#'))
+        .map(|((index, _), _)| index);
+
+    match comment {
+        Some(index) => line[..index].trim_end(),
+        None => line,
+    }
+}
+
+/// Expands `%(name)s` and `%(name)02d` like supervisord, with `%(ENV_X)s` reading the variable X
+/// of this process and `%%` giving `%`
+///
+/// # Arguments
+///
+/// * `value` - Value of an option
+/// * `variables` - Values by name, eg. `program_name`
+///
+fn expand(value: &str, variables: &BTreeMap<String, String>) -> Result<String, String> {
+    let pattern = Regex::new(r"%%|%\((\w+)\)(\d*)[sd]").unwrap();
+    let mut unknown = None;
+
+    let expanded = pattern.replace_all(value, |captures: &Captures| {
+        let Some(name) = captures.get(1).map(|name| name.as_str()) else {
+            return "%".to_string();
+        };
+
+        let value = match name.strip_prefix("ENV_") {
+            Some(variable) => std::env::var(variable).ok(),
+            None => variables.get(name).cloned(),
+        };
+        let Some(value) = value else {
+            unknown.get_or_insert(format!("unknown variable %({name})"));
+            return String::new();
+        };
+
+        // Eg. `02` pads process numbers with zeros to two digits
+        let width = &captures[2];
+        let padding = if width.starts_with('0') { "0" } else { " " };
+        let width: usize = width.parse().unwrap_or(0);
+        let missing = width.saturating_sub(value.chars().count());
+
+        padding.repeat(missing) + &value
+    });
+
+    match unknown {
+        Some(error) => Err(error),
+        None => Ok(expanded.to_string()),
+    }
+}
+
+/// Parses a boolean like supervisord, which also takes `yes`, `on` and `1` and their opposites
+///
+/// # Arguments
+///
+/// * `value` - Value of an option
+///
+fn parse_boolean(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Some(true),
+        "false" | "no" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Parses `environment` of a program, eg. `KEY="value",KEY2=other`. Commas inside quotes don't
+/// separate variables.
+///
+/// # Arguments
+///
+/// * `environment` - The option after expanding variables
+///
+fn parse_environment(environment: &str) -> BTreeMap<String, String> {
+    let mut pairs = vec![];
+    let mut pair = String::new();
+    let mut quote = None;
+
+    for c in environment.chars() {
+        match (c, quote) {
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(open)) if c == open => quote = None,
+            (',', None) => {
+                pairs.push(std::mem::take(&mut pair));
+                continue;
+            }
+            _ => {}
+        }
+        pair.push(c);
+    }
+    pairs.push(pair);
+
+    pairs
+        .iter()
+        .filter_map(|pair| {
+            let (key, value) = pair.split_once('=')?;
+            let value = split_command_line(value).join(" ");
+            Some((key.trim().to_string(), value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUPERVISORD_CONF: &str = "[supervisord]
+logfile = /var/log/supervisord.log ; main log
+
+[program:web]
+command = ./bin/web --port 80%(process_num)02d
+directory = %(here)s
+numprocs = 2
+process_name = %(program_name)s_%(process_num)02d
+environment = A=\"1,2\",B='x y',C=%(group_name)s
+autorestart = unexpected
+startsecs = 5
+stopsignal = INT
+autostart = no
+stdout_logfile = /var/log/web.log
+stdout_logfile_maxbytes = 1MB
+
+[group:shop]
+programs = web
+
+[include]
+files = conf.d/*.conf
+";
+
+    const WORKER_CONF: &str = "# worker
+[program:worker]
+Command: /usr/local/bin/worker
+  --verbose
+startretries = 3
+";
+
+    fn variables(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn reads_sections() {
+        let temp_directory = tempfile::tempdir().unwrap();
+        let directory = temp_directory.path();
+        let path = directory.join("worker.conf");
+        std::fs::write(&path, WORKER_CONF).unwrap();
+
+        let sections = read_sections(&path).unwrap();
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].name, "program:worker");
+        assert_eq!(
+            sections[0].options,
+            variables(&[
+                ("command", "/usr/local/bin/worker\n--verbose"),
+                ("startretries", "3")
+            ])
+        );
+        assert_eq!(sections[0].here, std::fs::canonicalize(directory).unwrap());
+
+        for (contents, reason) in [
+            ("[program:web\n", "expected `]`"),
+            ("command = true\n", "option outside of a section"),
+            ("[program:web]\ncommand true\n", "expected `key = value`"),
+        ] {
+            std::fs::write(&path, contents).unwrap();
+            let error = read_sections(&path).err().unwrap().to_string();
+            assert!(error.ends_with(reason), "{error}");
+        }
+    }
+
+    #[test]
+    fn strips_inline_comments() {
+        assert_eq!(strip_inline_comment("a = b ; comment"), "a = b");
+        assert_eq!(strip_inline_comment("a = b\t# comment"), "a = b");
+        assert_eq!(
+            strip_inline_comment("url = http://x/#top;a"),
+            "url = http://x/#top;a"
+        );
+    }
+
+    #[test]
+    fn expands_variables() {
+        let variables = variables(&[("program_name", "web"), ("process_num", "3")]);
+
+        assert_eq!(
+            expand("%(program_name)s-%(process_num)02d", &variables),
+            Ok("web-03".to_string())
+        );
+        assert_eq!(
+            expand("%(process_num)3d|100%%", &variables),
+            Ok("  3|100%".to_string())
+        );
+        assert_eq!(
+            expand("%(ENV_HOME)s", &variables),
+            Ok(std::env::var("HOME").unwrap_or_default())
+        );
+        assert_eq!(
+            expand("%(here)s", &variables),
+            Err("unknown variable %(here)".to_string())
+        );
+    }
+
+    #[test]
+    fn parses_booleans() {
+        for value in ["true", "Yes", "on", "1"] {
+            assert_eq!(parse_boolean(value), Some(true), "{value}");
+        }
+        for value in ["false", "NO", "off", "0"] {
+            assert_eq!(parse_boolean(value), Some(false), "{value}");
+        }
+        assert_eq!(parse_boolean("maybe"), None);
+    }
+
+    #[test]
+    fn parses_environment() {
+        assert_eq!(
+            parse_environment("A=\"1,2\",B='x y', C=plain,D=\"say \\\"hi\\\"\",broken"),
+            variables(&[
+                ("A", "1,2"),
+                ("B", "x y"),
+                ("C", "plain"),
+                ("D", "say \"hi\"")
+            ])
+        );
+        assert!(parse_environment("").is_empty());
+    }
+
+    #[test]
+    fn imports_programs_groups_and_includes() {
+        let temp_directory = tempfile::tempdir().unwrap();
+        let directory = temp_directory.path();
+        std::fs::create_dir(directory.join("conf.d")).unwrap();
+        std::fs::write(directory.join("supervisord.conf"), SUPERVISORD_CONF).unwrap();
+        std::fs::write(directory.join("conf.d/worker.conf"), WORKER_CONF).unwrap();
+
+        let imported =
+            import_supervisord(&directory.join("supervisord.conf"), None, true, false).unwrap();
+        let here = std::fs::canonicalize(directory).unwrap();
+
+        assert_eq!(
+            imported.services.keys().collect::<Vec<_>>(),
+            ["shop-web_00", "shop-web_01", "worker"]
+        );
+
+        let web = &imported.services["shop-web_01"];
+        assert_eq!(web.path, here.join("./bin/web"));
+        assert_eq!(web.args, ["--port", "8001"]);
+        assert_eq!(web.working_directory.as_deref(), Some(here.as_path()));
+        assert_eq!(
+            web.env,
+            variables(&[("A", "1,2"), ("B", "x y"), ("C", "shop")])
+        );
+        assert_eq!(web.restart.as_deref(), Some("on-failure"));
+        assert_eq!(web.start_secs, Some(5));
+        assert_eq!(web.stop_signal.as_deref(), Some("INT"));
+        // autostart=no
+        assert_eq!(web.start, None);
+
+        let worker = &imported.services["worker"];
+        assert_eq!(worker.path, Path::new("/usr/local/bin/worker"));
+        assert_eq!(worker.args, ["--verbose"]);
+        assert_eq!(worker.working_directory.as_deref(), Some(Path::new("/")));
+        assert_eq!(worker.user.as_deref(), Some("root"));
+        assert_eq!(worker.start, Some(true));
+
+        let reported: Vec<(&str, &str)> = imported
+            .unsupported
+            .iter()
+            .map(|option| (option.app.as_str(), option.option.as_str()))
+            .collect();
+        assert_eq!(
+            reported,
+            [("web", "stdout_logfile"), ("worker", "startretries")]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_programs() {
+        let temp_directory = tempfile::tempdir().unwrap();
+        let directory = temp_directory.path();
+        let path = directory.join("supervisord.conf");
+
+        for (contents, error) in [
+            (
+                "[program:web]\ndirectory = /\n",
+                "[program:web] has no command",
+            ),
+            (
+                "[program:web]\ncommand = /bin/web\nautostart = sometimes\n",
+                "Invalid autostart of [program:web]: sometimes",
+            ),
+            (
+                "[program:web]\ncommand = /bin/web %(nope)s\n",
+                "Invalid command of [program:web]: unknown variable %(nope)",
+            ),
+        ] {
+            std::fs::write(&path, contents).unwrap();
+            assert_eq!(
+                import_supervisord(&path, None, false, false)
+                    .err()
+                    .unwrap()
+                    .to_string(),
+                error
+            );
+        }
+    }
+}
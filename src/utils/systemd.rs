@@ -1,7 +1,14 @@
+use std::collections::HashMap;
+
+use zbus::fdo::PropertiesProxy;
+use zbus::names::InterfaceName;
+use zbus::proxy::CacheProperties;
 use zbus::zvariant;
 use zbus::{Connection, Result};
 use zbus_macros::proxy;
 
+use super::error::ServicerError;
+
 /// Proxy object for `org.freedesktop.systemd1.Manager`.
 /// Taken from https://github.com/lucab/zbus_systemd/blob/main/src/systemd1/generated.rs
 #[proxy(
@@ -23,6 +30,18 @@ pub trait Manager {
     #[zbus(name = "ReloadUnit")]
     fn reload_unit(&self, name: String, mode: String) -> zbus::Result<zvariant::OwnedObjectPath>;
 
+    /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#RestartUnit()) Call interface method `RestartUnit`.
+    #[zbus(name = "RestartUnit")]
+    fn restart_unit(&self, name: String, mode: String) -> zbus::Result<zvariant::OwnedObjectPath>;
+
+    /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#TryRestartUnit()) Call interface method `TryRestartUnit`.
+    #[zbus(name = "TryRestartUnit")]
+    fn try_restart_unit(
+        &self,
+        name: String,
+        mode: String,
+    ) -> zbus::Result<zvariant::OwnedObjectPath>;
+
     /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#EnableUnitFiles()) Call interface method `EnableUnitFiles`.
     #[zbus(name = "EnableUnitFiles")]
     fn enable_unit_files(
@@ -43,6 +62,57 @@ pub trait Manager {
     /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#Reload()) Call interface method `Reload`.
     #[zbus(name = "Reload")]
     fn reload(&self) -> zbus::Result<()>;
+
+    /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#ListUnitsByPatterns()) Call interface method `ListUnitsByPatterns`.
+    #[zbus(name = "ListUnitsByPatterns")]
+    fn list_units_by_patterns(
+        &self,
+        states: Vec<String>,
+        patterns: Vec<String>,
+    ) -> zbus::Result<
+        Vec<(
+            String,
+            String,
+            String,
+            String,
+            String,
+            String,
+            zvariant::OwnedObjectPath,
+            u32,
+            String,
+            zvariant::OwnedObjectPath,
+        )>,
+    >;
+
+    /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#ListUnitFilesByPatterns()) Call interface method `ListUnitFilesByPatterns`.
+    #[zbus(name = "ListUnitFilesByPatterns")]
+    fn list_unit_files_by_patterns(
+        &self,
+        states: Vec<String>,
+        patterns: Vec<String>,
+    ) -> zbus::Result<Vec<(String, String)>>;
+
+    /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#Subscribe()) Call interface method `Subscribe`.
+    #[zbus(name = "Subscribe")]
+    fn subscribe(&self) -> zbus::Result<()>;
+
+    /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#JobRemoved) Receive `JobRemoved` signal.
+    #[zbus(signal, name = "JobRemoved")]
+    fn job_removed(
+        &self,
+        id: u32,
+        job: zvariant::OwnedObjectPath,
+        unit: String,
+        result: String,
+    ) -> zbus::Result<()>;
+
+    /// Get property `Version`.
+    #[zbus(property)]
+    fn version(&self) -> zbus::Result<String>;
+
+    /// Get property `ControlGroup`.
+    #[zbus(property)]
+    fn control_group(&self) -> zbus::Result<String>;
 }
 
 /// Proxy object for `org.freedesktop.systemd1.Unit`.
@@ -58,6 +128,10 @@ pub trait Unit {
     #[zbus(property)]
     fn active_state(&self) -> zbus::Result<String>;
 
+    /// Get property `SubState`.
+    #[zbus(property)]
+    fn sub_state(&self) -> zbus::Result<String>;
+
     /// Get property `LoadState`.
     #[zbus(property)]
     fn load_state(&self) -> zbus::Result<String>;
@@ -65,6 +139,30 @@ pub trait Unit {
     /// Get property `UnitFileState`.
     #[zbus(property)]
     fn unit_file_state(&self) -> zbus::Result<String>;
+
+    /// Get property `Description`.
+    #[zbus(property)]
+    fn description(&self) -> zbus::Result<String>;
+
+    /// Get property `ActiveEnterTimestamp`.
+    #[zbus(property)]
+    fn active_enter_timestamp(&self) -> zbus::Result<u64>;
+
+    /// Get property `InvocationID`.
+    #[zbus(property, name = "InvocationID")]
+    fn invocation_id(&self) -> zbus::Result<Vec<u8>>;
+
+    /// Get property `NeedDaemonReload`.
+    #[zbus(property)]
+    fn need_daemon_reload(&self) -> zbus::Result<bool>;
+
+    /// Get property `StartLimitBurst`.
+    #[zbus(property)]
+    fn start_limit_burst(&self) -> zbus::Result<u32>;
+
+    /// Get property `StartLimitIntervalUSec`.
+    #[zbus(property, name = "StartLimitIntervalUSec")]
+    fn start_limit_interval_usec(&self) -> zbus::Result<u64>;
 }
 
 /// Proxy object for `org.freedesktop.systemd1.Service`.
@@ -75,10 +173,88 @@ pub trait Unit {
     assume_defaults = false,
     gen_blocking = false
 )]
-trait Service {
+pub trait Service {
     /// Get property `MainPID`.
     #[zbus(property, name = "MainPID")]
     fn main_pid(&self) -> zbus::Result<u32>;
+
+    /// Get property `ControlGroup`.
+    #[zbus(property)]
+    fn control_group(&self) -> zbus::Result<String>;
+
+    /// Get property `Slice`.
+    #[zbus(property)]
+    fn slice(&self) -> zbus::Result<String>;
+
+    /// Get property `NRestarts`.
+    #[zbus(property, name = "NRestarts")]
+    fn n_restarts(&self) -> zbus::Result<u32>;
+
+    /// Get property `Result`.
+    #[zbus(property)]
+    fn result(&self) -> zbus::Result<String>;
+
+    /// Get property `ExecMainCode`.
+    #[zbus(property)]
+    fn exec_main_code(&self) -> zbus::Result<i32>;
+
+    /// Get property `ExecMainStatus`.
+    #[zbus(property)]
+    fn exec_main_status(&self) -> zbus::Result<i32>;
+
+    /// Get property `RestartUSec`.
+    #[zbus(property, name = "RestartUSec")]
+    fn restart_usec(&self) -> zbus::Result<u64>;
+
+    /// Get property `ExecStart`.
+    #[zbus(property)]
+    fn exec_start(
+        &self,
+    ) -> zbus::Result<Vec<(String, Vec<String>, bool, u64, u64, u64, u64, u32, i32, i32)>>;
+
+    /// Get property `User`.
+    #[zbus(property)]
+    fn user(&self) -> zbus::Result<String>;
+
+    /// Get property `WorkingDirectory`.
+    #[zbus(property)]
+    fn working_directory(&self) -> zbus::Result<String>;
+
+    /// Get property `Environment`.
+    #[zbus(property)]
+    fn environment(&self) -> zbus::Result<Vec<String>>;
+
+    /// Get property `MemoryCurrent`.
+    #[zbus(property)]
+    fn memory_current(&self) -> zbus::Result<u64>;
+
+    /// Get property `MemoryMax`.
+    #[zbus(property)]
+    fn memory_max(&self) -> zbus::Result<u64>;
+
+    /// Get property `CPUUsageNSec`.
+    #[zbus(property, name = "CPUUsageNSec")]
+    fn cpu_usage_nsec(&self) -> zbus::Result<u64>;
+
+    /// Get property `CPUQuotaPerSecUSec`.
+    #[zbus(property, name = "CPUQuotaPerSecUSec")]
+    fn cpu_quota_per_sec_usec(&self) -> zbus::Result<u64>;
+
+    /// Get property `TasksCurrent`.
+    #[zbus(property)]
+    fn tasks_current(&self) -> zbus::Result<u64>;
+
+    /// Get property `TasksMax`.
+    #[zbus(property)]
+    fn tasks_max(&self) -> zbus::Result<u64>;
+
+    /// Get property `IOReadBytes`.
+    #[zbus(property, name = "IOReadBytes")]
+    fn io_read_bytes(&self) -> zbus::Result<u64>;
+
+    /// Get property `IOWriteBytes`.
+    #[zbus(property, name = "IOWriteBytes")]
+    fn io_write_bytes(&self) -> zbus::Result<u64>;
 }
 
 /// Returns the load state of a systemd unit
@@ -90,22 +266,36 @@ trait Service {
 /// * `connection`: zbus connection
 /// * `full_service_name`: Full name of the service name with '.service' in the end
 ///
-pub async fn get_active_state(connection: &Connection, full_service_name: &str) -> String {
+pub async fn get_active_state(
+    connection: &Connection,
+    full_service_name: &str,
+) -> std::result::Result<String, ServicerError> {
     let object_path = get_unit_path(full_service_name);
 
     match zvariant::ObjectPath::try_from(object_path) {
         Ok(path) => {
-            let unit_proxy = UnitProxy::new(connection, path).await.unwrap();
-            unit_proxy
+            let unit_proxy = UnitProxy::new(connection, path).await?;
+            Ok(unit_proxy
                 .active_state()
                 .await
-                .unwrap_or("invalid-unit-path".into())
+                .unwrap_or("invalid-unit-path".into()))
         }
-        Err(_) => "invalid-unit-path".to_string(),
+        Err(_) => Ok("invalid-unit-path".to_string()),
     }
 }
 
-/// Returns the unit file state of a systemd unit. If the state is `enabled`, the unit loads on every boot
+/// Whether a unit with this `ActiveState` is running or about to run. Includes `activating`,
+/// which also covers services waiting in `auto-restart`.
+///
+/// # Arguments
+///
+/// * `active_state`: The `ActiveState` property, eg. `active`
+///
+pub fn is_running(active_state: &str) -> bool {
+    matches!(active_state, "active" | "reloading" | "activating")
+}
+
+/// Returns the sub-state of a systemd unit, eg. `running` or `exited`
 ///
 /// Returns `invalid-unit-path` if the path is invalid
 ///
@@ -114,36 +304,325 @@ pub async fn get_active_state(connection: &Connection, full_service_name: &str)
 /// * `connection`: zbus connection
 /// * `full_service_name`: Full name of the service name with '.service' in the end
 ///
-pub async fn get_unit_file_state(connection: &Connection, full_service_name: &str) -> String {
+pub async fn get_sub_state(
+    connection: &Connection,
+    full_service_name: &str,
+) -> std::result::Result<String, ServicerError> {
     let object_path = get_unit_path(full_service_name);
 
     match zvariant::ObjectPath::try_from(object_path) {
         Ok(path) => {
-            let unit_proxy = UnitProxy::new(connection, path).await.unwrap();
-            unit_proxy
-                .unit_file_state()
+            let unit_proxy = UnitProxy::new(connection, path).await?;
+            Ok(unit_proxy
+                .sub_state()
                 .await
-                .unwrap_or("invalid-unit-path".into())
+                .unwrap_or("invalid-unit-path".into()))
         }
-        Err(_) => "invalid-unit-path".to_string(),
+        Err(_) => Ok("invalid-unit-path".to_string()),
     }
 }
 
-/// Returns the PID of a systemd service
+/// Returns the unit file state of a systemd unit. If the state is `enabled`, the unit loads on every boot
+///
+/// Returns `invalid-unit-path` if the path is invalid
 ///
 /// # Arguments
 ///
 /// * `connection`: zbus connection
 /// * `full_service_name`: Full name of the service name with '.service' in the end
 ///
-pub async fn get_main_pid(connection: &Connection, full_service_name: &str) -> Result<u32> {
+pub async fn get_unit_file_state(
+    connection: &Connection,
+    full_service_name: &str,
+) -> std::result::Result<String, ServicerError> {
     let object_path = get_unit_path(full_service_name);
 
-    let validated_object_path = zvariant::ObjectPath::try_from(object_path)?;
+    match zvariant::ObjectPath::try_from(object_path) {
+        Ok(path) => {
+            let unit_proxy = UnitProxy::new(connection, path).await?;
+            Ok(unit_proxy
+                .unit_file_state()
+                .await
+                .unwrap_or("invalid-unit-path".into()))
+        }
+        Err(_) => Ok("invalid-unit-path".to_string()),
+    }
+}
+
+/// State of a servicer unit, collected in bulk by `get_servicer_unit_statuses()`
+pub struct UnitStatus {
+    /// Full name of the service, having '.ser.service' at the end
+    pub full_service_name: String,
+
+    /// Active state, eg. `active`. Units which systemd has not loaded are `inactive`
+    pub active_state: String,
 
-    let service_proxy = ServiceProxy::new(connection, validated_object_path).await?;
+    /// Unit file state. `enabled` units start on boot
+    pub unit_file_state: String,
 
-    service_proxy.main_pid().await
+    /// PID of the main process. 0 if the service is not running
+    pub main_pid: u32,
+
+    /// Control group of the service, eg. `/system.slice/hello.ser.service`. Empty if the
+    /// service is not running
+    pub control_group: String,
+
+    /// `NRestarts`, the number of automatic restarts since the unit was loaded
+    pub n_restarts: u32,
+
+    /// `MemoryCurrent` in bytes. `None` if memory accounting is off
+    pub memory_current: Option<u64>,
+
+    /// `TasksCurrent`. `None` if task accounting is off
+    pub tasks_current: Option<u64>,
+
+    /// `IOReadBytes`. `None` if IO accounting is off
+    pub io_read_bytes: Option<u64>,
+
+    /// `IOWriteBytes`. `None` if IO accounting is off
+    pub io_write_bytes: Option<u64>,
+
+    /// `NeedDaemonReload`, set if the .service file changed on disk since systemd loaded it
+    pub need_daemon_reload: bool,
+}
+
+/// Collects the state of every `.ser.service` unit with a constant number of round trips.
+///
+/// One `ListUnitFilesByPatterns` call finds every unit file and its enablement, one
+/// `ListUnitsByPatterns` call returns the active state of loaded units, then a
+/// `Properties.GetAll` request per loaded unit is sent concurrently to read `NeedDaemonReload`
+/// and, for running services, the main PID, control group and resource accounting.
+/// Proxies are built without property caching, which would otherwise add match rules and
+/// extra calls per unit.
+///
+/// # Arguments
+///
+/// * `connection`: zbus connection
+///
+pub async fn get_servicer_unit_statuses(connection: &Connection) -> Result<Vec<UnitStatus>> {
+    let manager_proxy = ManagerProxy::builder(connection)
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+
+    collect_unit_statuses(&manager_proxy).await
+}
+
+/// A unit as returned by `ListUnitsByPatterns`: name, description, load state, active state,
+/// sub state, followed unit, object path, job id, job type and job path
+type ListedUnit = (
+    String,
+    String,
+    String,
+    String,
+    String,
+    String,
+    zvariant::OwnedObjectPath,
+    u32,
+    String,
+    zvariant::OwnedObjectPath,
+);
+
+/// The D-Bus calls made to collect statuses, so tests can count them without a bus
+trait UnitStatusSource {
+    /// `ListUnitFilesByPatterns`, unit file paths with their enablement
+    async fn list_unit_files(&self, patterns: Vec<String>) -> Result<Vec<(String, String)>>;
+
+    /// `ListUnitsByPatterns`, units systemd has loaded
+    async fn list_units(&self, patterns: Vec<String>) -> Result<Vec<ListedUnit>>;
+
+    /// `Properties.GetAll` on every interface of a unit
+    async fn get_unit_properties(
+        &self,
+        unit_path: zvariant::OwnedObjectPath,
+    ) -> Result<HashMap<String, zvariant::OwnedValue>>;
+}
+
+impl UnitStatusSource for ManagerProxy<'_> {
+    async fn list_unit_files(&self, patterns: Vec<String>) -> Result<Vec<(String, String)>> {
+        self.list_unit_files_by_patterns(vec![], patterns).await
+    }
+
+    async fn list_units(&self, patterns: Vec<String>) -> Result<Vec<ListedUnit>> {
+        self.list_units_by_patterns(vec![], patterns).await
+    }
+
+    async fn get_unit_properties(
+        &self,
+        unit_path: zvariant::OwnedObjectPath,
+    ) -> Result<HashMap<String, zvariant::OwnedValue>> {
+        get_unit_properties(self.inner().connection(), unit_path).await
+    }
+}
+
+/// Collects statuses with two list calls, sent together, followed by one call per loaded unit,
+/// also sent together
+///
+/// # Arguments
+///
+/// * `source`: Where the calls go, the systemd manager outside of tests
+///
+async fn collect_unit_statuses(source: &impl UnitStatusSource) -> Result<Vec<UnitStatus>> {
+    let patterns = vec!["*.ser.service".to_string()];
+
+    let (unit_files, units) = futures_util::future::try_join(
+        source.list_unit_files(patterns.clone()),
+        source.list_units(patterns),
+    )
+    .await?;
+
+    let mut statuses: Vec<UnitStatus> = vec![];
+    let mut unit_paths = vec![];
+
+    for (path, unit_file_state) in unit_files {
+        let Some(full_service_name) = path.rsplit('/').next() else {
+            continue;
+        };
+
+        // The same unit may be installed in several unit paths. systemd uses the first one.
+        if statuses
+            .iter()
+            .any(|status| status.full_service_name == full_service_name)
+        {
+            continue;
+        }
+
+        let loaded = units.iter().find(|unit| unit.0 == full_service_name);
+
+        statuses.push(UnitStatus {
+            full_service_name: full_service_name.to_string(),
+            active_state: loaded.map_or("inactive".to_string(), |unit| unit.3.clone()),
+            unit_file_state,
+            main_pid: 0,
+            control_group: String::new(),
+            n_restarts: 0,
+            memory_current: None,
+            tasks_current: None,
+            io_read_bytes: None,
+            io_write_bytes: None,
+            need_daemon_reload: false,
+        });
+        unit_paths.push(loaded.map(|unit| unit.6.clone()));
+    }
+
+    let unit_properties =
+        futures_util::future::join_all(unit_paths.iter().map(|unit_path| async move {
+            match unit_path {
+                Some(path) => source.get_unit_properties(path.clone()).await.ok(),
+                None => None,
+            }
+        }))
+        .await;
+
+    for (status, properties) in statuses.iter_mut().zip(unit_properties) {
+        let Some(properties) = properties else {
+            continue;
+        };
+
+        status.need_daemon_reload = properties
+            .get("NeedDaemonReload")
+            .cloned()
+            .and_then(|need_daemon_reload| bool::try_from(need_daemon_reload).ok())
+            .unwrap_or(false);
+
+        // Stopped services keep the numbers of their last run
+        if status.active_state == "inactive" {
+            continue;
+        }
+
+        status.main_pid = properties
+            .get("MainPID")
+            .cloned()
+            .and_then(|pid| u32::try_from(pid).ok())
+            .unwrap_or(0);
+        status.control_group = properties
+            .get("ControlGroup")
+            .cloned()
+            .and_then(|control_group| String::try_from(control_group).ok())
+            .unwrap_or_default();
+        status.n_restarts = properties
+            .get("NRestarts")
+            .cloned()
+            .and_then(|restarts| u32::try_from(restarts).ok())
+            .unwrap_or(0);
+        status.memory_current = get_accounting_property(&properties, "MemoryCurrent");
+        status.tasks_current = get_accounting_property(&properties, "TasksCurrent");
+        status.io_read_bytes = get_accounting_property(&properties, "IOReadBytes");
+        status.io_write_bytes = get_accounting_property(&properties, "IOWriteBytes");
+    }
+
+    Ok(statuses)
+}
+
+/// Reads the properties of every interface of a unit with one `Properties.GetAll` call, eg.
+/// `NeedDaemonReload` of `org.freedesktop.systemd1.Unit` and `MainPID` of
+/// `org.freedesktop.systemd1.Service`. systemd returns every interface for an empty interface
+/// name, which `PropertiesProxy` can't send.
+///
+/// # Arguments
+///
+/// * `connection`: zbus connection
+/// * `unit_path`: Object path of the unit
+///
+async fn get_unit_properties(
+    connection: &Connection,
+    unit_path: zvariant::OwnedObjectPath,
+) -> Result<HashMap<String, zvariant::OwnedValue>> {
+    let reply = connection
+        .call_method(
+            Some("org.freedesktop.systemd1"),
+            unit_path,
+            Some("org.freedesktop.DBus.Properties"),
+            "GetAll",
+            &("",),
+        )
+        .await?;
+
+    reply.body().deserialize()
+}
+
+/// Get every property of a unit on one D-Bus interface in a single call
+///
+/// # Arguments
+///
+/// * `connection`: zbus connection
+/// * `unit_path`: Object path of the unit
+/// * `interface`: Interface name, eg. `org.freedesktop.systemd1.Unit`
+///
+pub async fn get_all_properties(
+    connection: &Connection,
+    unit_path: zvariant::OwnedObjectPath,
+    interface: &'static str,
+) -> Result<HashMap<String, zvariant::OwnedValue>> {
+    let properties_proxy = PropertiesProxy::builder(connection)
+        .destination("org.freedesktop.systemd1")?
+        .path(unit_path)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+
+    let interface = InterfaceName::from_static_str(interface)?;
+
+    Ok(properties_proxy.get_all(interface).await?)
+}
+
+/// Reads a resource accounting property such as `MemoryCurrent`. systemd reports `u64::MAX` if
+/// accounting is turned off for the unit.
+///
+/// # Arguments
+///
+/// * `properties`: Properties returned by `get_unit_properties()`
+/// * `name`: Property name
+///
+fn get_accounting_property(
+    properties: &HashMap<String, zvariant::OwnedValue>,
+    name: &str,
+) -> Option<u64> {
+    properties
+        .get(name)
+        .cloned()
+        .and_then(|value| u64::try_from(value).ok())
+        .filter(|value| *value != u64::MAX)
 }
 
 /// Encode into a valid dbus string
@@ -177,3 +656,214 @@ pub fn get_unit_path(full_service_name: &str) -> String {
         encode_as_dbus_object_path(full_service_name)
     )
 }
+
+/// Control group of a unit in a slice, eg. `/system.slice/hello.ser.service`. The path of a
+/// slice follows from its name: `a-b.slice` is in `/a.slice/a-b.slice`.
+///
+/// # Arguments
+///
+/// * `manager_control_group` - Control group of the systemd manager, empty for the system
+///   manager and eg. `/user.slice/user-1000.slice/user@1000.service` for a user manager
+/// * `slice` - The slice of the unit, eg. `system.slice` or `app.slice`
+/// * `unit_name` - Full name of the unit
+///
+pub fn get_control_group(manager_control_group: &str, slice: &str, unit_name: &str) -> String {
+    let mut control_group = manager_control_group.trim_end_matches('/').to_string();
+
+    // The root slice `-.slice` has no directory of its own
+    if let Some(slice_name) = slice.strip_suffix(".slice").filter(|name| *name != "-") {
+        let mut prefix = String::new();
+
+        for part in slice_name.split('-') {
+            if !prefix.is_empty() {
+                prefix.push('-');
+            }
+            prefix.push_str(part);
+            control_group.push_str(&format!("/{prefix}.slice"));
+        }
+    }
+
+    format!("{control_group}/{unit_name}")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use super::*;
+
+    /// Simulated latency of one D-Bus round trip
+    const ROUND_TRIP: Duration = Duration::from_millis(10);
+
+    /// A systemd manager with `count` running servicer units which counts the calls it gets
+    #[derive(Default)]
+    struct MockManager {
+        count: usize,
+        list_calls: AtomicUsize,
+        get_all_calls: AtomicUsize,
+    }
+
+    impl MockManager {
+        fn new(count: usize) -> Self {
+            MockManager {
+                count,
+                ..Default::default()
+            }
+        }
+
+        fn unit_path(index: usize) -> zvariant::OwnedObjectPath {
+            zvariant::OwnedObjectPath::try_from(get_unit_path(&format!("app-{index}.ser.service")))
+                .unwrap()
+        }
+    }
+
+    impl UnitStatusSource for MockManager {
+        async fn list_unit_files(&self, _patterns: Vec<String>) -> Result<Vec<(String, String)>> {
+            self.list_calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(ROUND_TRIP).await;
+
+            Ok((0..self.count)
+                .map(|index| {
+                    (
+                        format!("/etc/systemd/system/app-{index}.ser.service"),
+                        "enabled".to_string(),
+                    )
+                })
+                .collect())
+        }
+
+        async fn list_units(&self, _patterns: Vec<String>) -> Result<Vec<ListedUnit>> {
+            self.list_calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(ROUND_TRIP).await;
+
+            Ok((0..self.count)
+                .map(|index| {
+                    (
+                        format!("app-{index}.ser.service"),
+                        String::new(),
+                        "loaded".to_string(),
+                        "active".to_string(),
+                        "running".to_string(),
+                        String::new(),
+                        Self::unit_path(index),
+                        0,
+                        String::new(),
+                        zvariant::OwnedObjectPath::try_from("/").unwrap(),
+                    )
+                })
+                .collect())
+        }
+
+        async fn get_unit_properties(
+            &self,
+            unit_path: zvariant::OwnedObjectPath,
+        ) -> Result<HashMap<String, zvariant::OwnedValue>> {
+            self.get_all_calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(ROUND_TRIP).await;
+
+            let index = (0..self.count)
+                .find(|index| Self::unit_path(*index) == unit_path)
+                .unwrap();
+
+            Ok(HashMap::from([
+                (
+                    "MainPID".to_string(),
+                    zvariant::OwnedValue::from(1000 + index as u32),
+                ),
+                (
+                    "MemoryCurrent".to_string(),
+                    zvariant::OwnedValue::from(u64::MAX),
+                ),
+                (
+                    "NeedDaemonReload".to_string(),
+                    zvariant::OwnedValue::from(index == 1),
+                ),
+            ]))
+        }
+    }
+
+    /// Collects statuses from a mock manager, returning the statuses and the simulated time taken
+    async fn collect(manager: &MockManager) -> (Vec<UnitStatus>, Duration) {
+        let started = tokio::time::Instant::now();
+        let statuses = collect_unit_statuses(manager).await.unwrap();
+        (statuses, started.elapsed())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn round_trips_stay_the_same_as_services_grow() {
+        let one = MockManager::new(1);
+        let fifty = MockManager::new(50);
+
+        let (one_statuses, one_elapsed) = collect(&one).await;
+        let (fifty_statuses, fifty_elapsed) = collect(&fifty).await;
+
+        assert_eq!(one_statuses.len(), 1);
+        assert_eq!(fifty_statuses.len(), 50);
+
+        // The list calls run together, then every per-unit call runs together
+        assert_eq!(one_elapsed, ROUND_TRIP * 2);
+        assert_eq!(fifty_elapsed, one_elapsed);
+
+        // Two list calls whatever the number of services
+        assert_eq!(one.list_calls.load(Ordering::SeqCst), 2);
+        assert_eq!(fifty.list_calls.load(Ordering::SeqCst), 2);
+
+        // One GetAll per loaded unit, no call per property
+        assert_eq!(fifty.get_all_calls.load(Ordering::SeqCst), 50);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn statuses_are_read_from_the_calls() {
+        let manager = MockManager::new(3);
+        let (statuses, _) = collect(&manager).await;
+
+        let status = &statuses[2];
+        assert_eq!(status.full_service_name, "app-2.ser.service");
+        assert_eq!(status.active_state, "active");
+        assert_eq!(status.unit_file_state, "enabled");
+        assert_eq!(status.main_pid, 1002);
+
+        // u64::MAX means accounting is off
+        assert_eq!(status.memory_current, None);
+
+        assert!(!status.need_daemon_reload);
+        assert!(statuses[1].need_daemon_reload);
+    }
+
+    #[test]
+    fn activating_services_are_running() {
+        for state in ["active", "reloading", "activating"] {
+            assert!(is_running(state), "{state}");
+        }
+        for state in ["inactive", "failed", "deactivating", "invalid-unit-path"] {
+            assert!(!is_running(state), "{state}");
+        }
+    }
+
+    #[test]
+    fn builds_control_groups_of_slices() {
+        assert_eq!(
+            get_control_group("", "system.slice", "hello.ser.service"),
+            "/system.slice/hello.ser.service"
+        );
+        assert_eq!(
+            get_control_group("/", "-.slice", "hello.ser.service"),
+            "/hello.ser.service"
+        );
+        assert_eq!(
+            get_control_group("", "apps-web-api.slice", "api.ser.service"),
+            "/apps.slice/apps-web.slice/apps-web-api.slice/api.ser.service"
+        );
+        assert_eq!(
+            get_control_group(
+                "/user.slice/user-1000.slice/user@1000.service",
+                "app.slice",
+                "hello.ser.service"
+            ),
+            "/user.slice/user-1000.slice/user@1000.service/app.slice/hello.ser.service"
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    error::ServicerError,
+    scope::get_state_directory,
+    service_names::{get_drop_in_directory, get_full_service_name, get_service_file_path},
+};
+
+/// Name of the metadata file in a trash entry
+const METADATA_FILE: &str = "metadata.json";
+
+/// State of a service when it was deleted, used by `ser undelete` to restore it
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TrashMetadata {
+    /// The service name without '.ser.service'
+    pub name: String,
+
+    /// When the service was deleted, in RFC 3339 format
+    pub deleted_at: String,
+
+    /// Whether the service started on boot
+    pub enabled: bool,
+
+    /// Whether the service was running
+    pub active: bool,
+}
+
+/// A deleted service in the trash
+pub struct TrashEntry {
+    /// Directory of the entry, eg. `/var/lib/servicer/trash/hello-world.1714568400`
+    pub path: PathBuf,
+
+    pub metadata: TrashMetadata,
+}
+
+/// Directory holding deleted services, one subdirectory per deletion. `/var/lib/servicer/trash`,
+/// or `~/.local/state/servicer/trash` for user services
+fn get_trash_directory() -> PathBuf {
+    get_state_directory().join("trash")
+}
+
+/// Moves the .service file of a service and its drop-in directory, if any, to a new trash
+/// entry. Returns the directory of the entry.
+///
+/// # Arguments
+///
+/// * `name` - The service name without '.ser.service'
+/// * `enabled` - Whether the service started on boot
+/// * `active` - Whether the service was running
+///
+pub fn move_to_trash(name: &str, enabled: bool, active: bool) -> Result<PathBuf, ServicerError> {
+    let full_service_name = get_full_service_name(name);
+    let service_file_path = get_service_file_path(&full_service_name)?;
+    let drop_in_directory = get_drop_in_directory(&full_service_name)?;
+    let now = chrono::Local::now();
+
+    let metadata = TrashMetadata {
+        name: name.to_string(),
+        deleted_at: now.to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        enabled,
+        active,
+    };
+
+    // Several deletions of a service within a second get a counter
+    let mut entry_path = get_trash_directory().join(format!("{name}.{}", now.timestamp()));
+    let mut counter = 1;
+    while entry_path.exists() {
+        entry_path = get_trash_directory().join(format!("{name}.{}-{counter}", now.timestamp()));
+        counter += 1;
+    }
+    std::fs::create_dir_all(&entry_path)?;
+
+    std::fs::write(
+        entry_path.join(METADATA_FILE),
+        serde_json::to_string_pretty(&metadata).map_err(std::io::Error::other)?,
+    )?;
+
+    move_path(&service_file_path, &entry_path.join(&full_service_name))?;
+
+    if drop_in_directory.is_dir() {
+        move_path(
+            &drop_in_directory,
+            &entry_path.join(drop_in_directory.file_name().unwrap_or_default()),
+        )?;
+    }
+
+    Ok(entry_path)
+}
+
+/// Lists the deleted services in the trash, oldest first
+pub fn list_trash() -> Result<Vec<TrashEntry>, ServicerError> {
+    let trash_directory = get_trash_directory();
+    if !trash_directory.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut entries = vec![];
+
+    for entry in std::fs::read_dir(trash_directory)? {
+        let path = entry?.path();
+
+        let Ok(metadata) = std::fs::read_to_string(path.join(METADATA_FILE)) else {
+            continue;
+        };
+        if let Ok(metadata) = serde_json::from_str::<TrashMetadata>(&metadata) {
+            entries.push(TrashEntry { path, metadata });
+        }
+    }
+
+    // `deleted_at` has second precision and a local offset, the directory name has the Unix
+    // time and the counter of deletions within a second
+    entries.sort_by_key(|entry| get_entry_order(&entry.path));
+
+    Ok(entries)
+}
+
+/// Returns the Unix time of the deletion and its counter from the directory of a trash entry,
+/// eg. `(1714568400, 2)` for `hello-world.1714568400-2`. `None` if the name has another form.
+///
+/// # Arguments
+///
+/// * `path` - Directory of the entry
+///
+fn get_entry_order(path: &Path) -> Option<(i64, u32)> {
+    let file_name = path.file_name()?.to_str()?;
+    let (_, suffix) = file_name.rsplit_once('.')?;
+
+    match suffix.split_once('-') {
+        Some((timestamp, counter)) => Some((timestamp.parse().ok()?, counter.parse().ok()?)),
+        None => Some((suffix.parse().ok()?, 0)),
+    }
+}
+
+/// Moves the .service file and drop-ins of a trash entry back to the unit directory and removes
+/// the entry
+///
+/// # Arguments
+///
+/// * `entry` - The trash entry
+///
+pub fn restore_from_trash(entry: &TrashEntry) -> Result<(), ServicerError> {
+    let full_service_name = get_full_service_name(&entry.metadata.name);
+    let drop_in_directory = get_drop_in_directory(&full_service_name)?;
+    let trashed_drop_ins = entry
+        .path
+        .join(drop_in_directory.file_name().unwrap_or_default());
+
+    // Merged into drop-ins created since the deletion, if any
+    if trashed_drop_ins.is_dir() {
+        move_path(&trashed_drop_ins, &drop_in_directory)?;
+    }
+
+    move_path(
+        &entry.path.join(&full_service_name),
+        &get_service_file_path(&full_service_name)?,
+    )?;
+
+    std::fs::remove_dir_all(&entry.path)?;
+
+    Ok(())
+}
+
+/// Moves a file or directory. Falls back to copying and removing it since `/var/lib` may be on
+/// a different file system than `/etc`.
+///
+/// # Arguments
+///
+/// * `from`
+/// * `to`
+///
+fn move_path(from: &Path, to: &Path) -> std::io::Result<()> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    copy_path(from, to)?;
+
+    if from.is_dir() {
+        std::fs::remove_dir_all(from)
+    } else {
+        std::fs::remove_file(from)
+    }
+}
+
+/// Copies a file, or a directory with its contents
+///
+/// # Arguments
+///
+/// * `from`
+/// * `to`
+///
+fn copy_path(from: &Path, to: &Path) -> std::io::Result<()> {
+    if !from.is_dir() {
+        return std::fs::copy(from, to).map(|_| ());
+    }
+
+    std::fs::create_dir_all(to)?;
+
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        copy_path(&entry.path(), &to.join(entry.file_name()))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orders_entries_by_time_and_counter() {
+        let order = |file_name: &str| {
+            get_entry_order(
+                Path::new("/var/lib/servicer/trash")
+                    .join(file_name)
+                    .as_path(),
+            )
+        };
+
+        assert_eq!(order("hello-world.1714568400"), Some((1714568400, 0)));
+        assert_eq!(order("hello-world.1714568400-2"), Some((1714568400, 2)));
+        assert_eq!(order("api.v2.1714568400-10"), Some((1714568400, 10)));
+        assert_eq!(order("hello-world"), None);
+        assert_eq!(order("hello-world.x-1"), None);
+
+        let mut names = [
+            "a.1714568401",
+            "a.1714568400-10",
+            "a.1714568400-2",
+            "a.1714568400",
+        ];
+        names.sort_by_key(|name| order(name));
+        assert_eq!(
+            names,
+            [
+                "a.1714568400",
+                "a.1714568400-2",
+                "a.1714568400-10",
+                "a.1714568401"
+            ]
+        );
+    }
+}
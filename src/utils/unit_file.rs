@@ -0,0 +1,795 @@
+use std::{fmt, str::FromStr};
+
+use super::import::split_first_word;
+
+/// A systemd unit file which can be parsed and written back without losing comments, key order,
+/// repeated keys or line continuations.
+///
+/// The file is stored line by line. Untouched lines are written back byte for byte, only entries
+/// changed with [`UnitFile::set`] or [`UnitFile::add`] are re-serialized as `Key=value`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UnitFile {
+    /// Sections in file order. The first section has an empty name and holds lines before the
+    /// first `[Section]` header, eg. a `# Generated with Servicer` comment.
+    sections: Vec<Section>,
+}
+
+/// A `[Section]` of a unit file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    /// Section name without brackets, eg. `Service`
+    pub name: String,
+
+    /// 1-based line number of the header. `None` for the leading section and sections created
+    /// after parsing.
+    pub line_number: Option<usize>,
+
+    /// The raw header line including the line ending. Empty for the leading section.
+    header: String,
+
+    /// Comments, blank lines and entries in file order
+    lines: Vec<Line>,
+}
+
+/// A line inside a section
+#[derive(Debug, Clone, PartialEq)]
+enum Line {
+    /// A blank line or a comment starting with `#` or `;`, kept verbatim
+    Other(String),
+
+    /// A `Key=value` entry
+    Entry(Entry),
+}
+
+/// A `Key=value` entry which may span several lines with trailing backslashes
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    /// The key, eg. `ExecStart`
+    pub key: String,
+
+    /// The value with continuations joined by a space
+    pub value: String,
+
+    /// 1-based line number of the key. `None` if the entry was created or modified after
+    /// parsing.
+    pub line_number: Option<usize>,
+
+    /// The original text including continuation lines and line endings. `None` if the entry was
+    /// created or modified after parsing.
+    raw: Option<String>,
+}
+
+/// Error returned when a unit file can't be parsed
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// 1-based line number
+    pub line_number: usize,
+
+    /// What went wrong
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line_number, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl FromStr for UnitFile {
+    type Err = ParseError;
+
+    fn from_str(contents: &str) -> Result<Self, Self::Err> {
+        let mut sections = vec![Section::new("", "")];
+        let mut raw_lines = contents.split_inclusive('\n').enumerate();
+
+        while let Some((index, raw_line)) = raw_lines.next() {
+            let line_number = index + 1;
+            let trimmed = raw_line.trim();
+            let current = sections.last_mut().unwrap();
+
+            if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with(';') {
+                current.lines.push(Line::Other(raw_line.to_string()));
+            } else if trimmed.starts_with('[') {
+                if !trimmed.ends_with(']') || trimmed.len() < 3 {
+                    return Err(ParseError {
+                        line_number,
+                        message: format!("invalid section header {trimmed}"),
+                    });
+                }
+
+                let mut section = Section::new(&trimmed[1..trimmed.len() - 1], raw_line);
+                section.line_number = Some(line_number);
+                sections.push(section);
+            } else {
+                let Some((key, value)) = trimmed.split_once('=') else {
+                    return Err(ParseError {
+                        line_number,
+                        message: format!("expected Key=value, found {trimmed}"),
+                    });
+                };
+
+                if current.name.is_empty() {
+                    return Err(ParseError {
+                        line_number,
+                        message: format!("{} is outside of a section", key.trim()),
+                    });
+                }
+
+                let mut raw = raw_line.to_string();
+                let mut value = value.trim().to_string();
+
+                // A trailing backslash joins the next line with a space. Comments inside a
+                // continued value are skipped by systemd.
+                while let Some(stripped) = value.strip_suffix('\\') {
+                    value = stripped.trim_end().to_string();
+
+                    let Some((_, next_line)) = raw_lines.next() else {
+                        break;
+                    };
+                    raw.push_str(next_line);
+
+                    let next_trimmed = next_line.trim();
+                    if next_trimmed.starts_with('#') || next_trimmed.starts_with(';') {
+                        value.push('\\');
+                    } else if !next_trimmed.is_empty() {
+                        value = format!("{value} {next_trimmed}").trim_start().to_string();
+                    }
+                }
+
+                current.lines.push(Line::Entry(Entry {
+                    key: key.trim().to_string(),
+                    value,
+                    line_number: Some(line_number),
+                    raw: Some(raw),
+                }));
+            }
+        }
+
+        Ok(UnitFile { sections })
+    }
+}
+
+impl fmt::Display for UnitFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The last parsed line may lack a line ending. Lines added after it need one.
+        let mut at_line_start = true;
+        let mut write_raw = |f: &mut fmt::Formatter<'_>, raw: &str| -> fmt::Result {
+            if raw.is_empty() {
+                return Ok(());
+            }
+            if !at_line_start {
+                f.write_str("\n")?;
+            }
+            at_line_start = raw.ends_with('\n');
+            f.write_str(raw)
+        };
+
+        for section in &self.sections {
+            write_raw(f, &section.header)?;
+
+            for line in &section.lines {
+                match line {
+                    Line::Other(raw) => write_raw(f, raw)?,
+                    Line::Entry(Entry { raw: Some(raw), .. }) => write_raw(f, raw)?,
+                    Line::Entry(entry) => {
+                        write_raw(f, &format!("{}={}\n", entry.key, entry.value))?
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Entry {
+    /// An entry created after parsing, written as `Key=value`
+    fn new(key: &str, value: &str) -> Self {
+        Entry {
+            key: key.to_string(),
+            value: value.to_string(),
+            line_number: None,
+            raw: None,
+        }
+    }
+}
+
+impl Section {
+    fn new(name: &str, header: &str) -> Self {
+        Section {
+            name: name.to_string(),
+            line_number: None,
+            header: header.to_string(),
+            lines: vec![],
+        }
+    }
+
+    /// Entries of the section in file order
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.lines.iter().filter_map(|line| match line {
+            Line::Entry(entry) => Some(entry),
+            Line::Other(_) => None,
+        })
+    }
+
+    /// Index after the last entry, so new entries are grouped with existing ones instead of
+    /// trailing blank lines
+    fn insert_position(&self) -> usize {
+        self.lines
+            .iter()
+            .rposition(|line| matches!(line, Line::Entry(_)))
+            .map(|index| index + 1)
+            .unwrap_or(self.lines.len())
+    }
+}
+
+impl UnitFile {
+    /// Sections in file order, including the leading section without a name
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    /// Returns the last value of a key, which is the one systemd uses for single-valued keys
+    ///
+    /// # Arguments
+    ///
+    /// * `section` - Section name, eg. `Service`
+    /// * `key` - Key name, eg. `ExecStart`
+    ///
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.get_all(section, key).last().copied()
+    }
+
+    /// Returns every value of a key in file order. Used for list keys like `Environment=`.
+    ///
+    /// An empty assignment resets the list in systemd, so values before it are dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `section` - Section name, eg. `Service`
+    /// * `key` - Key name, eg. `Environment`
+    ///
+    pub fn get_all(&self, section: &str, key: &str) -> Vec<&str> {
+        let mut values = vec![];
+
+        for entry in self
+            .sections
+            .iter()
+            .filter(|s| s.name == section)
+            .flat_map(|s| s.entries())
+            .filter(|entry| entry.key == key)
+        {
+            if entry.value.is_empty() {
+                values.clear();
+            } else {
+                values.push(entry.value.as_str());
+            }
+        }
+
+        values
+    }
+
+    /// Sets a single-valued key. The first existing entry is updated in place and later duplicates
+    /// are removed. A missing key is appended to the section, creating the section if needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `section` - Section name, eg. `Service`
+    /// * `key` - Key name, eg. `User`
+    /// * `value` - The new value
+    ///
+    pub fn set(&mut self, section: &str, key: &str, value: &str) {
+        let mut updated = false;
+
+        for s in self.sections.iter_mut().filter(|s| s.name == section) {
+            s.lines.retain_mut(|line| match line {
+                Line::Entry(entry) if entry.key == key => {
+                    if updated {
+                        return false;
+                    }
+
+                    if entry.value != value {
+                        entry.value = value.to_string();
+                        entry.line_number = None;
+                        entry.raw = None;
+                    }
+                    updated = true;
+                    true
+                }
+                _ => true,
+            });
+        }
+
+        if !updated {
+            self.add(section, key, value);
+        }
+    }
+
+    /// Replaces every value of a list key such as `Environment=`, `ExecStart=` or `WantedBy=`.
+    /// Nothing changes if the key already has these values, so resets with an empty value are
+    /// kept. Otherwise existing entries which are kept stay at their position and the order of
+    /// `values` is preserved.
+    ///
+    /// # Arguments
+    ///
+    /// * `section` - Section name, eg. `Service`
+    /// * `key` - Key name, eg. `Environment`
+    /// * `values` - The new values
+    ///
+    pub fn set_all(&mut self, section: &str, key: &str, values: &[String]) {
+        if self.get_all(section, key) == values {
+            return;
+        }
+
+        // Index of the next value to place
+        let mut next = 0;
+
+        for s in self.sections.iter_mut().filter(|s| s.name == section) {
+            let mut index = 0;
+
+            while index < s.lines.len() {
+                let Line::Entry(entry) = &s.lines[index] else {
+                    index += 1;
+                    continue;
+                };
+                if entry.key != key {
+                    index += 1;
+                    continue;
+                }
+
+                match values[next..]
+                    .iter()
+                    .position(|value| *value == entry.value)
+                {
+                    Some(position) => {
+                        // Values missing before a kept entry go right before it
+                        for value in &values[next..next + position] {
+                            s.lines.insert(index, Line::Entry(Entry::new(key, value)));
+                            index += 1;
+                        }
+                        next += position + 1;
+                        index += 1;
+                    }
+                    None => {
+                        s.lines.remove(index);
+                    }
+                }
+            }
+        }
+
+        for value in &values[next..] {
+            self.add(section, key, value);
+        }
+    }
+
+    /// Changes every entry of a key having a value, keeping its position. Other entries of the
+    /// key, including resets with an empty value, are left alone.
+    ///
+    /// # Arguments
+    ///
+    /// * `section` - Section name, eg. `Service`
+    /// * `key` - Key name, eg. `ExecStart`
+    /// * `value` - The value to change
+    /// * `replacement` - The new value
+    ///
+    pub fn replace_value(&mut self, section: &str, key: &str, value: &str, replacement: &str) {
+        for s in self.sections.iter_mut().filter(|s| s.name == section) {
+            for line in s.lines.iter_mut() {
+                if let Line::Entry(entry) = line {
+                    if entry.key == key && entry.value == value {
+                        *entry = Entry::new(key, replacement);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Appends an entry to a section without touching existing entries with the same key.
+    ///
+    /// # Arguments
+    ///
+    /// * `section` - Section name, eg. `Service`
+    /// * `key` - Key name, eg. `Environment`
+    /// * `value` - The value
+    ///
+    pub fn add(&mut self, section: &str, key: &str, value: &str) {
+        let entry = Line::Entry(Entry::new(key, value));
+
+        match self.sections.iter_mut().rfind(|s| s.name == section) {
+            Some(s) => {
+                let position = s.insert_position();
+                s.lines.insert(position, entry);
+            }
+            None => {
+                // Separate the new section from the previous one by a blank line
+                if let Some(last) = self.sections.last_mut() {
+                    let ends_with_blank = matches!(
+                        last.lines.last(),
+                        Some(Line::Other(raw)) if raw.trim().is_empty()
+                    );
+
+                    if !ends_with_blank && (last.lines.iter().any(|l| matches!(l, Line::Entry(_))))
+                    {
+                        last.lines.push(Line::Other("\n".to_string()));
+                    }
+                }
+
+                let mut new_section = Section::new(section, &format!("[{section}]\n"));
+                new_section.lines.push(entry);
+                self.sections.push(new_section);
+            }
+        }
+    }
+
+    /// Removes every entry of a key
+    ///
+    /// # Arguments
+    ///
+    /// * `section` - Section name, eg. `Service`
+    /// * `key` - Key name, eg. `Restart`
+    ///
+    pub fn remove(&mut self, section: &str, key: &str) {
+        for s in self.sections.iter_mut().filter(|s| s.name == section) {
+            s.lines
+                .retain(|line| !matches!(line, Line::Entry(entry) if entry.key == key));
+        }
+    }
+}
+
+/// Quotes a word of an `ExecStart=` command line or an `Environment=` assignment so systemd reads
+/// it back unchanged. Words with whitespace, quotes or backslashes are wrapped in double quotes
+/// and `%` is escaped since systemd expands it as a specifier. `$VAR` is left alone so it still
+/// expands in `ExecStart=`.
+///
+/// # Arguments
+///
+/// * `word` - The word, eg. `--name=hello world`
+///
+pub fn quote_word(word: &str) -> String {
+    let escaped = word.replace('%', "%%");
+
+    if !word.is_empty()
+        && !word
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\'))
+    {
+        return escaped;
+    }
+
+    format!("\"{}\"", escaped.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Prefixes of `ExecStart=` changing how systemd runs the program, eg. `-` to ignore failures
+const EXEC_PREFIXES: &[char] = &['-', '@', ':', '+', '!'];
+
+/// Splits an `ExecStart=` value into its prefixes, the program and the rest of the line as
+/// written. The program is unquoted and `%%` unescaped, undoing `quote_word()`. `None` for an
+/// empty value, which resets the commands.
+///
+/// # Arguments
+///
+/// * `value` - Eg. `-"/opt/my app/run" --port 80`
+///
+pub fn split_exec_start(value: &str) -> Option<(&str, String, &str)> {
+    let command = value.trim_start_matches(EXEC_PREFIXES);
+    let prefix = &value[..value.len() - command.len()];
+    let (program, args) = split_first_word(command)?;
+
+    Some((prefix, program.replace("%%", "%"), args))
+}
+
+/// Typed view of the keys servicer reads and writes. Keys without a field here are still kept by
+/// the underlying [`UnitFile`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ServiceUnit {
+    pub unit: UnitSection,
+    pub service: ServiceSection,
+    pub install: InstallSection,
+}
+
+/// The `[Unit]` section
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UnitSection {
+    /// `Description=`
+    pub description: Option<String>,
+
+    /// `After=` entries, eg. `network.target`
+    pub after: Vec<String>,
+}
+
+/// The `[Service]` section
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ServiceSection {
+    /// `Type=`, eg. `simple`
+    pub service_type: Option<String>,
+
+    /// `User=`
+    pub user: Option<String>,
+
+    /// `WorkingDirectory=`
+    pub working_directory: Option<String>,
+
+    /// `ExecStart=` entries. `Type=oneshot` services may run several commands.
+    pub exec_start: Vec<String>,
+
+    /// `ExecReload=`
+    pub exec_reload: Option<String>,
+
+    /// `ExecStartPost=` entries
+    pub exec_start_post: Vec<String>,
+
+    /// `Restart=`, eg. `always`
+    pub restart: Option<String>,
+
+    /// `RestartSec=`
+    pub restart_sec: Option<String>,
+
+    /// `Environment=` entries, each holding one or more `KEY=value` pairs
+    pub environment: Vec<String>,
+
+    /// `MemoryMax=`, eg. `512M`
+    pub memory_max: Option<String>,
+
+    /// `CPUQuota=`, eg. `50%`
+    pub cpu_quota: Option<String>,
+
+    /// `TasksMax=`
+    pub tasks_max: Option<String>,
+
+    /// `KillSignal=`, eg. `SIGINT`
+    pub kill_signal: Option<String>,
+}
+
+/// The `[Install]` section
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InstallSection {
+    /// `WantedBy=` entries, eg. `multi-user.target`
+    pub wanted_by: Vec<String>,
+}
+
+impl From<&UnitFile> for ServiceUnit {
+    fn from(file: &UnitFile) -> Self {
+        let get = |section: &str, key: &str| file.get(section, key).map(str::to_string);
+        let get_list = |section: &str, key: &str| -> Vec<String> {
+            file.get_all(section, key)
+                .iter()
+                .map(|value| value.to_string())
+                .collect()
+        };
+
+        ServiceUnit {
+            unit: UnitSection {
+                description: get("Unit", "Description"),
+                after: get_list("Unit", "After"),
+            },
+            service: ServiceSection {
+                service_type: get("Service", "Type"),
+                user: get("Service", "User"),
+                working_directory: get("Service", "WorkingDirectory"),
+                exec_start: get_list("Service", "ExecStart"),
+                exec_reload: get("Service", "ExecReload"),
+                exec_start_post: get_list("Service", "ExecStartPost"),
+                restart: get("Service", "Restart"),
+                restart_sec: get("Service", "RestartSec"),
+                environment: get_list("Service", "Environment"),
+                memory_max: get("Service", "MemoryMax"),
+                cpu_quota: get("Service", "CPUQuota"),
+                tasks_max: get("Service", "TasksMax"),
+                kill_signal: get("Service", "KillSignal"),
+            },
+            install: InstallSection {
+                wanted_by: get_list("Install", "WantedBy"),
+            },
+        }
+    }
+}
+
+impl ServiceUnit {
+    /// Writes the typed fields into a unit file. Keys set to `None` are removed, other keys and
+    /// comments in the file are left as they are.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The unit file to update
+    ///
+    pub fn write_to(&self, file: &mut UnitFile) {
+        let set =
+            |file: &mut UnitFile, section: &str, key: &str, value: &Option<String>| match value {
+                Some(value) => file.set(section, key, value),
+                None => file.remove(section, key),
+            };
+
+        set(file, "Unit", "Description", &self.unit.description);
+        file.set_all("Unit", "After", &self.unit.after);
+
+        let service = &self.service;
+        set(file, "Service", "Type", &service.service_type);
+        set(file, "Service", "User", &service.user);
+        set(
+            file,
+            "Service",
+            "WorkingDirectory",
+            &service.working_directory,
+        );
+        file.set_all("Service", "ExecStart", &service.exec_start);
+        set(file, "Service", "ExecReload", &service.exec_reload);
+        file.set_all("Service", "ExecStartPost", &service.exec_start_post);
+        set(file, "Service", "Restart", &service.restart);
+        set(file, "Service", "RestartSec", &service.restart_sec);
+        file.set_all("Service", "Environment", &service.environment);
+        set(file, "Service", "MemoryMax", &service.memory_max);
+        set(file, "Service", "CPUQuota", &service.cpu_quota);
+        set(file, "Service", "TasksMax", &service.tasks_max);
+        set(file, "Service", "KillSignal", &service.kill_signal);
+
+        file.set_all("Install", "WantedBy", &self.install.wanted_by);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A oneshot unit with comments, a continuation, an `ExecStart=` reset and repeated keys
+    const ONESHOT: &str = "# Generated with Servicer
+[Unit]
+Description=Backup ; not a comment
+After=network.target
+After=postgresql.service
+
+[Service]
+Type=oneshot
+; reset commands of the vendor unit
+ExecStart=
+ExecStart=/usr/bin/pg_dump \\
+  --file=/var/backups/db.sql \\
+  # a comment inside the continuation
+  shop
+ExecStart=/usr/bin/gzip -f /var/backups/db.sql
+ExecStartPost=/usr/bin/logger backup done
+ExecStartPost=/usr/bin/touch /var/backups/done
+Environment=PGHOST=localhost
+Environment=\"PGOPTIONS=-c statement_timeout=0\"
+
+[Install]
+WantedBy=multi-user.target";
+
+    #[test]
+    fn parse_and_display_keeps_bytes() {
+        let file: UnitFile = ONESHOT.parse().unwrap();
+        assert_eq!(file.to_string(), ONESHOT);
+
+        let with_crlf = ONESHOT.replace('\n', "\r\n");
+        let file: UnitFile = with_crlf.parse().unwrap();
+        assert_eq!(file.to_string(), with_crlf);
+    }
+
+    #[test]
+    fn service_unit_round_trip_keeps_bytes() {
+        let mut file: UnitFile = ONESHOT.parse().unwrap();
+        let unit = ServiceUnit::from(&file);
+
+        assert_eq!(
+            unit.service.exec_start,
+            [
+                "/usr/bin/pg_dump --file=/var/backups/db.sql shop",
+                "/usr/bin/gzip -f /var/backups/db.sql",
+            ]
+        );
+        assert_eq!(unit.service.exec_start_post.len(), 2);
+        assert_eq!(unit.unit.after, ["network.target", "postgresql.service"]);
+
+        unit.write_to(&mut file);
+        assert_eq!(file.to_string(), ONESHOT);
+    }
+
+    #[test]
+    fn continuation_joins_lines() {
+        let file: UnitFile = "[Service]\nExecStart=/bin/echo \\\n  hello \\\n  world\n"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            file.get("Service", "ExecStart"),
+            Some("/bin/echo hello world")
+        );
+    }
+
+    #[test]
+    fn get_all_drops_values_before_a_reset() {
+        let file: UnitFile = "[Service]\nEnvironment=A=1\nEnvironment=\nEnvironment=B=2\n"
+            .parse()
+            .unwrap();
+        assert_eq!(file.get_all("Service", "Environment"), ["B=2"]);
+    }
+
+    #[test]
+    fn set_removes_duplicates_of_single_valued_keys() {
+        let mut file: UnitFile = "[Service]\nUser=a\n# who\nUser=b\n".parse().unwrap();
+        file.set("Service", "User", "c");
+        assert_eq!(file.to_string(), "[Service]\nUser=c\n# who\n");
+    }
+
+    #[test]
+    fn set_all_keeps_order_of_values() {
+        let mut file: UnitFile = "[Service]\nType=oneshot\nExecStart=/bin/b\nRestart=no\n"
+            .parse()
+            .unwrap();
+        let values = ["/bin/a", "/bin/b", "/bin/c"].map(String::from);
+        file.set_all("Service", "ExecStart", &values);
+
+        assert_eq!(
+            file.to_string(),
+            "[Service]\nType=oneshot\nExecStart=/bin/a\nExecStart=/bin/b\nRestart=no\n\
+            ExecStart=/bin/c\n"
+        );
+        assert_eq!(file.get_all("Service", "ExecStart"), values);
+    }
+
+    #[test]
+    fn set_all_replaces_a_reset_list() {
+        let mut file: UnitFile = "[Service]\nExecStart=/bin/a\nExecStart=\nExecStart=/bin/b\n"
+            .parse()
+            .unwrap();
+        file.set_all("Service", "ExecStart", &["/bin/c".to_string()]);
+        assert_eq!(file.to_string(), "[Service]\nExecStart=/bin/c\n");
+    }
+
+    #[test]
+    fn replace_value_keeps_other_entries() {
+        let mut file: UnitFile =
+            "[Service]\nExecStart=/bin/a\nExecStart=\nExecStart=/bin/b x\nExecStart=/bin/c\n"
+                .parse()
+                .unwrap();
+        file.replace_value("Service", "ExecStart", "/bin/b x", "/usr/bin/b x");
+        assert_eq!(
+            file.to_string(),
+            "[Service]\nExecStart=/bin/a\nExecStart=\nExecStart=/usr/bin/b x\nExecStart=/bin/c\n"
+        );
+    }
+
+    #[test]
+    fn add_creates_a_missing_section() {
+        let mut file: UnitFile = "[Service]\nType=simple\n".parse().unwrap();
+        file.add("Install", "WantedBy", "multi-user.target");
+        assert_eq!(
+            file.to_string(),
+            "[Service]\nType=simple\n\n[Install]\nWantedBy=multi-user.target\n"
+        );
+    }
+
+    #[test]
+    fn parse_errors_have_line_numbers() {
+        let error = "[Service]\nnot an entry\n".parse::<UnitFile>().unwrap_err();
+        assert_eq!(error.line_number, 2);
+
+        let error = "Type=simple\n".parse::<UnitFile>().unwrap_err();
+        assert_eq!(error.line_number, 1);
+    }
+
+    #[test]
+    fn split_exec_start_undoes_quote_word() {
+        let program = "/opt/50% off/run";
+        let value = format!("-{} --port 80", quote_word(program));
+
+        assert_eq!(
+            split_exec_start(&value),
+            Some(("-", program.to_string(), " --port 80"))
+        );
+        assert_eq!(
+            split_exec_start("/usr/bin/node"),
+            Some(("", "/usr/bin/node".to_string(), ""))
+        );
+        assert_eq!(split_exec_start(""), None);
+    }
+
+    #[test]
+    fn quote_word_escapes_specifiers_and_spaces() {
+        assert_eq!(quote_word("/usr/bin/node"), "/usr/bin/node");
+        assert_eq!(quote_word("50%"), "50%%");
+        assert_eq!(quote_word("hello world"), "\"hello world\"");
+        assert_eq!(quote_word("say \"hi\""), "\"say \\\"hi\\\"\"");
+        assert_eq!(quote_word(""), "\"\"");
+    }
+}
@@ -0,0 +1,717 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use super::{
+    scope::{get_unit_directory, is_user, user_exists},
+    unit_file::{split_exec_start, UnitFile},
+};
+
+/// Directories systemd loads units from, searched for `WantedBy=` targets
+const UNIT_DIRECTORIES: [&str; 6] = [
+    "/etc/systemd/system",
+    "/run/systemd/system",
+    "/usr/local/lib/systemd/system",
+    "/usr/lib/systemd/system",
+    "/lib/systemd/system",
+    "/run/systemd/generator",
+];
+
+/// Directories the user manager loads units from besides `~/.config/systemd/user`
+const USER_UNIT_DIRECTORIES: [&str; 5] = [
+    "/etc/systemd/user",
+    "/run/systemd/user",
+    "/usr/local/lib/systemd/user",
+    "/usr/lib/systemd/user",
+    "/lib/systemd/user",
+];
+
+/// Keys of `[Unit]`
+#[rustfmt::skip]
+const UNIT_KEYS: &[&str] = &[
+    "Description", "Documentation", "Wants", "Requires", "Requisite", "BindsTo", "PartOf",
+    "Upholds", "Conflicts", "Before", "After", "OnFailure", "OnSuccess", "PropagatesReloadTo",
+    "ReloadPropagatedFrom", "PropagatesStopTo", "StopPropagatedFrom", "JoinsNamespaceOf",
+    "RequiresMountsFor", "WantsMountsFor", "OnFailureJobMode", "OnSuccessJobMode",
+    "IgnoreOnIsolate", "StopWhenUnneeded", "RefuseManualStart", "RefuseManualStop",
+    "AllowIsolate", "DefaultDependencies", "SurviveFinalKillSignal", "CollectMode",
+    "FailureAction", "SuccessAction", "FailureActionExitStatus", "SuccessActionExitStatus",
+    "JobTimeoutSec", "JobRunningTimeoutSec", "JobTimeoutAction", "JobTimeoutRebootArgument",
+    "StartLimitIntervalSec", "StartLimitBurst", "StartLimitAction", "RebootArgument",
+    "SourcePath",
+];
+
+/// Keys of `[Install]`
+#[rustfmt::skip]
+const INSTALL_KEYS: &[&str] = &[
+    "Alias", "WantedBy", "RequiredBy", "UpheldBy", "Also", "DefaultInstance",
+];
+
+/// Keys of `[Service]`, including execution, kill and resource control settings
+#[rustfmt::skip]
+const SERVICE_KEYS: &[&str] = &[
+    // Service
+    "Type", "ExitType", "RemainAfterExit", "GuessMainPID", "PIDFile", "BusName", "ExecStart",
+    "ExecStartPre", "ExecStartPost", "ExecCondition", "ExecReload", "ExecStop", "ExecStopPost",
+    "RestartSec", "RestartSteps", "RestartMaxDelaySec", "TimeoutStartSec", "TimeoutStopSec",
+    "TimeoutAbortSec", "TimeoutSec", "TimeoutStartFailureMode", "TimeoutStopFailureMode",
+    "RuntimeMaxSec", "RuntimeRandomizedExtraSec", "WatchdogSec", "Restart", "RestartMode",
+    "SuccessExitStatus", "RestartPreventExitStatus", "RestartForceExitStatus",
+    "RootDirectoryStartOnly", "NonBlocking", "NotifyAccess", "Sockets", "FileDescriptorStoreMax",
+    "FileDescriptorStorePreserve", "USBFunctionDescriptors", "USBFunctionStrings", "OOMPolicy",
+    "OpenFile", "ReloadSignal", "PermissionsStartOnly", "StartLimitInterval", "StartLimitBurst",
+    "StartLimitAction", "FailureAction", "SuccessAction", "RebootArgument",
+    // Execution environment
+    "WorkingDirectory", "RootDirectory", "RootImage", "RootImageOptions", "RootEphemeral",
+    "RootHash", "RootHashSignature", "RootVerity", "MountAPIVFS", "ProtectProc", "ProcSubset",
+    "BindPaths", "BindReadOnlyPaths", "MountImages", "ExtensionImages", "ExtensionDirectories",
+    "User", "Group", "DynamicUser", "SupplementaryGroups", "SetLoginEnvironment", "PAMName",
+    "CapabilityBoundingSet", "AmbientCapabilities", "NoNewPrivileges", "SecureBits",
+    "SELinuxContext", "AppArmorProfile", "SmackProcessLabel", "UMask", "CoredumpFilter",
+    "KeyringMode", "OOMScoreAdjust", "TimerSlackNSec", "Personality", "IgnoreSIGPIPE", "Nice",
+    "CPUSchedulingPolicy", "CPUSchedulingPriority", "CPUSchedulingResetOnFork", "CPUAffinity",
+    "NUMAPolicy", "NUMAMask", "IOSchedulingClass", "IOSchedulingPriority", "ProtectSystem",
+    "ProtectHome", "RuntimeDirectory", "StateDirectory", "CacheDirectory", "LogsDirectory",
+    "ConfigurationDirectory", "RuntimeDirectoryMode", "StateDirectoryMode", "CacheDirectoryMode",
+    "LogsDirectoryMode", "ConfigurationDirectoryMode", "RuntimeDirectoryPreserve",
+    "TimeoutCleanSec", "ReadWritePaths", "ReadOnlyPaths", "InaccessiblePaths", "ExecPaths",
+    "NoExecPaths", "TemporaryFileSystem", "PrivateTmp", "PrivateDevices", "PrivateNetwork",
+    "NetworkNamespacePath", "PrivateIPC", "IPCNamespacePath", "MemoryKSM", "PrivateUsers",
+    "ProtectHostname", "ProtectClock", "ProtectKernelTunables", "ProtectKernelModules",
+    "ProtectKernelLogs", "ProtectControlGroups", "RestrictAddressFamilies", "RestrictFileSystems",
+    "RestrictNamespaces", "LockPersonality", "MemoryDenyWriteExecute", "RestrictRealtime",
+    "RestrictSUIDSGID", "RemoveIPC", "PrivateMounts", "MountFlags", "SystemCallFilter",
+    "SystemCallErrorNumber", "SystemCallArchitectures", "SystemCallLog", "Environment",
+    "EnvironmentFile", "PassEnvironment", "UnsetEnvironment", "StandardInput", "StandardOutput",
+    "StandardError", "StandardInputText", "StandardInputData", "LogLevelMax", "LogExtraFields",
+    "LogRateLimitIntervalSec", "LogRateLimitBurst", "LogFilterPatterns", "LogNamespace",
+    "SyslogIdentifier", "SyslogFacility", "SyslogLevel", "SyslogLevelPrefix", "TTYPath",
+    "TTYReset", "TTYVHangup", "TTYRows", "TTYColumns", "TTYVTDisallocate", "LoadCredential",
+    "LoadCredentialEncrypted", "ImportCredential", "SetCredential", "SetCredentialEncrypted",
+    "UtmpIdentifier", "UtmpMode",
+    // Kill
+    "KillMode", "KillSignal", "RestartKillSignal", "SendSIGHUP", "SendSIGKILL",
+    "FinalKillSignal", "WatchdogSignal",
+    // Resource control
+    "CPUAccounting", "CPUWeight", "StartupCPUWeight", "CPUQuota", "CPUQuotaPeriodSec",
+    "AllowedCPUs", "StartupAllowedCPUs", "AllowedMemoryNodes", "StartupAllowedMemoryNodes",
+    "MemoryAccounting", "MemoryMin", "MemoryLow", "StartupMemoryLow", "DefaultStartupMemoryLow",
+    "MemoryHigh", "StartupMemoryHigh", "MemoryMax", "StartupMemoryMax", "MemorySwapMax",
+    "StartupMemorySwapMax", "MemoryZSwapMax", "StartupMemoryZSwapMax", "MemoryZSwapWriteback",
+    "TasksAccounting", "TasksMax", "IOAccounting", "IOWeight", "StartupIOWeight",
+    "IODeviceWeight", "IOReadBandwidthMax", "IOWriteBandwidthMax", "IOReadIOPSMax",
+    "IOWriteIOPSMax", "IODeviceLatencyTargetSec", "IPAccounting", "IPAddressAllow",
+    "IPAddressDeny", "SocketBindAllow", "SocketBindDeny", "RestrictNetworkInterfaces", "NFTSet",
+    "IPIngressFilterPath", "IPEgressFilterPath", "BPFProgram", "DeviceAllow", "DevicePolicy",
+    "Slice", "Delegate", "DelegateSubgroup", "DisableControllers", "ManagedOOMSwap",
+    "ManagedOOMMemoryPressure", "ManagedOOMMemoryPressureLimit",
+    "ManagedOOMMemoryPressureDurationSec", "ManagedOOMPreference", "MemoryPressureWatch",
+    "MemoryPressureThresholdSec", "CoredumpReceive", "CPUShares", "StartupCPUShares",
+    "MemoryLimit", "BlockIOAccounting", "BlockIOWeight", "StartupBlockIOWeight",
+    "BlockIODeviceWeight", "BlockIOReadBandwidth", "BlockIOWriteBandwidth",
+];
+
+/// Key prefixes valid in every section of their kind, eg. `LimitNOFILE` or `ConditionPathExists`
+const UNIT_KEY_PREFIXES: [&str; 2] = ["Condition", "Assert"];
+const SERVICE_KEY_PREFIXES: [&str; 1] = ["Limit"];
+
+/// Keys which may be repeated to build a list
+#[rustfmt::skip]
+const LIST_KEYS: &[&str] = &[
+    "Documentation", "Wants", "Requires", "Requisite", "BindsTo", "PartOf", "Upholds",
+    "Conflicts", "Before", "After", "OnFailure", "OnSuccess", "PropagatesReloadTo",
+    "ReloadPropagatedFrom", "PropagatesStopTo", "StopPropagatedFrom", "JoinsNamespaceOf",
+    "RequiresMountsFor", "WantsMountsFor", "Alias", "WantedBy", "RequiredBy", "UpheldBy", "Also",
+    "ExecStartPre", "ExecStartPost", "ExecCondition", "ExecReload", "ExecStop", "ExecStopPost",
+    "SuccessExitStatus", "RestartPreventExitStatus", "RestartForceExitStatus", "Sockets",
+    "OpenFile", "BindPaths", "BindReadOnlyPaths", "MountImages", "ExtensionImages",
+    "ExtensionDirectories", "SupplementaryGroups", "CapabilityBoundingSet", "AmbientCapabilities",
+    "RuntimeDirectory", "StateDirectory", "CacheDirectory", "LogsDirectory",
+    "ConfigurationDirectory", "ReadWritePaths", "ReadOnlyPaths", "InaccessiblePaths",
+    "ExecPaths", "NoExecPaths", "TemporaryFileSystem", "RestrictAddressFamilies",
+    "RestrictFileSystems", "RestrictNamespaces", "SystemCallFilter", "SystemCallArchitectures",
+    "SystemCallLog", "Environment", "EnvironmentFile", "PassEnvironment", "UnsetEnvironment",
+    "StandardInputText", "StandardInputData", "LogExtraFields", "LogFilterPatterns",
+    "LoadCredential", "LoadCredentialEncrypted", "ImportCredential", "SetCredential",
+    "SetCredentialEncrypted", "IODeviceWeight", "IOReadBandwidthMax", "IOWriteBandwidthMax",
+    "IOReadIOPSMax", "IOWriteIOPSMax", "IODeviceLatencyTargetSec", "IPAddressAllow",
+    "IPAddressDeny", "SocketBindAllow", "SocketBindDeny", "RestrictNetworkInterfaces", "NFTSet",
+    "IPIngressFilterPath", "IPEgressFilterPath", "BPFProgram", "DeviceAllow",
+    "DisableControllers", "BlockIODeviceWeight", "BlockIOReadBandwidth", "BlockIOWriteBandwidth",
+];
+
+const SERVICE_TYPES: [&str; 8] = [
+    "simple",
+    "exec",
+    "forking",
+    "oneshot",
+    "dbus",
+    "notify",
+    "notify-reload",
+    "idle",
+];
+
+const RESTART_VALUES: [&str; 7] = [
+    "no",
+    "on-success",
+    "on-failure",
+    "on-abnormal",
+    "on-watchdog",
+    "on-abort",
+    "always",
+];
+
+/// How serious a diagnostic is. Errors stop systemd from loading or starting the service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A problem found in a unit file
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    /// Path of the unit file
+    pub path: String,
+
+    /// 1-based line number
+    pub line: usize,
+
+    pub severity: Severity,
+
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {}",
+            self.path, self.line, self.severity, self.message
+        )
+    }
+}
+
+/// Checks a .service file for mistakes systemd would reject or silently ignore. Diagnostics are
+/// sorted by line.
+///
+/// # Arguments
+///
+/// * `path` - Path shown in diagnostics
+/// * `contents` - Contents of the file
+///
+pub fn lint_unit_file(path: &str, contents: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let mut report = |line: usize, severity: Severity, message: String| {
+        diagnostics.push(Diagnostic {
+            path: path.to_string(),
+            line,
+            severity,
+            message,
+        })
+    };
+
+    let unit_file = match contents.parse::<UnitFile>() {
+        Ok(unit_file) => unit_file,
+        Err(error) => {
+            report(error.line_number, Severity::Error, error.message);
+            return diagnostics;
+        }
+    };
+
+    check_keys(&unit_file, &mut report);
+    check_service(&unit_file, &mut report);
+    check_install(&unit_file, &mut report);
+    check_placeholder_lines(contents, &mut report);
+
+    diagnostics.sort_by_key(|diagnostic| diagnostic.line);
+    diagnostics
+}
+
+/// Unknown sections and keys, and single-valued keys set more than once
+///
+/// # Arguments
+///
+/// * `unit_file`
+/// * `report` - Receives the line, severity and message of each diagnostic
+///
+fn check_keys(unit_file: &UnitFile, report: &mut impl FnMut(usize, Severity, String)) {
+    for section in unit_file.sections() {
+        let (keys, prefixes): (&[&str], &[&str]) = match section.name.as_str() {
+            "Unit" => (UNIT_KEYS, &UNIT_KEY_PREFIXES),
+            "Service" => (SERVICE_KEYS, &SERVICE_KEY_PREFIXES),
+            "Install" => (INSTALL_KEYS, &[]),
+            // Lines before the first section are comments
+            "" => continue,
+            name if name.starts_with("X-") => continue,
+            name => {
+                report(
+                    section.line_number.unwrap_or(1),
+                    Severity::Warning,
+                    format!("unknown section [{name}], systemd ignores it"),
+                );
+                continue;
+            }
+        };
+
+        let mut seen: Vec<(&str, usize)> = vec![];
+
+        for entry in section.entries() {
+            let key = entry.key.as_str();
+            let line = entry.line_number.unwrap_or(1);
+
+            if key.starts_with("X-") {
+                continue;
+            }
+
+            if !keys.contains(&key) && !prefixes.iter().any(|prefix| key.starts_with(prefix)) {
+                let suggestion = keys
+                    .iter()
+                    .map(|known| (known, get_edit_distance(known, key)))
+                    .filter(|(_, distance)| *distance <= 2)
+                    .min_by_key(|(_, distance)| *distance)
+                    .map(|(known, _)| format!(", did you mean {known}=?"))
+                    .unwrap_or_default();
+
+                report(
+                    line,
+                    Severity::Warning,
+                    format!(
+                        "unknown key {key}= in [{}], systemd ignores it{suggestion}",
+                        section.name
+                    ),
+                );
+                continue;
+            }
+
+            // An empty value resets list keys, and ExecStart is checked with Type=
+            if LIST_KEYS.contains(&key)
+                || key == "ExecStart"
+                || prefixes.iter().any(|prefix| key.starts_with(prefix))
+            {
+                continue;
+            }
+
+            match seen.iter().find(|(seen_key, _)| *seen_key == key) {
+                Some((_, first_line)) => report(
+                    line,
+                    Severity::Warning,
+                    format!("{key}= is already set on line {first_line}, only this value is used"),
+                ),
+                None => seen.push((key, line)),
+            }
+        }
+    }
+}
+
+/// `Type=`, `Restart=`, `ExecStart=`, `WorkingDirectory=` and `User=` of the `[Service]` section
+///
+/// # Arguments
+///
+/// * `unit_file`
+/// * `report` - Receives the line, severity and message of each diagnostic
+///
+fn check_service(unit_file: &UnitFile, report: &mut impl FnMut(usize, Severity, String)) {
+    let entries: Vec<_> = unit_file
+        .sections()
+        .iter()
+        .filter(|section| section.name == "Service")
+        .flat_map(|section| section.entries())
+        .collect();
+    let last = |key: &str| entries.iter().rev().find(|entry| entry.key == key);
+
+    let service_type = last("Type");
+    let type_name = service_type.map_or("simple", |entry| entry.value.as_str());
+
+    if let Some(entry) = service_type {
+        if !SERVICE_TYPES.contains(&type_name) {
+            report(
+                entry.line_number.unwrap_or(1),
+                Severity::Error,
+                format!(
+                    "invalid Type={type_name}, expected one of {}",
+                    SERVICE_TYPES.join(", ")
+                ),
+            );
+        }
+    }
+
+    if let Some(restart) = last("Restart") {
+        let line = restart.line_number.unwrap_or(1);
+        let value = restart.value.as_str();
+
+        if !RESTART_VALUES.contains(&value) {
+            report(
+                line,
+                Severity::Error,
+                format!(
+                    "invalid Restart={value}, expected one of {}",
+                    RESTART_VALUES.join(", ")
+                ),
+            );
+        } else if type_name == "oneshot" && (value == "always" || value == "on-success") {
+            report(
+                line,
+                Severity::Error,
+                format!("Restart={value} is not allowed for Type=oneshot, use on-failure"),
+            );
+        } else if value == "always" && last("RestartSec").is_none() {
+            report(
+                line,
+                Severity::Warning,
+                "Restart=always without RestartSec= restarts after 100ms and quickly hits the \
+                start limit, eg. add RestartSec=1s"
+                    .to_string(),
+            );
+        }
+    }
+
+    let exec_starts: Vec<_> = entries
+        .iter()
+        .filter(|entry| entry.key == "ExecStart" && !entry.value.is_empty())
+        .collect();
+
+    if exec_starts.is_empty() && type_name != "oneshot" {
+        report(
+            entries
+                .first()
+                .and_then(|entry| entry.line_number)
+                .unwrap_or(1),
+            Severity::Error,
+            "[Service] has no ExecStart=".to_string(),
+        );
+    }
+
+    for (index, entry) in exec_starts.iter().enumerate() {
+        let line = entry.line_number.unwrap_or(1);
+
+        if index > 0 && type_name != "oneshot" {
+            report(
+                line,
+                Severity::Error,
+                format!("more than one ExecStart= is only allowed for Type=oneshot, not Type={type_name}"),
+            );
+        }
+
+        let program = split_exec_start(&entry.value)
+            .map(|(_, program, _)| program)
+            .unwrap_or_default();
+
+        if !program.starts_with('/') && !program.contains('%') {
+            report(
+                line,
+                Severity::Warning,
+                format!(
+                    "ExecStart program {program} is not an absolute path. systemd only searches \
+                    its own PATH, which lacks eg. nvm and pyenv directories"
+                ),
+            );
+        }
+    }
+
+    if let Some(entry) = last("WorkingDirectory") {
+        let directory = entry.value.as_str();
+        let line = entry.line_number.unwrap_or(1);
+
+        // `-` makes the directory optional, `~` is the home directory of User=
+        let checked = !directory.starts_with('-') && directory != "~" && !directory.contains('%');
+
+        if checked && !directory.starts_with('/') {
+            report(
+                line,
+                Severity::Error,
+                format!("WorkingDirectory={directory} must be an absolute path"),
+            );
+        } else if checked && !Path::new(directory).is_dir() {
+            report(
+                line,
+                Severity::Error,
+                format!("WorkingDirectory {directory} doesn't exist"),
+            );
+        }
+    }
+
+    if let Some(entry) = last("User") {
+        let user = entry.value.as_str();
+
+        if is_user() {
+            report(
+                entry.line_number.unwrap_or(1),
+                Severity::Error,
+                "User= can't be used in user services, they run as your user".to_string(),
+            );
+        } else if !user.is_empty()
+            && !user.contains('%')
+            && user.parse::<u32>().is_err()
+            && !user_exists(user)
+        {
+            report(
+                entry.line_number.unwrap_or(1),
+                Severity::Error,
+                format!("User {user} doesn't exist"),
+            );
+        }
+    }
+}
+
+/// `WantedBy=` targets of the `[Install]` section
+///
+/// # Arguments
+///
+/// * `unit_file`
+/// * `report` - Receives the line, severity and message of each diagnostic
+///
+fn check_install(unit_file: &UnitFile, report: &mut impl FnMut(usize, Severity, String)) {
+    for entry in unit_file
+        .sections()
+        .iter()
+        .filter(|section| section.name == "Install")
+        .flat_map(|section| section.entries())
+        .filter(|entry| entry.key == "WantedBy")
+    {
+        for target in entry.value.split_whitespace() {
+            if target.contains('%') {
+                continue;
+            }
+
+            let found = if is_user() {
+                USER_UNIT_DIRECTORIES
+                    .iter()
+                    .map(PathBuf::from)
+                    .chain([get_unit_directory()])
+                    .any(|directory| directory.join(target).exists())
+            } else {
+                UNIT_DIRECTORIES
+                    .iter()
+                    .any(|directory| Path::new(directory).join(target).exists())
+            };
+
+            if !found {
+                report(
+                    entry.line_number.unwrap_or(1),
+                    Severity::Warning,
+                    format!("WantedBy target {target} doesn't exist, enabling won't start the service on boot"),
+                );
+            }
+        }
+    }
+}
+
+/// Blank lines after `ExecStart=` left by the `{restart_policy}` and `{env_vars_formatted}`
+/// placeholders of older servicer versions. `Restart=always` may stand between them when
+/// auto-restart was on. One blank line before the next section is kept.
+///
+/// # Arguments
+///
+/// * `contents` - Contents of the file
+/// * `report` - Receives the line, severity and message of each diagnostic
+///
+fn check_placeholder_lines(contents: &str, report: &mut impl FnMut(usize, Severity, String)) {
+    let lines: Vec<&str> = contents.lines().map(str::trim).collect();
+    let mut section = "";
+
+    for (index, line) in lines.iter().enumerate() {
+        if line.starts_with('[') {
+            section = line;
+            continue;
+        }
+
+        if section != "[Service]" || !line.starts_with("ExecStart=") {
+            continue;
+        }
+
+        // Index of the first line after the filled `{restart_policy}` placeholder, if any
+        let first = match lines.get(index + 1) {
+            Some(&"Restart=always") => index + 2,
+            _ => index + 1,
+        };
+
+        let blank_lines = lines[first.min(lines.len())..]
+            .iter()
+            .take_while(|line| line.is_empty())
+            .count();
+        let next = lines.get(first + blank_lines);
+        let placeholders = match next {
+            Some(next) if !next.starts_with('[') => blank_lines,
+            _ => blank_lines.saturating_sub(1),
+        };
+
+        for offset in 0..placeholders {
+            report(
+                first + offset + 1,
+                Severity::Warning,
+                "empty line left by a servicer template placeholder".to_string(),
+            );
+        }
+    }
+}
+
+/// Number of single character insertions, deletions or substitutions between two keys, ignoring
+/// case. Used to suggest the intended key for a typo.
+///
+/// # Arguments
+///
+/// * `a`
+/// * `b`
+///
+fn get_edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_ascii_lowercase().chars().collect();
+    let b: Vec<char> = b.to_ascii_lowercase().chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::handle_create_service::{new_service_unit, render_service_unit};
+
+    const PLACEHOLDER: &str = "empty line left by a servicer template placeholder";
+
+    /// A file of the old `formatdoc!` template, with the lines the placeholders were filled with
+    fn old_template(restart_policy: &str, env_vars: &str) -> String {
+        format!(
+            "# Generated with Servicer\n\
+            [Unit]\n\
+            After=network.target\n\
+            \n\
+            [Service]\n\
+            Type=simple\n\
+            User=root\n\
+            \n\
+            WorkingDirectory=/\n\
+            ExecStart=/bin/true\n\
+            {restart_policy}\n\
+            {env_vars}\n\
+            \n\
+            [Install]\n\
+            WantedBy=multi-user.target\n"
+        )
+    }
+
+    fn lines_with(diagnostics: &[Diagnostic], message: &str) -> Vec<usize> {
+        diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.message.contains(message))
+            .map(|diagnostic| diagnostic.line)
+            .collect()
+    }
+
+    #[test]
+    fn reports_placeholder_lines_without_restart() {
+        let diagnostics = lint_unit_file("old.service", &old_template("", ""));
+        assert_eq!(lines_with(&diagnostics, PLACEHOLDER), [11, 12]);
+
+        let diagnostics = lint_unit_file("old.service", &old_template("", "Environment=A=1"));
+        assert_eq!(lines_with(&diagnostics, PLACEHOLDER), [11]);
+    }
+
+    #[test]
+    fn reports_placeholder_lines_after_restart() {
+        let diagnostics = lint_unit_file("old.service", &old_template("Restart=always", ""));
+        assert_eq!(lines_with(&diagnostics, PLACEHOLDER), [12]);
+
+        let diagnostics = lint_unit_file(
+            "old.service",
+            &old_template("Restart=always", "Environment=A=1"),
+        );
+        assert!(lines_with(&diagnostics, PLACEHOLDER).is_empty());
+    }
+
+    #[test]
+    fn accepts_new_template() {
+        for auto_restart in [false, true] {
+            let unit = new_service_unit(
+                None,
+                "/",
+                "/bin/true".to_string(),
+                auto_restart,
+                vec!["A=1".to_string()],
+            );
+            let diagnostics = lint_unit_file("new.service", &render_service_unit(&unit));
+
+            assert!(lines_with(&diagnostics, PLACEHOLDER).is_empty());
+            assert!(lines_with(&diagnostics, "RestartSec").is_empty());
+        }
+    }
+
+    #[test]
+    fn warns_about_restart_always_without_restart_sec() {
+        let contents = "[Service]\nExecStart=/bin/true\nRestart=always\n";
+        let diagnostics = lint_unit_file("a.service", contents);
+        assert_eq!(lines_with(&diagnostics, "without RestartSec="), [3]);
+
+        let contents = "[Service]\nExecStart=/bin/true\nRestart=always\nRestartSec=1s\n";
+        let diagnostics = lint_unit_file("a.service", contents);
+        assert!(lines_with(&diagnostics, "RestartSec").is_empty());
+    }
+
+    #[test]
+    fn reports_service_errors() {
+        let contents = "[Service]\nType=simple\nExecStart=/bin/true\nExecStart=/bin/false\n\
+            Restart=sometimes\nWorkingDirectory=relative\n";
+        let diagnostics = lint_unit_file("a.service", contents);
+
+        assert_eq!(lines_with(&diagnostics, "more than one ExecStart="), [4]);
+        assert_eq!(lines_with(&diagnostics, "invalid Restart=sometimes"), [5]);
+        assert_eq!(lines_with(&diagnostics, "must be an absolute path"), [6]);
+
+        let diagnostics = lint_unit_file("a.service", "[Service]\nType=oneshot\nRestart=always\n");
+        assert_eq!(
+            lines_with(&diagnostics, "not allowed for Type=oneshot"),
+            [3]
+        );
+        assert!(lines_with(&diagnostics, "has no ExecStart=").is_empty());
+    }
+
+    #[test]
+    fn warns_about_relative_programs() {
+        let contents = "[Service]\nExecStart=node index.js\nExecStart=-/bin/true\n\
+            ExecStart=-\"/opt/my app/run\" --port 80\nExecStart=\"./my app\"\n";
+        let diagnostics = lint_unit_file("a.service", contents);
+
+        assert_eq!(lines_with(&diagnostics, "not an absolute path"), [2, 5]);
+    }
+
+    #[test]
+    fn reports_parse_errors() {
+        let diagnostics = lint_unit_file("a.service", "[Service]\nnot a key value line\n");
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, 2);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn measures_edit_distance() {
+        assert_eq!(get_edit_distance("ExecStart", "ExecStart"), 0);
+        assert_eq!(get_edit_distance("ExecStrat", "ExecStart"), 2);
+        assert_eq!(get_edit_distance("execstart", "ExecStart"), 0);
+        assert_eq!(get_edit_distance("WorkingDir", "WorkingDirectory"), 6);
+    }
+}